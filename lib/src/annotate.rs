@@ -0,0 +1,151 @@
+use super::ASIAir;
+use super::DeviceError;
+use super::Timestamped;
+use super::solve::SolverJob;
+use serde::Deserialize;
+use std::fmt;
+use tokio::time::Duration;
+
+// Longest time to wait for the device to report the end of an annotation
+const ANNOTATE_TIMEOUT: Duration = Duration::from_secs(60);
+
+const ANNOTATE_JOB: SolverJob = SolverJob {
+    start: "start_annotate",
+    stop: "stop_annotate",
+    event: "Annotate",
+    tag: "Annotate",
+    timeout: ANNOTATE_TIMEOUT,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ObjectType {
+    Galaxy,
+    Nebula,
+    OpenCluster,
+    GlobularCluster,
+    PlanetaryNebula,
+    SupernovaRemnant,
+    #[serde(other)]
+    Other,
+}
+
+/// Catalog object found in an image
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct AnnotateObject {
+    pub name: String,
+    pub r#type: ObjectType,
+    // Position of the object center in pixels from the top left corner of the image,
+    // it may be outside of the image for large objects
+    pub x: f64,
+    pub y: f64,
+    // Radius of the object in pixels
+    pub radius: f64,
+}
+
+/// Plate solving solution of an image, with the catalog objects it contains
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct AnnotateResult {
+    // Right ascension of the image center in hours
+    pub ra: f64,
+    // Declination of the image center in degrees
+    pub dec: f64,
+    // Rotation of the image in degrees, east of north
+    pub angle: f64,
+    // Pixel scale in arcseconds per pixel
+    pub scale: f64,
+    pub objects: Vec<AnnotateObject>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AnnotateError {
+    // Not enough stars detected in the image to solve it
+    NoStars,
+    // The annotation didn't finish in time
+    Timeout,
+    // The annotation was stopped before the end
+    Cancelled,
+    Failed(String),
+}
+
+impl fmt::Display for AnnotateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AnnotateError::NoStars => write!(f, "annotation failed: no stars"),
+            AnnotateError::Timeout => write!(f, "annotation failed: timeout"),
+            AnnotateError::Cancelled => write!(f, "annotation cancelled"),
+            AnnotateError::Failed(reason) => write!(f, "annotation failed: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for AnnotateError {}
+
+impl AnnotateError {
+    pub(crate) fn from_reason(reason: &str) -> Self {
+        match reason {
+            "no stars" => AnnotateError::NoStars,
+            "timeout" => AnnotateError::Timeout,
+            "cancel" => AnnotateError::Cancelled,
+            _ => AnnotateError::Failed(reason.to_string()),
+        }
+    }
+}
+
+impl ASIAir {
+    /// Annotate the last image of the main camera, waiting for the result.
+    /// Failures are reported as an `AnnotateError`
+    pub async fn annotate_current_image(
+        &mut self,
+    ) -> Result<AnnotateResult, Box<dyn std::error::Error + Send + Sync>> {
+        if !self.run_solver_job(&ANNOTATE_JOB).await? {
+            return Err(Box::new(AnnotateError::Timeout));
+        }
+
+        self.get_annotate_result().await
+    }
+
+    /// Start annotating the last image, the progress is reported by `AnnotateEvent` events
+    pub async fn start_annotate(
+        &mut self,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let method = "start_annotate";
+        self.rpc_request_4700(method, None).await?;
+        Ok(())
+    }
+
+    pub async fn stop_annotate(
+        &mut self,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let method = "stop_annotate";
+        self.rpc_request_4700(method, None).await?;
+        Ok(())
+    }
+
+    /// Result of the last annotation
+    pub async fn get_annotate_result(
+        &mut self,
+    ) -> Result<AnnotateResult, Box<dyn std::error::Error + Send + Sync>> {
+        let method = "get_annotate_result";
+        let result = self
+            .rpc_request_4700(method, None)
+            .await
+            .map_err(|e| DeviceError::map_reason(e, AnnotateError::from_reason))?;
+
+        let annotation: AnnotateResult = serde_json::from_value(result)?;
+        Ok(annotation)
+    }
+
+    /// `get_annotate_result` with the Timestamp of the response
+    pub async fn get_annotate_result_timestamped(
+        &mut self,
+    ) -> Result<Timestamped<AnnotateResult>, Box<dyn std::error::Error + Send + Sync>> {
+        let method = "get_annotate_result";
+        let response = self
+            .rpc_request_4700_timestamped(method, None)
+            .await
+            .map_err(|e| DeviceError::map_reason(e, AnnotateError::from_reason))?;
+
+        Ok(response.deserialize()?)
+    }
+}
@@ -0,0 +1,158 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+
+use super::ASIAir;
+use super::Timestamped;
+use super::ASIAirPage;
+
+/// Settings of the ASIAir app stored on the device. Fields unknown to this client, sent by newer
+/// firmwares, are kept in `other` and sent back untouched
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppSetting {
+    // Exposure of the frames taken to center a target after a goto, in microseconds
+    pub autogoto_exp_us: u64,
+    // Version and checksum of the comet catalog
+    pub comets_version: u32,
+    pub comets_md5: String,
+    pub continuous_preview: bool,
+    pub goto_auto: bool,
+    pub flat_auto_exp: bool,
+    // Exposure and binning of each frame type, the custom exposure is used when the flag is set
+    pub light_custom_exp: bool,
+    pub flat_custom_exp: bool,
+    pub dark_custom_exp: bool,
+    pub bias_custom_exp: bool,
+    pub light_exposure: u32,
+    pub flat_exposure: u32,
+    pub dark_exposure: u32,
+    pub bias_exposure: u32,
+    pub light_bin: u32,
+    pub flat_bin: u32,
+    pub dark_bin: u32,
+    pub bias_bin: u32,
+    pub main_camera_name: String,
+    pub guide_camera_name: String,
+    pub guide_rate: f64,
+    // Target of the last goto, right ascension in hours and declination in degrees
+    pub goto_target_name: String,
+    pub goto_target_ra: f64,
+    pub goto_target_dec: f64,
+    // Focal length of the main telescope in millimeters
+    pub focal_length: f64,
+    pub auto_merid_flip: bool,
+    pub merid_flip_minutes: f64,
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+impl AppSetting {
+    /// Settings of `updated` that differ from these ones
+    pub fn changes(&self, updated: &AppSetting) -> Map<String, Value> {
+        let current = serde_json::to_value(self).unwrap_or_default();
+        let updated = serde_json::to_value(updated).unwrap_or_default();
+        let mut changes = Map::new();
+        if let Value::Object(updated) = updated {
+            for (key, value) in updated {
+                if current.get(&key) != Some(&value) {
+                    changes.insert(key, value);
+                }
+            }
+        }
+        changes
+    }
+}
+
+/// State of a task of the device. Details specific to each task, like the progress of a stack
+/// or the result of an auto focus, are kept in `details`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TaskState {
+    pub is_working: bool,
+    #[serde(flatten)]
+    pub details: Map<String, Value>,
+}
+
+/// State of the ASIAir app on the device
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppState {
+    pub page: ASIAirPage,
+    pub annotate: TaskState,
+    pub solve: TaskState,
+    pub capture: TaskState,
+    pub pa: TaskState,
+    pub auto_goto: TaskState,
+    pub stack: TaskState,
+    pub export_image: TaskState,
+    pub merid_flip: TaskState,
+    pub auto_focus: TaskState,
+    pub find_star: TaskState,
+    pub avi_record: TaskState,
+    pub rtmp: TaskState,
+    pub auto_exp: TaskState,
+    pub restart_guide: TaskState,
+    pub batch_stack: TaskState,
+    pub demonstrate: TaskState,
+    pub format_drive: TaskState,
+    pub plan: TaskState,
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+impl AppState {
+    /// Names of the tasks in progress, including the ones unknown to this client
+    pub fn working(&self) -> Vec<String> {
+        let Ok(Value::Object(tasks)) = serde_json::to_value(self) else {
+            return Vec::new();
+        };
+        tasks
+            .into_iter()
+            .filter(|(_, task)| task.get("is_working").and_then(|working| working.as_bool()) == Some(true))
+            .map(|(name, _)| name)
+            .collect()
+    }
+}
+
+impl ASIAir {
+    pub async fn get_app_state(&self) -> Result<AppState, Box<dyn std::error::Error + Send + Sync>> {
+        let method = "get_app_state";
+        let result = self.rpc_request_4700(method, None).await?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    /// `get_app_state` with the Timestamp of the response
+    pub async fn get_app_state_timestamped(
+        &self,
+    ) -> Result<Timestamped<AppState>, Box<dyn std::error::Error + Send + Sync>> {
+        let method = "get_app_state";
+        let response = self.rpc_request_4700_timestamped(method, None).await?;
+
+        Ok(response.deserialize()?)
+    }
+
+    pub async fn get_app_setting(&self) -> Result<AppSetting, Box<dyn std::error::Error + Send + Sync>> {
+        let method = "get_app_setting";
+        let result = self.rpc_request_4700(method, None).await?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    /// Change the app settings with `update`, only the settings it changed are sent to the device.
+    /// Returns the settings of the device once updated
+    pub async fn update_app_setting<F>(&mut self, update: F) -> Result<AppSetting, Box<dyn std::error::Error + Send + Sync>>
+    where
+        F: FnOnce(&mut AppSetting),
+    {
+        let current = self.get_app_setting().await?;
+        let mut updated = current.clone();
+        update(&mut updated);
+
+        let changes = current.changes(&updated);
+        if changes.is_empty() {
+            return Ok(current);
+        }
+        let method = "set_app_setting";
+        let result = self.rpc_request_4700(method, Some(json!([changes]))).await?;
+        Ok(serde_json::from_value(result)?)
+    }
+}
@@ -0,0 +1,150 @@
+use super::ASIAir;
+use super::Timestamped;
+use super::camera::FrameType;
+use serde::Deserialize;
+use std::time::Duration;
+
+// Longest time an auto exposure run is waited for
+const AUTO_EXP_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// State of the auto exposure
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+pub struct AutoExpStatus {
+    pub is_working: bool,
+    // Exposure last tried, the one chosen once complete, and the mean of its frame
+    #[serde(default)]
+    pub exposure_us: u64,
+    #[serde(default)]
+    pub mean_adu: f64,
+    // Frames taken so far
+    #[serde(default)]
+    pub iteration: u32,
+}
+
+/// Exposure found by an auto exposure
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct AutoExpResult {
+    pub exposure_us: u64,
+    // Mean of the last frame, within the tolerance of the target
+    pub mean_adu: f64,
+    pub iterations: u32,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AutoExpState {
+    #[default]
+    Idle,
+    Start,
+    // A frame was measured
+    Working,
+    // The main camera uses the exposure found
+    Complete,
+    Fail,
+    Cancel,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct AutoExpEvent {
+    pub state: AutoExpState,
+    #[serde(default)]
+    pub exposure_us: u64,
+    #[serde(default)]
+    pub mean_adu: f64,
+    #[serde(default)]
+    pub iteration: u32,
+    // Reason of a failure
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+impl ASIAir {
+    /// Find the exposure of flat frames with a mean of `target_adu`, give or take `tolerance`.
+    /// The main camera keeps that exposure
+    pub async fn auto_expose(
+        &mut self,
+        target_adu: f64,
+        tolerance: f64,
+    ) -> Result<AutoExpResult, Box<dyn std::error::Error + Send + Sync>> {
+        let mut auto_exp_rx = self.subscribe_auto_exp();
+        auto_exp_rx.mark_unchanged();
+
+        self.start_auto_exp(FrameType::Flat, target_adu, tolerance).await?;
+
+        let finished = tokio::time::timeout(AUTO_EXP_TIMEOUT, async {
+            loop {
+                if auto_exp_rx.changed().await.is_err() {
+                    return None;
+                }
+                let event = auto_exp_rx.borrow_and_update();
+                if matches!(event.state, AutoExpState::Complete | AutoExpState::Fail | AutoExpState::Cancel) {
+                    return Some(event.clone());
+                }
+            }
+        })
+        .await;
+        let event = match finished {
+            Ok(Some(event)) => event,
+            Ok(None) => return Err("Connection closed".into()),
+            Err(_) => {
+                let _ = self.stop_auto_exp().await;
+                return Err("auto exposure timeout".into());
+            }
+        };
+
+        match event.state {
+            AutoExpState::Complete => Ok(AutoExpResult {
+                exposure_us: event.exposure_us,
+                mean_adu: event.mean_adu,
+                iterations: event.iteration,
+            }),
+            AutoExpState::Cancel => Err("auto exposure cancelled".into()),
+            _ => Err(event.error.unwrap_or_else(|| "auto exposure failed".to_string()).into()),
+        }
+    }
+
+    /// Start an auto exposure of frames of `frame_type`, lights for previews. The progress is
+    /// reported by `AutoExpEvent` events
+    pub async fn start_auto_exp(
+        &mut self,
+        frame_type: FrameType,
+        target_adu: f64,
+        tolerance: f64,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let method = "start_auto_exp";
+        let params = Some(serde_json::json!([ {
+            "frame_type": frame_type,
+            "target_adu": target_adu,
+            "tolerance": tolerance,
+        } ]));
+        self.rpc_request_4700(method, params).await?;
+        Ok(())
+    }
+
+    pub async fn stop_auto_exp(
+        &mut self,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let method = "stop_auto_exp";
+        self.rpc_request_4700(method, None).await?;
+        Ok(())
+    }
+
+    pub async fn auto_exp_get_state(
+        &mut self,
+    ) -> Result<AutoExpStatus, Box<dyn std::error::Error + Send + Sync>> {
+        let method = "get_auto_exp_state";
+        let result = self.rpc_request_4700(method, None).await?;
+
+        Ok(serde_json::from_value(result)?)
+    }
+
+    /// `auto_exp_get_state` with the Timestamp of the response
+    pub async fn auto_exp_get_state_timestamped(
+        &mut self,
+    ) -> Result<Timestamped<AutoExpStatus>, Box<dyn std::error::Error + Send + Sync>> {
+        let method = "get_auto_exp_state";
+        let response = self.rpc_request_4700_timestamped(method, None).await?;
+
+        Ok(response.deserialize()?)
+    }
+}
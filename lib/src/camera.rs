@@ -1,7 +1,9 @@
 use super::ASIAir;
+use super::Timestamped;
 use serde::{Deserialize, Serialize};
 use std::io::Cursor;
 use std::io::Read;
+use std::sync::atomic::Ordering;
 use zip::ZipArchive;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -12,6 +14,15 @@ pub struct ConnectedCamera {
     pub dslr: bool,
 }
 
+/// Cameras connected to the device after one was plugged in or unplugged
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CameraListEvent {
+    pub cameras: Vec<ConnectedCamera>,
+    // Name of the camera plugged in or unplugged
+    pub plugged: Option<String>,
+    pub unplugged: Option<String>,
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "state")]
 pub enum CameraState {
@@ -21,6 +32,27 @@ pub enum CameraState {
     Idle { name: String, path: String },
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FrameType {
+    #[default]
+    Light,
+    Dark,
+    Flat,
+    Bias,
+}
+
+impl FrameType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FrameType::Light => "light",
+            FrameType::Dark => "dark",
+            FrameType::Flat => "flat",
+            FrameType::Bias => "bias",
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CameraInfo {
     pub chip_size: [u32; 2],
@@ -90,6 +122,16 @@ impl ASIAir {
         Ok(state)
     }
 
+    /// `main_camera_get_state` with the Timestamp of the response
+    pub async fn main_camera_get_state_timestamped(
+        &mut self,
+    ) -> Result<Timestamped<CameraState>, Box<dyn std::error::Error + Send + Sync>> {
+        let method = "get_camera_state";
+        let response = self.rpc_request_4700_timestamped(method, None).await?;
+
+        Ok(response.deserialize()?)
+    }
+
     pub async fn main_camera_set_name(
         &mut self,
         camera_name: String,
@@ -104,11 +146,7 @@ impl ASIAir {
     pub async fn main_camera_get_name(
         &mut self,
     ) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
-        let method = "get_app_setting";
-        let result = self.rpc_request_4700(method, None).await?;
-
-        let camera_name: String = serde_json::from_value(result["main_camera_name"].clone())?;
-        Ok(camera_name)
+        Ok(self.get_app_setting().await?.main_camera_name)
     }
 
     pub async fn guide_camera_set_name(
@@ -125,11 +163,7 @@ impl ASIAir {
     pub async fn guide_camera_get_name(
         &mut self,
     ) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
-        let method = "get_app_setting";
-        let result = self.rpc_request_4700(method, None).await?;
-
-        let camera_name: String = serde_json::from_value(result["guide_camera_name"].clone())?;
-        Ok(camera_name)
+        Ok(self.get_app_setting().await?.guide_camera_name)
     }
 
     pub async fn main_camera_open(
@@ -143,6 +177,31 @@ impl ASIAir {
         Ok(())
     }
 
+    /// Reopen the main camera each time it is plugged back in, off by default
+    pub fn set_reopen_main_camera(&self, reopen: bool) {
+        self.reopen_main_camera.store(reopen, Ordering::SeqCst);
+    }
+
+    // Opens the camera just plugged in when it is the configured main camera and no camera is open
+    pub(crate) async fn reopen_main_camera(mut self, plugged: String, cameras: Vec<ConnectedCamera>) {
+        let reopen = async {
+            if self.main_camera_get_name().await? != plugged {
+                return Ok(());
+            }
+            if self.main_camera_get_state().await? != CameraState::Close {
+                return Ok(());
+            }
+            if let Some(camera) = cameras.iter().find(|camera| camera.name == plugged) {
+                self.main_camera_open(camera.id).await?;
+                log::info!("Reopened the main camera {}", plugged);
+            }
+            Ok::<(), Box<dyn std::error::Error + Send + Sync>>(())
+        };
+        if let Err(e) = reopen.await {
+            log::warn!("Failed to reopen the main camera {}: {}", plugged, e);
+        }
+    }
+
     pub async fn main_camera_close(
         &mut self
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
@@ -154,13 +213,28 @@ impl ASIAir {
 
     pub async fn main_camera_start_exposure(
         &mut self,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.main_camera_start_frame_exposure(FrameType::Light).await
+    }
+
+    pub async fn main_camera_start_frame_exposure(
+        &mut self,
+        frame_type: FrameType,
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         let method = "start_exposure";
-        let params = Some(serde_json::json!([ "light" ]));
+        let params = Some(serde_json::json!([ frame_type.as_str() ]));
         self.rpc_request_4700(method, params).await?;
         Ok(())
     }
 
+    pub async fn main_camera_stop_exposure(
+        &mut self,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let method = "stop_exposure";
+        self.rpc_request_4700(method, None).await?;
+        Ok(())
+    }
+
     pub async fn main_camera_get_info(&self) -> Result<CameraInfo, Box<dyn std::error::Error + Send + Sync>> {
         let method = "get_camera_info";
         let result = self.rpc_request_4700(method, None).await?;
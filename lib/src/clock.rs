@@ -0,0 +1,163 @@
+use chrono::{DateTime, FixedOffset, SubsecRound, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::Deserialize;
+use tokio::sync::watch;
+use tokio::time::Duration;
+
+use super::ASIAir;
+
+// Requests made for each measure of the offset, the one with the shortest round trip is kept
+const CLOCK_SAMPLES: u32 = 3;
+
+/// Difference between the clock of the device and the local clock
+#[derive(Debug, Clone, Default)]
+pub struct ClockOffset {
+    // Device time minus local time, positive when the device is ahead
+    pub offset: chrono::Duration,
+    // Round trip of the request the offset was measured with, the offset is accurate to half of it
+    pub round_trip: Duration,
+    // Time of the device when it answered
+    pub device_time: DateTime<FixedOffset>,
+}
+
+/// Periodic check of the clock of the device
+#[derive(Debug, Clone)]
+pub struct ClockSyncConfig {
+    // Time between two measures of the offset
+    pub interval: Duration,
+    // Offset above which the device clock is set again, when `resync` is on
+    pub threshold: Duration,
+    // Set the clock of the device to the local time when it drifted past the threshold,
+    // otherwise the offset is only published
+    pub resync: bool,
+    // Time zone the device clock is set in
+    pub time_zone: Tz,
+}
+
+impl Default for ClockSyncConfig {
+    fn default() -> Self {
+        ClockSyncConfig {
+            interval: Duration::from_secs(60),
+            threshold: Duration::from_secs(2),
+            resync: false,
+            time_zone: Tz::UTC,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct DeviceTime {
+    year: i32,
+    mon: u32,
+    day: u32,
+    hour: u32,
+    min: u32,
+    sec: u32,
+    #[serde(default)]
+    msec: u32,
+    #[serde(default)]
+    utc_offset: i32,
+}
+
+impl DeviceTime {
+    fn to_date_time(&self) -> Option<DateTime<FixedOffset>> {
+        let offset = FixedOffset::east_opt(self.utc_offset)?;
+        let date_time = offset
+            .with_ymd_and_hms(self.year, self.mon, self.day, self.hour, self.min, self.sec)
+            .single()?;
+        Some(date_time + chrono::Duration::milliseconds(self.msec as i64))
+    }
+}
+
+impl ASIAir {
+    /// Read the clock of the device
+    pub async fn get_time(&self) -> Result<DateTime<FixedOffset>, Box<dyn std::error::Error + Send + Sync>> {
+        let method = "pi_get_time";
+        let response = self.rpc_request_4700(method, None).await?;
+        let time = serde_json::from_value::<DeviceTime>(response)?;
+        time.to_date_time().ok_or_else(|| format!("{}: invalid time", method).into())
+    }
+
+    /// Measure the offset of the clock of the device from the local clock. The device is assumed
+    /// to read its clock halfway through the round trip of the request
+    pub async fn measure_clock_offset(&self) -> Result<ClockOffset, Box<dyn std::error::Error + Send + Sync>> {
+        let mut best: Option<ClockOffset> = None;
+        for _ in 0..CLOCK_SAMPLES {
+            let sent = Utc::now();
+            let device_time = self.get_time().await?;
+            let received = Utc::now();
+
+            let round_trip = (received - sent).to_std().unwrap_or_default();
+            let local_time = sent + chrono::Duration::from_std(round_trip / 2)?;
+            let sample = ClockOffset {
+                offset: device_time.with_timezone(&Utc) - local_time,
+                round_trip,
+                device_time,
+            };
+            if best.as_ref().is_none_or(|best| sample.round_trip < best.round_trip) {
+                best = Some(sample);
+            }
+        }
+        let offset = best.ok_or("no clock sample")?;
+        let _ = self.clock_offset_tx.send(offset.clone());
+        Ok(offset)
+    }
+
+    /// Check the clock of the device periodically while connected, None to stop.
+    /// The interval can't be zero
+    pub fn set_clock_sync(&self, config: Option<ClockSyncConfig>) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        if config.as_ref().is_some_and(|config| config.interval.is_zero()) {
+            return Err("clock sync interval can't be zero".into());
+        }
+        let _ = self.clock_sync_tx.send(config);
+        Ok(())
+    }
+
+    /// Offsets of the clock of the device, published on each measure
+    pub fn subscribe_clock_offset(&self) -> watch::Receiver<ClockOffset> {
+        self.clock_offset_tx.subscribe()
+    }
+
+    // Measures the offset with the current configuration until the connection is shut down
+    pub(crate) async fn run_clock_sync(mut self, mut shutdown_rx: watch::Receiver<()>) {
+        let mut config_rx = self.clock_sync_tx.subscribe();
+        loop {
+            let config = config_rx.borrow_and_update().clone();
+            if let Some(config) = &config
+                && let Err(e) = self.sync_clock(config).await
+            {
+                log::warn!("Clock sync failed: {}", e);
+            }
+
+            let interval = config.map(|config| config.interval);
+            tokio::select! {
+                _ = shutdown_rx.changed() => break,
+                result = config_rx.changed() => {
+                    if result.is_err() {
+                        break;
+                    }
+                }
+                _ = tokio::time::sleep(interval.unwrap_or_default()), if interval.is_some() => {}
+            }
+        }
+    }
+
+    async fn sync_clock(&mut self, config: &ClockSyncConfig) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let measured = self.measure_clock_offset().await?;
+        let threshold = chrono::Duration::from_std(config.threshold)?;
+        if !config.resync || measured.offset.abs() <= threshold {
+            return Ok(());
+        }
+
+        log::info!("Device clock is off by {} ms, setting it again", measured.offset.num_milliseconds());
+        // The device only takes whole seconds, the request is sent so that it gets there on one
+        let half_trip = chrono::Duration::from_std(measured.round_trip / 2)?;
+        let target = (Utc::now() + half_trip).trunc_subsecs(0) + chrono::Duration::seconds(1);
+        let wait = (target - half_trip - Utc::now()).to_std().unwrap_or_default();
+        tokio::time::sleep(wait).await;
+        self.set_time(target.with_timezone(&config.time_zone)).await?;
+
+        self.measure_clock_offset().await?;
+        Ok(())
+    }
+}
@@ -1,12 +1,15 @@
+use chrono::{DateTime, FixedOffset};
+use serde::de::DeserializeOwned;
 use serde_json::{Value, json};
 use std::collections::HashMap;
+use std::marker::PhantomData;
 use std::net::{Ipv4Addr, SocketAddrV4};
 use std::str::FromStr;
 use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 use std::sync::{Arc, Mutex};
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::TcpStream;
-use tokio::sync::{mpsc, oneshot, watch};
+use tokio::sync::{broadcast, mpsc, oneshot, watch};
 use tokio::time::Duration;
 
 use super::ASIAir;
@@ -15,21 +18,109 @@ use super::ASIAirPage;
 use super::AnnotateEvent;
 use super::BinaryHeader;
 use super::BinaryResult;
+use super::DeviceError;
+use super::EventReceiver;
 use super::ExposureEvent;
 use super::PiStatusEvent;
 use super::PlateSolveEvent;
+use super::Timestamped;
+use super::parse_timestamp;
+use super::camera::CameraListEvent;
+use super::clock::ClockOffset;
+use super::focuser::{AutoFocusEvent, FocuserEvent};
+use super::guide::GuideEvent;
+use super::merid_flip::MeridFlipEvent;
+use super::record::AviRecordEvent;
+use super::rtmp::RtmpEvent;
+use super::auto_exp::AutoExpEvent;
+use super::find_star::FindStarEvent;
+use super::stack::StackEvent;
+use super::storage::{ExportEvent, FormatDriveEvent};
+use super::mount::MountEvent;
+use super::plan::PlanEvent;
+use super::polar::PolarAlignEvent;
+
+// Result of a JSON-RPC response. Any method can fail on the device, which then answers with an
+// `error` reason instead of a `result`, the reason becomes the error of the request
+fn response_result(
+    response: &Value,
+    timestamp: Option<DateTime<FixedOffset>>,
+) -> Result<Timestamped<Value>, Box<dyn std::error::Error + Send + Sync>> {
+    match response.get("error").and_then(|e| e.as_str()) {
+        Some(error) => Err(Box::new(DeviceError { reason: error.to_string() })),
+        None => Ok(Timestamped {
+            timestamp,
+            value: response["result"].clone(),
+        }),
+    }
+}
+
+// Publish an event on its channel, and on its timestamped channel with the Timestamp of the device
+fn publish<T: Clone>(
+    tx: &watch::Sender<T>,
+    timestamped_tx: &watch::Sender<Timestamped<T>>,
+    timestamp: Option<DateTime<FixedOffset>>,
+    value: T,
+) {
+    let _ = timestamped_tx.send(Timestamped {
+        timestamp,
+        value: value.clone(),
+    });
+    let _ = tx.send(value);
+}
 
 impl ASIAir {
     pub fn new(addr: Ipv4Addr) -> Self {
         let (connection_state_tx, _) = watch::channel(false);
+        let (events_tx, _) = broadcast::channel(256);
         let (camera_temperature_tx, _) = watch::channel(0.0);
         let (cooler_power_tx, _) = watch::channel(0);
         let (camera_control_change_tx, _) = watch::channel(());
         let (camera_state_change_tx, _) = watch::channel(());
+        let (camera_list_tx, _) = watch::channel(CameraListEvent::default());
         let (exposure_tx, _) = watch::channel(ExposureEvent::default());
         let (pi_status_tx, _) = watch::channel(PiStatusEvent::default());
         let (annotate_tx, _) = watch::channel(AnnotateEvent::default());
         let (plate_solve_tx, _) = watch::channel(PlateSolveEvent::default());
+        let (plan_tx, _) = watch::channel(PlanEvent::default());
+        let (mount_tx, _) = watch::channel(MountEvent::default());
+        let (polar_align_tx, _) = watch::channel(PolarAlignEvent::default());
+        let (focuser_tx, _) = watch::channel(FocuserEvent::default());
+        let (auto_focus_tx, _) = watch::channel(AutoFocusEvent::default());
+        let (guide_tx, _) = watch::channel(GuideEvent::default());
+        let (merid_flip_tx, _) = watch::channel(MeridFlipEvent::default());
+        let (stack_tx, _) = watch::channel(StackEvent::default());
+        let (format_drive_tx, _) = watch::channel(FormatDriveEvent::default());
+        let (export_tx, _) = watch::channel(ExportEvent::default());
+        let (avi_record_tx, _) = watch::channel(AviRecordEvent::default());
+        let (rtmp_tx, _) = watch::channel(RtmpEvent::default());
+        let (auto_exp_tx, _) = watch::channel(AutoExpEvent::default());
+        let (find_star_tx, _) = watch::channel(FindStarEvent::default());
+        let (camera_temperature_timestamped_tx, _) = watch::channel(Timestamped::default());
+        let (camera_state_change_timestamped_tx, _) = watch::channel(Timestamped::default());
+        let (camera_list_timestamped_tx, _) = watch::channel(Timestamped::default());
+        let (cooler_power_timestamped_tx, _) = watch::channel(Timestamped::default());
+        let (camera_control_change_timestamped_tx, _) = watch::channel(Timestamped::default());
+        let (exposure_timestamped_tx, _) = watch::channel(Timestamped::default());
+        let (pi_status_timestamped_tx, _) = watch::channel(Timestamped::default());
+        let (annotate_timestamped_tx, _) = watch::channel(Timestamped::default());
+        let (plate_solve_timestamped_tx, _) = watch::channel(Timestamped::default());
+        let (plan_timestamped_tx, _) = watch::channel(Timestamped::default());
+        let (mount_timestamped_tx, _) = watch::channel(Timestamped::default());
+        let (polar_align_timestamped_tx, _) = watch::channel(Timestamped::default());
+        let (focuser_timestamped_tx, _) = watch::channel(Timestamped::default());
+        let (auto_focus_timestamped_tx, _) = watch::channel(Timestamped::default());
+        let (guide_timestamped_tx, _) = watch::channel(Timestamped::default());
+        let (merid_flip_timestamped_tx, _) = watch::channel(Timestamped::default());
+        let (stack_timestamped_tx, _) = watch::channel(Timestamped::default());
+        let (format_drive_timestamped_tx, _) = watch::channel(Timestamped::default());
+        let (export_timestamped_tx, _) = watch::channel(Timestamped::default());
+        let (avi_record_timestamped_tx, _) = watch::channel(Timestamped::default());
+        let (rtmp_timestamped_tx, _) = watch::channel(Timestamped::default());
+        let (auto_exp_timestamped_tx, _) = watch::channel(Timestamped::default());
+        let (find_star_timestamped_tx, _) = watch::channel(Timestamped::default());
+        let (clock_offset_tx, _) = watch::channel(ClockOffset::default());
+        let (clock_sync_tx, _) = watch::channel(None);
 
         ASIAir {
             addr,
@@ -45,15 +136,57 @@ impl ASIAir {
             reconnect_tx: None,
             should_be_connected: Arc::new(AtomicBool::new(false)),
             connected: Arc::new(AtomicBool::new(false)),
+            reopen_main_camera: Arc::new(AtomicBool::new(false)),
+            clock_sync_tx,
             connection_state_tx,
+            events_tx,
             camera_temperature_tx,
             camera_state_change_tx,
+            camera_list_tx,
             cooler_power_tx,
             camera_control_change_tx,
             exposure_tx,
             pi_status_tx,
             annotate_tx,
             plate_solve_tx,
+            plan_tx,
+            mount_tx,
+            polar_align_tx,
+            focuser_tx,
+            auto_focus_tx,
+            guide_tx,
+            merid_flip_tx,
+            stack_tx,
+            format_drive_tx,
+            export_tx,
+            avi_record_tx,
+            rtmp_tx,
+            auto_exp_tx,
+            find_star_tx,
+            camera_temperature_timestamped_tx,
+            camera_state_change_timestamped_tx,
+            camera_list_timestamped_tx,
+            cooler_power_timestamped_tx,
+            camera_control_change_timestamped_tx,
+            exposure_timestamped_tx,
+            pi_status_timestamped_tx,
+            annotate_timestamped_tx,
+            plate_solve_timestamped_tx,
+            plan_timestamped_tx,
+            mount_timestamped_tx,
+            polar_align_timestamped_tx,
+            focuser_timestamped_tx,
+            auto_focus_timestamped_tx,
+            guide_timestamped_tx,
+            merid_flip_timestamped_tx,
+            stack_timestamped_tx,
+            format_drive_timestamped_tx,
+            export_timestamped_tx,
+            avi_record_timestamped_tx,
+            rtmp_timestamped_tx,
+            auto_exp_timestamped_tx,
+            find_star_timestamped_tx,
+            clock_offset_tx,
         }
     }
 
@@ -120,14 +253,55 @@ impl ASIAir {
         self.connected.store(true, Ordering::SeqCst);
         let _ = self.connection_state_tx.send(true); // Notify that we are connected
 
+        let events_tx = self.events_tx.clone();
         let camera_temperature_tx = self.camera_temperature_tx.clone();
+        let camera_temperature_timestamped_tx = self.camera_temperature_timestamped_tx.clone();
         let cooler_power_tx = self.cooler_power_tx.clone();
+        let cooler_power_timestamped_tx = self.cooler_power_timestamped_tx.clone();
         let camera_control_change_tx = self.camera_control_change_tx.clone();
+        let camera_control_change_timestamped_tx = self.camera_control_change_timestamped_tx.clone();
         let camera_state_change_tx = self.camera_state_change_tx.clone();
+        let camera_state_change_timestamped_tx = self.camera_state_change_timestamped_tx.clone();
+        let camera_list_tx = self.camera_list_tx.clone();
+        let camera_list_timestamped_tx = self.camera_list_timestamped_tx.clone();
+        let reopen_main_camera = self.reopen_main_camera.clone();
+        let camera_reopen = self.clone();
         let exposure_tx = self.exposure_tx.clone();
+        let exposure_timestamped_tx = self.exposure_timestamped_tx.clone();
         let pi_status_tx = self.pi_status_tx.clone();
+        let pi_status_timestamped_tx = self.pi_status_timestamped_tx.clone();
         let annotate_tx = self.annotate_tx.clone();
+        let annotate_timestamped_tx = self.annotate_timestamped_tx.clone();
         let plate_solve_tx = self.plate_solve_tx.clone();
+        let plate_solve_timestamped_tx = self.plate_solve_timestamped_tx.clone();
+        let plan_tx = self.plan_tx.clone();
+        let plan_timestamped_tx = self.plan_timestamped_tx.clone();
+        let mount_tx = self.mount_tx.clone();
+        let mount_timestamped_tx = self.mount_timestamped_tx.clone();
+        let polar_align_tx = self.polar_align_tx.clone();
+        let polar_align_timestamped_tx = self.polar_align_timestamped_tx.clone();
+        let focuser_tx = self.focuser_tx.clone();
+        let focuser_timestamped_tx = self.focuser_timestamped_tx.clone();
+        let auto_focus_tx = self.auto_focus_tx.clone();
+        let auto_focus_timestamped_tx = self.auto_focus_timestamped_tx.clone();
+        let guide_tx = self.guide_tx.clone();
+        let guide_timestamped_tx = self.guide_timestamped_tx.clone();
+        let merid_flip_tx = self.merid_flip_tx.clone();
+        let merid_flip_timestamped_tx = self.merid_flip_timestamped_tx.clone();
+        let stack_tx = self.stack_tx.clone();
+        let stack_timestamped_tx = self.stack_timestamped_tx.clone();
+        let format_drive_tx = self.format_drive_tx.clone();
+        let format_drive_timestamped_tx = self.format_drive_timestamped_tx.clone();
+        let export_tx = self.export_tx.clone();
+        let export_timestamped_tx = self.export_timestamped_tx.clone();
+        let avi_record_tx = self.avi_record_tx.clone();
+        let avi_record_timestamped_tx = self.avi_record_timestamped_tx.clone();
+        let rtmp_tx = self.rtmp_tx.clone();
+        let rtmp_timestamped_tx = self.rtmp_timestamped_tx.clone();
+        let auto_exp_tx = self.auto_exp_tx.clone();
+        let auto_exp_timestamped_tx = self.auto_exp_timestamped_tx.clone();
+        let find_star_tx = self.find_star_tx.clone();
+        let find_star_timestamped_tx = self.find_star_timestamped_tx.clone();
 
         let socket_4800 = SocketAddrV4::new(self.addr.clone(), 4800);
         let stream_4800 = TcpStream::connect(socket_4800).await?;
@@ -145,6 +319,9 @@ impl ASIAir {
         let (tx_4800, mut rx_4800) = mpsc::channel::<ASIAirCommand>(32);
         self.tx_4800 = Some(tx_4800.clone());
 
+        // Clock sync loop, on a handle holding the channels of this connection
+        tokio::spawn(self.clone().run_clock_sync(shutdown_rx.clone()));
+
         tokio::spawn(async move {
             loop {
                 tokio::select! {
@@ -222,23 +399,37 @@ impl ASIAir {
                                     let frame = buffer.drain(..pos + 2).collect::<Vec<_>>();
                                     if let Ok(response) = serde_json::from_slice::<Value>(&frame) {
                                         // Process the response as before
+                                        let timestamp = parse_timestamp(&response);
                                         if let Some(event) = response.get("Event") {
+                                            let _ = events_tx.send(Timestamped {
+                                                timestamp,
+                                                value: response.clone(),
+                                            });
                                             match event.as_str() {
                                                 Some("Temperature") => {
                                                     if let Some(temp) = response.get("value").and_then(|r| r.as_f64()) {
-                                                        let _ = camera_temperature_tx.send(temp as f32);
+                                                        publish(&camera_temperature_tx, &camera_temperature_timestamped_tx, timestamp, temp as f32);
                                                     }
                                                 },
                                                 Some("CoolerPower") => {
                                                     if let Some(power) = response.get("value").and_then(|r| r.as_i64()) {
-                                                        let _ = cooler_power_tx.send(power as i32);
+                                                        publish(&cooler_power_tx, &cooler_power_timestamped_tx, timestamp, power as i32);
                                                     }
                                                 },
                                                 Some("CameraControlChange") => {
-                                                    let _ = camera_control_change_tx.send(());
+                                                    publish(&camera_control_change_tx, &camera_control_change_timestamped_tx, timestamp, ());
                                                 },
                                                 Some("CameraStateChange") => {
-                                                    let _ = camera_state_change_tx.send(());
+                                                    publish(&camera_state_change_tx, &camera_state_change_timestamped_tx, timestamp, ());
+                                                },
+                                                Some("CameraListChange") => {
+                                                    if let Ok(event) = serde_json::from_value::<CameraListEvent>(response.clone()) {
+                                                        if let Some(plugged) = event.plugged.clone().filter(|_| reopen_main_camera.load(Ordering::SeqCst)) {
+                                                            // Requests can't be sent from the read loop, which reads their responses
+                                                            tokio::spawn(camera_reopen.clone().reopen_main_camera(plugged, event.cameras.clone()));
+                                                        }
+                                                        publish(&camera_list_tx, &camera_list_timestamped_tx, timestamp, event);
+                                                    }
                                                 },
                                                 Some("Exposure") => {
                                                     match response.get("state").and_then(|r| r.as_str()) {
@@ -247,7 +438,7 @@ impl ASIAir {
                                                                 if let Some(gain) = response.get("gain").and_then(|r| r.as_u64()) {
                                                                     if let Some(page) = response.get("page").and_then(|r| r.as_str()) {
                                                                         if let Ok(page) = ASIAirPage::from_str(page) {
-                                                                            let _ = exposure_tx.send(ExposureEvent::Start {
+                                                                            publish(&exposure_tx, &exposure_timestamped_tx, timestamp, ExposureEvent::Start {
                                                                                 page: page,
                                                                                 exp_us: exp_us,
                                                                                 gain: gain as u64,
@@ -258,10 +449,19 @@ impl ASIAir {
                                                             }
                                                         },
                                                         Some("complete") => {
-                                                            let _ = exposure_tx.send(ExposureEvent::Complete);
+                                                            publish(&exposure_tx, &exposure_timestamped_tx, timestamp, ExposureEvent::Complete);
                                                         },
                                                         Some("downloading") => {
-                                                            let _ = exposure_tx.send(ExposureEvent::Downloading);
+                                                            publish(&exposure_tx, &exposure_timestamped_tx, timestamp, ExposureEvent::Downloading);
+                                                        }
+                                                        Some("cancel") => {
+                                                            publish(&exposure_tx, &exposure_timestamped_tx, timestamp, ExposureEvent::Cancel);
+                                                        }
+                                                        Some("fail") => {
+                                                            let error = response.get("error").and_then(|r| r.as_str()).unwrap_or_default();
+                                                            publish(&exposure_tx, &exposure_timestamped_tx, timestamp, ExposureEvent::Fail {
+                                                                error: error.to_string(),
+                                                            });
                                                         }
                                                         _ => {}
                                                     }
@@ -271,7 +471,7 @@ impl ASIAir {
                                                         if let Some(temp) = response.get("temp").and_then(|r| r.as_f64()) {
                                                             if let Some(is_undervolt) = response.get("is_undervolt").and_then(|r| r.as_bool()) {
                                                                 if let Some(is_over_current) = response.get("is_over_current").and_then(|r| r.as_bool()) {
-                                                                    let _ = pi_status_tx.send(PiStatusEvent {
+                                                                    publish(&pi_status_tx, &pi_status_timestamped_tx, timestamp, PiStatusEvent {
                                                                         is_overtemp: is_overtemp,
                                                                         temp: temp as f32,
                                                                         is_undervolt: is_undervolt,
@@ -287,7 +487,7 @@ impl ASIAir {
                                                         if let Some(tag) = response.get("tag").and_then(|r| r.as_str()) {
                                                             if let Some(state) = response.get("state").and_then(|r| r.as_str()) {
                                                                 if let Ok(page) = ASIAirPage::from_str(page) {
-                                                                    let _ = annotate_tx.send(AnnotateEvent {
+                                                                    publish(&annotate_tx, &annotate_timestamped_tx, timestamp, AnnotateEvent {
                                                                         page: page,
                                                                         tag: tag.to_string(),
                                                                         state: state.to_string(),
@@ -302,7 +502,7 @@ impl ASIAir {
                                                         if let Some(tag) = response.get("tag").and_then(|r| r.as_str()) {
                                                             if let Some(state) = response.get("state").and_then(|r| r.as_str()) {
                                                                 if let Ok(page) = ASIAirPage::from_str(page) {
-                                                                    let _ = plate_solve_tx.send(PlateSolveEvent {
+                                                                    publish(&plate_solve_tx, &plate_solve_timestamped_tx, timestamp, PlateSolveEvent {
                                                                         page: page,
                                                                         tag: tag.to_string(),
                                                                         state: state.to_string(),
@@ -312,6 +512,76 @@ impl ASIAir {
                                                         }
                                                     }
                                                 },
+                                                Some("Plan") => {
+                                                    if let Ok(event) = serde_json::from_value::<PlanEvent>(response.clone()) {
+                                                        publish(&plan_tx, &plan_timestamped_tx, timestamp, event);
+                                                    }
+                                                },
+                                                Some("ScopeGoto") | Some("ScopePark") | Some("ScopePosition") | Some("ScopeTrack") => {
+                                                    if let Ok(event) = serde_json::from_value::<MountEvent>(response.clone()) {
+                                                        publish(&mount_tx, &mount_timestamped_tx, timestamp, event);
+                                                    }
+                                                },
+                                                Some("PolarAlign") => {
+                                                    if let Ok(event) = serde_json::from_value::<PolarAlignEvent>(response.clone()) {
+                                                        publish(&polar_align_tx, &polar_align_timestamped_tx, timestamp, event);
+                                                    }
+                                                },
+                                                Some("Focuser") => {
+                                                    if let Ok(event) = serde_json::from_value::<FocuserEvent>(response.clone()) {
+                                                        publish(&focuser_tx, &focuser_timestamped_tx, timestamp, event);
+                                                    }
+                                                },
+                                                Some("AutoFocus") => {
+                                                    if let Ok(event) = serde_json::from_value::<AutoFocusEvent>(response.clone()) {
+                                                        publish(&auto_focus_tx, &auto_focus_timestamped_tx, timestamp, event);
+                                                    }
+                                                },
+                                                Some("Guide") => {
+                                                    if let Ok(event) = serde_json::from_value::<GuideEvent>(response.clone()) {
+                                                        publish(&guide_tx, &guide_timestamped_tx, timestamp, event);
+                                                    }
+                                                },
+                                                Some("MeridianFlip") => {
+                                                    if let Ok(event) = serde_json::from_value::<MeridFlipEvent>(response.clone()) {
+                                                        publish(&merid_flip_tx, &merid_flip_timestamped_tx, timestamp, event);
+                                                    }
+                                                },
+                                                Some("Stack") => {
+                                                    if let Ok(event) = serde_json::from_value::<StackEvent>(response.clone()) {
+                                                        publish(&stack_tx, &stack_timestamped_tx, timestamp, event);
+                                                    }
+                                                },
+                                                Some("FormatDrive") => {
+                                                    if let Ok(event) = serde_json::from_value::<FormatDriveEvent>(response.clone()) {
+                                                        publish(&format_drive_tx, &format_drive_timestamped_tx, timestamp, event);
+                                                    }
+                                                },
+                                                Some("ExportImage") => {
+                                                    if let Ok(event) = serde_json::from_value::<ExportEvent>(response.clone()) {
+                                                        publish(&export_tx, &export_timestamped_tx, timestamp, event);
+                                                    }
+                                                },
+                                                Some("AviRecord") => {
+                                                    if let Ok(event) = serde_json::from_value::<AviRecordEvent>(response.clone()) {
+                                                        publish(&avi_record_tx, &avi_record_timestamped_tx, timestamp, event);
+                                                    }
+                                                },
+                                                Some("Rtmp") => {
+                                                    if let Ok(event) = serde_json::from_value::<RtmpEvent>(response.clone()) {
+                                                        publish(&rtmp_tx, &rtmp_timestamped_tx, timestamp, event);
+                                                    }
+                                                },
+                                                Some("AutoExp") => {
+                                                    if let Ok(event) = serde_json::from_value::<AutoExpEvent>(response.clone()) {
+                                                        publish(&auto_exp_tx, &auto_exp_timestamped_tx, timestamp, event);
+                                                    }
+                                                },
+                                                Some("FindStar") => {
+                                                    if let Ok(event) = serde_json::from_value::<FindStarEvent>(response.clone()) {
+                                                        publish(&find_star_tx, &find_star_timestamped_tx, timestamp, event);
+                                                    }
+                                                },
                                                 _ => {}
                                             }
                                         } else if response.get("jsonrpc").is_some() {
@@ -321,7 +591,7 @@ impl ASIAir {
                                                     .unwrap()
                                                     .remove(&(id as u32))
                                                 {
-                                                    let _ = tx.send(Ok(response["result"].clone()));
+                                                    let _ = tx.send(response_result(&response, timestamp));
                                                 } else {
                                                     log::warn!("No pending response for ID {}: {:?}", id, response);
                                                 }
@@ -561,6 +831,20 @@ impl ASIAir {
         self.connection_state_tx.subscribe()
     }
 
+    /// Every event of the device with its timestamp, including the events without a dedicated channel
+    pub fn subscribe_events(&self) -> broadcast::Receiver<Timestamped<Value>> {
+        self.events_tx.subscribe()
+    }
+
+    // Every event `name` from now on, for the streams and the waits that can't miss one
+    pub(crate) fn subscribe_events_of<T: DeserializeOwned>(&self, name: &'static str) -> EventReceiver<T> {
+        EventReceiver {
+            events_rx: self.events_tx.subscribe(),
+            name,
+            _event: PhantomData,
+        }
+    }
+
     pub fn subscribe_camera_temperature(&self) -> watch::Receiver<f32> {
         self.camera_temperature_tx.subscribe()
     }
@@ -569,6 +853,10 @@ impl ASIAir {
         self.camera_state_change_tx.subscribe()
     }
 
+    pub fn subscribe_camera_list(&self) -> watch::Receiver<CameraListEvent> {
+        self.camera_list_tx.subscribe()
+    }
+
     pub fn subscribe_cooler_power(&self) -> watch::Receiver<i32> {
         self.cooler_power_tx.subscribe()
     }
@@ -593,11 +881,170 @@ impl ASIAir {
         self.plate_solve_tx.subscribe()
     }
 
+    pub fn subscribe_plan(&self) -> watch::Receiver<PlanEvent> {
+        self.plan_tx.subscribe()
+    }
+
+    pub fn subscribe_mount(&self) -> watch::Receiver<MountEvent> {
+        self.mount_tx.subscribe()
+    }
+
+    pub fn subscribe_polar_align(&self) -> watch::Receiver<PolarAlignEvent> {
+        self.polar_align_tx.subscribe()
+    }
+
+    pub fn subscribe_focuser(&self) -> watch::Receiver<FocuserEvent> {
+        self.focuser_tx.subscribe()
+    }
+
+    pub fn subscribe_auto_focus(&self) -> watch::Receiver<AutoFocusEvent> {
+        self.auto_focus_tx.subscribe()
+    }
+
+    pub fn subscribe_guide(&self) -> watch::Receiver<GuideEvent> {
+        self.guide_tx.subscribe()
+    }
+
+    pub fn subscribe_merid_flip(&self) -> watch::Receiver<MeridFlipEvent> {
+        self.merid_flip_tx.subscribe()
+    }
+
+    pub fn subscribe_stack(&self) -> watch::Receiver<StackEvent> {
+        self.stack_tx.subscribe()
+    }
+
+    pub fn subscribe_format_drive(&self) -> watch::Receiver<FormatDriveEvent> {
+        self.format_drive_tx.subscribe()
+    }
+
+    pub fn subscribe_export(&self) -> watch::Receiver<ExportEvent> {
+        self.export_tx.subscribe()
+    }
+
+    pub fn subscribe_avi_record(&self) -> watch::Receiver<AviRecordEvent> {
+        self.avi_record_tx.subscribe()
+    }
+
+    pub fn subscribe_rtmp(&self) -> watch::Receiver<RtmpEvent> {
+        self.rtmp_tx.subscribe()
+    }
+
+    pub fn subscribe_auto_exp(&self) -> watch::Receiver<AutoExpEvent> {
+        self.auto_exp_tx.subscribe()
+    }
+
+    pub fn subscribe_find_star(&self) -> watch::Receiver<FindStarEvent> {
+        self.find_star_tx.subscribe()
+    }
+
+    pub fn subscribe_camera_temperature_timestamped(&self) -> watch::Receiver<Timestamped<f32>> {
+        self.camera_temperature_timestamped_tx.subscribe()
+    }
+
+    pub fn subscribe_camera_state_change_timestamped(&self) -> watch::Receiver<Timestamped<()>> {
+        self.camera_state_change_timestamped_tx.subscribe()
+    }
+
+    pub fn subscribe_camera_list_timestamped(&self) -> watch::Receiver<Timestamped<CameraListEvent>> {
+        self.camera_list_timestamped_tx.subscribe()
+    }
+
+    pub fn subscribe_cooler_power_timestamped(&self) -> watch::Receiver<Timestamped<i32>> {
+        self.cooler_power_timestamped_tx.subscribe()
+    }
+
+    pub fn subscribe_camera_control_change_timestamped(&self) -> watch::Receiver<Timestamped<()>> {
+        self.camera_control_change_timestamped_tx.subscribe()
+    }
+
+    pub fn subscribe_exposure_timestamped(&self) -> watch::Receiver<Timestamped<ExposureEvent>> {
+        self.exposure_timestamped_tx.subscribe()
+    }
+
+    pub fn subscribe_pi_status_timestamped(&self) -> watch::Receiver<Timestamped<PiStatusEvent>> {
+        self.pi_status_timestamped_tx.subscribe()
+    }
+
+    pub fn subscribe_annotate_timestamped(&self) -> watch::Receiver<Timestamped<AnnotateEvent>> {
+        self.annotate_timestamped_tx.subscribe()
+    }
+
+    pub fn subscribe_plate_solve_timestamped(&self) -> watch::Receiver<Timestamped<PlateSolveEvent>> {
+        self.plate_solve_timestamped_tx.subscribe()
+    }
+
+    pub fn subscribe_plan_timestamped(&self) -> watch::Receiver<Timestamped<PlanEvent>> {
+        self.plan_timestamped_tx.subscribe()
+    }
+
+    pub fn subscribe_mount_timestamped(&self) -> watch::Receiver<Timestamped<MountEvent>> {
+        self.mount_timestamped_tx.subscribe()
+    }
+
+    pub fn subscribe_polar_align_timestamped(&self) -> watch::Receiver<Timestamped<PolarAlignEvent>> {
+        self.polar_align_timestamped_tx.subscribe()
+    }
+
+    pub fn subscribe_focuser_timestamped(&self) -> watch::Receiver<Timestamped<FocuserEvent>> {
+        self.focuser_timestamped_tx.subscribe()
+    }
+
+    pub fn subscribe_auto_focus_timestamped(&self) -> watch::Receiver<Timestamped<AutoFocusEvent>> {
+        self.auto_focus_timestamped_tx.subscribe()
+    }
+
+    pub fn subscribe_guide_timestamped(&self) -> watch::Receiver<Timestamped<GuideEvent>> {
+        self.guide_timestamped_tx.subscribe()
+    }
+
+    pub fn subscribe_merid_flip_timestamped(&self) -> watch::Receiver<Timestamped<MeridFlipEvent>> {
+        self.merid_flip_timestamped_tx.subscribe()
+    }
+
+    pub fn subscribe_stack_timestamped(&self) -> watch::Receiver<Timestamped<StackEvent>> {
+        self.stack_timestamped_tx.subscribe()
+    }
+
+    pub fn subscribe_format_drive_timestamped(&self) -> watch::Receiver<Timestamped<FormatDriveEvent>> {
+        self.format_drive_timestamped_tx.subscribe()
+    }
+
+    pub fn subscribe_export_timestamped(&self) -> watch::Receiver<Timestamped<ExportEvent>> {
+        self.export_timestamped_tx.subscribe()
+    }
+
+    pub fn subscribe_avi_record_timestamped(&self) -> watch::Receiver<Timestamped<AviRecordEvent>> {
+        self.avi_record_timestamped_tx.subscribe()
+    }
+
+    pub fn subscribe_rtmp_timestamped(&self) -> watch::Receiver<Timestamped<RtmpEvent>> {
+        self.rtmp_timestamped_tx.subscribe()
+    }
+
+    pub fn subscribe_auto_exp_timestamped(&self) -> watch::Receiver<Timestamped<AutoExpEvent>> {
+        self.auto_exp_timestamped_tx.subscribe()
+    }
+
+    pub fn subscribe_find_star_timestamped(&self) -> watch::Receiver<Timestamped<FindStarEvent>> {
+        self.find_star_timestamped_tx.subscribe()
+    }
+
     pub async fn rpc_request_4700(
         &self,
         method: &str,
         params: Option<Value>,
     ) -> Result<Value, Box<dyn std::error::Error + Send + Sync>> {
+        self.rpc_request_4700_timestamped(method, params)
+            .await
+            .map(|response| response.value)
+    }
+
+    /// Send a request on port 4700, the result comes with the Timestamp of the response
+    pub async fn rpc_request_4700_timestamped(
+        &self,
+        method: &str,
+        params: Option<Value>,
+    ) -> Result<Timestamped<Value>, Box<dyn std::error::Error + Send + Sync>> {
         if !self.should_be_connected.load(Ordering::SeqCst) {
             return Err("Not connected".into());
         }
@@ -0,0 +1,129 @@
+use super::ASIAir;
+use super::Timestamped;
+use serde::Deserialize;
+use std::time::Duration;
+
+// Longest time to wait for the device to find a star, and center it
+const FIND_STAR_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Brightest star of a frame of the main camera
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+pub struct FindStarResult {
+    // Position in pixels from the top left corner of the frame
+    pub x: f64,
+    pub y: f64,
+    // Half flux radius in pixels
+    pub hfr: f64,
+    // Peak above the background in ADU
+    pub peak: f64,
+    // Position on the sky, right ascension in hours and declination in degrees
+    pub ra: f64,
+    pub dec: f64,
+    // Whether the mount was moved to put the star at the center of the frame
+    pub centered: bool,
+    // Time the device spent exposing, finding and centering
+    #[serde(default)]
+    pub lapse_ms: u32,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FindStarState {
+    #[default]
+    Idle,
+    Start,
+    // The star was found and the mount is moving to center it
+    Center,
+    Complete,
+    Fail,
+    Cancel,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct FindStarEvent {
+    pub state: FindStarState,
+    #[serde(default)]
+    pub lapse_ms: u32,
+    // Star found, only set by complete events
+    #[serde(default)]
+    pub star: Option<FindStarResult>,
+    // Reason of a failure
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+impl ASIAir {
+    /// Expose a frame with the main camera and find its brightest star, waiting for the result.
+    /// With `center`, the mount is moved so the star ends up at the center of the frame
+    pub async fn find_star(
+        &mut self,
+        center: bool,
+    ) -> Result<FindStarResult, Box<dyn std::error::Error + Send + Sync>> {
+        let mut find_star_rx = self.subscribe_find_star();
+        find_star_rx.mark_unchanged();
+
+        self.start_find_star(center).await?;
+
+        let finished = tokio::time::timeout(FIND_STAR_TIMEOUT, async {
+            loop {
+                if find_star_rx.changed().await.is_err() {
+                    return false;
+                }
+                let event = find_star_rx.borrow_and_update();
+                if matches!(event.state, FindStarState::Complete | FindStarState::Fail | FindStarState::Cancel) {
+                    return true;
+                }
+            }
+        })
+        .await;
+        match finished {
+            Ok(true) => {}
+            Ok(false) => return Err("Connection closed".into()),
+            Err(_) => {
+                let _ = self.stop_find_star().await;
+                return Err("find star timeout".into());
+            }
+        }
+
+        self.get_find_star_result().await
+    }
+
+    /// Start finding a star, the progress is reported by `FindStarEvent` events
+    pub async fn start_find_star(
+        &mut self,
+        center: bool,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let method = "start_find_star";
+        let params = Some(serde_json::json!([ { "center": center } ]));
+        self.rpc_request_4700(method, params).await?;
+        Ok(())
+    }
+
+    pub async fn stop_find_star(
+        &mut self,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let method = "stop_find_star";
+        self.rpc_request_4700(method, None).await?;
+        Ok(())
+    }
+
+    /// Result of the last find star
+    pub async fn get_find_star_result(
+        &mut self,
+    ) -> Result<FindStarResult, Box<dyn std::error::Error + Send + Sync>> {
+        let method = "get_find_star_result";
+        let result = self.rpc_request_4700(method, None).await?;
+
+        Ok(serde_json::from_value(result)?)
+    }
+
+    /// `get_find_star_result` with the Timestamp of the response
+    pub async fn get_find_star_result_timestamped(
+        &mut self,
+    ) -> Result<Timestamped<FindStarResult>, Box<dyn std::error::Error + Send + Sync>> {
+        let method = "get_find_star_result";
+        let response = self.rpc_request_4700_timestamped(method, None).await?;
+
+        Ok(response.deserialize()?)
+    }
+}
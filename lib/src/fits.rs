@@ -0,0 +1,67 @@
+use std::io::Write;
+
+const BLOCK_SIZE: usize = 2880;
+const CARD_SIZE: usize = 80;
+
+/// A header card value, formatted following the FITS fixed format rules
+pub enum FitsValue {
+    Logical(bool),
+    Integer(i64),
+    Real(f64),
+    Text(String),
+}
+
+/// Minimal writer for single HDU 16 bit FITS images, as produced by ASI cameras.
+///
+/// The pixel data is expected as big-endian signed 16 bit integers, which is the
+/// layout used by the ASIAir when transferring raw images, so it is written as is
+/// with `BZERO = 32768` to represent the unsigned sensor values.
+pub struct FitsWriter {
+    cards: Vec<(String, FitsValue)>,
+}
+
+impl FitsWriter {
+    pub fn new(width: u16, height: u16) -> Self {
+        FitsWriter {
+            cards: vec![
+                ("SIMPLE".to_string(), FitsValue::Logical(true)),
+                ("BITPIX".to_string(), FitsValue::Integer(16)),
+                ("NAXIS".to_string(), FitsValue::Integer(2)),
+                ("NAXIS1".to_string(), FitsValue::Integer(width as i64)),
+                ("NAXIS2".to_string(), FitsValue::Integer(height as i64)),
+                ("BZERO".to_string(), FitsValue::Real(32768.0)),
+                ("BSCALE".to_string(), FitsValue::Real(1.0)),
+            ],
+        }
+    }
+
+    pub fn card(mut self, keyword: &str, value: FitsValue) -> Self {
+        self.cards.push((keyword.to_uppercase(), value));
+        self
+    }
+
+    pub fn write<W: Write>(&self, out: &mut W, data: &[u8]) -> std::io::Result<()> {
+        let mut header = String::with_capacity(BLOCK_SIZE);
+        for (keyword, value) in &self.cards {
+            let value = match value {
+                FitsValue::Logical(v) => format!("{:>20}", if *v { "T" } else { "F" }),
+                FitsValue::Integer(v) => format!("{:>20}", v),
+                FitsValue::Real(v) if v.fract() == 0.0 => format!("{:>20.1}", v),
+                FitsValue::Real(v) => format!("{:>20}", v),
+                FitsValue::Text(v) => format!("{:<20}", format!("'{:<8}'", v.replace('\'', "''"))),
+            };
+            let card = format!("{:<8}= {}", &keyword[..keyword.len().min(8)], value);
+            header.push_str(&format!("{:<80}", &card[..card.len().min(CARD_SIZE)]));
+        }
+        header.push_str(&format!("{:<80}", "END"));
+        while !header.len().is_multiple_of(BLOCK_SIZE) {
+            header.push(' ');
+        }
+        out.write_all(header.as_bytes())?;
+
+        out.write_all(data)?;
+        let padding = (BLOCK_SIZE - data.len() % BLOCK_SIZE) % BLOCK_SIZE;
+        out.write_all(&vec![0u8; padding])?;
+        Ok(())
+    }
+}
@@ -0,0 +1,199 @@
+use super::ASIAir;
+use super::Timestamped;
+use serde::Deserialize;
+use std::time::Duration;
+
+// Longest time an autofocus run is waited for
+const AUTO_FOCUS_TIMEOUT: Duration = Duration::from_secs(300);
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct FocuserState {
+    // Positions in steps
+    pub position: i32,
+    pub max_step: i32,
+    pub backlash: i32,
+    // Temperature reported by the focuser probe, in degrees Celsius
+    pub temperature: f64,
+    pub moving: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FocuserMoveState {
+    #[default]
+    Idle,
+    Moving,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct FocuserEvent {
+    pub state: FocuserMoveState,
+    pub position: i32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct AutoFocusPoint {
+    pub position: i32,
+    // Star half flux radius in pixels
+    pub hfr: f64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct AutoFocusResult {
+    // Points of the V-curve, in the order they were measured
+    pub points: Vec<AutoFocusPoint>,
+    pub best_position: i32,
+    pub best_hfr: f64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AutoFocusState {
+    #[default]
+    Idle,
+    Start,
+    // A point of the V-curve was measured
+    Working,
+    Complete,
+    Fail,
+    Cancel,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct AutoFocusEvent {
+    pub state: AutoFocusState,
+    pub position: i32,
+    // Star half flux radius measured at the position, only set by working and complete events
+    #[serde(default)]
+    pub hfr: f64,
+    // Fitted V-curve, only set by complete events
+    #[serde(default)]
+    pub result: Option<AutoFocusResult>,
+    // Reason of a failure
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+impl ASIAir {
+    pub async fn focuser_open(
+        &mut self,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let method = "open_focuser";
+        self.rpc_request_4700(method, None).await?;
+        Ok(())
+    }
+
+    pub async fn focuser_close(
+        &mut self,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let method = "close_focuser";
+        self.rpc_request_4700(method, None).await?;
+        Ok(())
+    }
+
+    pub async fn focuser_get_state(
+        &mut self,
+    ) -> Result<FocuserState, Box<dyn std::error::Error + Send + Sync>> {
+        let method = "get_focuser_state";
+        let result = self.rpc_request_4700(method, None).await?;
+
+        let state: FocuserState = serde_json::from_value(result)?;
+        Ok(state)
+    }
+
+    /// `focuser_get_state` with the Timestamp of the response
+    pub async fn focuser_get_state_timestamped(
+        &mut self,
+    ) -> Result<Timestamped<FocuserState>, Box<dyn std::error::Error + Send + Sync>> {
+        let method = "get_focuser_state";
+        let response = self.rpc_request_4700_timestamped(method, None).await?;
+
+        Ok(response.deserialize()?)
+    }
+
+    pub async fn focuser_get_position(
+        &mut self,
+    ) -> Result<i32, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(self.focuser_get_state().await?.position)
+    }
+
+    /// Move the focuser to an absolute position in steps.
+    /// Returns once the move started, the end of the move is reported by a `FocuserEvent` event
+    pub async fn focuser_move(
+        &mut self,
+        position: i32,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let method = "move_focuser";
+        let params = Some(serde_json::json!([ position ]));
+        self.rpc_request_4700(method, params).await?;
+        Ok(())
+    }
+
+    pub async fn focuser_stop(
+        &mut self,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let method = "stop_focuser";
+        self.rpc_request_4700(method, None).await?;
+        Ok(())
+    }
+
+    /// Run an autofocus around the current focuser position, `step` steps between the `points`
+    /// points of the V-curve, and wait for the fitted curve. The focuser is left at the best position
+    pub async fn auto_focus(
+        &mut self,
+        step: i32,
+        points: u32,
+    ) -> Result<AutoFocusResult, Box<dyn std::error::Error + Send + Sync>> {
+        let mut auto_focus_rx = self.subscribe_auto_focus();
+        auto_focus_rx.mark_unchanged();
+
+        self.start_auto_focus(step, points).await?;
+
+        let finished = tokio::time::timeout(AUTO_FOCUS_TIMEOUT, async {
+            loop {
+                if auto_focus_rx.changed().await.is_err() {
+                    return None;
+                }
+                let event = auto_focus_rx.borrow_and_update();
+                if matches!(event.state, AutoFocusState::Complete | AutoFocusState::Fail | AutoFocusState::Cancel) {
+                    return Some(event.clone());
+                }
+            }
+        })
+        .await;
+        let event = match finished {
+            Ok(Some(event)) => event,
+            Ok(None) => return Err("Connection closed".into()),
+            Err(_) => {
+                let _ = self.stop_auto_focus().await;
+                return Err("auto focus timeout".into());
+            }
+        };
+
+        match event.state {
+            AutoFocusState::Complete => event.result.ok_or_else(|| "auto focus result is missing".into()),
+            AutoFocusState::Cancel => Err("auto focus cancelled".into()),
+            _ => Err(event.error.unwrap_or_else(|| "auto focus failed".to_string()).into()),
+        }
+    }
+
+    /// Start an autofocus, the progress is reported by `AutoFocusEvent` events
+    pub async fn start_auto_focus(
+        &mut self,
+        step: i32,
+        points: u32,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let method = "start_auto_focus";
+        let params = Some(serde_json::json!([ step, points ]));
+        self.rpc_request_4700(method, params).await?;
+        Ok(())
+    }
+
+    pub async fn stop_auto_focus(
+        &mut self,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let method = "stop_auto_focus";
+        self.rpc_request_4700(method, None).await?;
+        Ok(())
+    }
+}
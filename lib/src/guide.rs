@@ -0,0 +1,189 @@
+use super::ASIAir;
+use super::Timestamped;
+use serde::Deserialize;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+// Longest time a dither is waited for to settle
+const SETTLE_TIMEOUT: Duration = Duration::from_secs(120);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GuideState {
+    #[default]
+    Idle,
+    Start,
+    // A guide frame was measured and corrected
+    Guiding,
+    // Guide frame taken while waiting for the star to settle after a dither
+    Settling,
+    Settled,
+    SettleFailed,
+    // The guide star can't be found in the frame
+    Lost,
+    Stop,
+}
+
+/// Guide frame or change of the guiding state. Errors are in arcseconds toward east and north,
+/// pulses in milliseconds, positive toward east and north
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct GuideEvent {
+    pub state: GuideState,
+    pub frame: u64,
+    #[serde(default)]
+    pub ra_error: f64,
+    #[serde(default)]
+    pub dec_error: f64,
+    #[serde(default)]
+    pub ra_pulse: f64,
+    #[serde(default)]
+    pub dec_pulse: f64,
+    // RMS of the errors on the last frames, in arcseconds
+    #[serde(default)]
+    pub rms_ra: f64,
+    #[serde(default)]
+    pub rms_dec: f64,
+    #[serde(default)]
+    pub rms_total: f64,
+    // Reason of a lost star or of a failed settle
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct GuideStatus {
+    pub state: GuideState,
+    // Number of guide frames taken since guiding started
+    pub frame: u64,
+    pub rms_ra: f64,
+    pub rms_dec: f64,
+    pub rms_total: f64,
+}
+
+impl ASIAir {
+    /// Start guiding on the current position, the mount has to be tracking.
+    /// Every guide frame is reported by a `GuideEvent` event
+    pub async fn guide_start(
+        &mut self,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let method = "start_guide";
+        self.rpc_request_4700(method, None).await?;
+        Ok(())
+    }
+
+    pub async fn guide_stop(
+        &mut self,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let method = "stop_guide";
+        self.rpc_request_4700(method, None).await?;
+        Ok(())
+    }
+
+    pub async fn guide_get_state(
+        &mut self,
+    ) -> Result<GuideStatus, Box<dyn std::error::Error + Send + Sync>> {
+        let method = "get_guide_state";
+        let result = self.rpc_request_4700(method, None).await?;
+
+        let status: GuideStatus = serde_json::from_value(result)?;
+        Ok(status)
+    }
+
+    /// `guide_get_state` with the Timestamp of the response
+    pub async fn guide_get_state_timestamped(
+        &mut self,
+    ) -> Result<Timestamped<GuideStatus>, Box<dyn std::error::Error + Send + Sync>> {
+        let method = "get_guide_state";
+        let response = self.rpc_request_4700_timestamped(method, None).await?;
+
+        Ok(response.deserialize()?)
+    }
+
+    /// Stream of the guide frames, with their errors and pulses, until guiding stops
+    pub fn guide_steps(&self) -> mpsc::Receiver<GuideEvent> {
+        let mut guide_rx = self.subscribe_events_of::<GuideEvent>("Guide");
+
+        let (tx, rx) = mpsc::channel(64);
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    _ = tx.closed() => break,
+                    event = guide_rx.recv() => {
+                        let Some(event) = event else {
+                            break;
+                        };
+                        match event.state {
+                            GuideState::Stop => break,
+                            GuideState::Guiding | GuideState::Settling | GuideState::Lost => {
+                                let _ = tx.send(event.value).await;
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+            }
+        });
+
+        rx
+    }
+
+    /// Request a random offset of the guiding lock position of up to `pixels`
+    /// pixels, and wait for the device to accept it.
+    pub async fn dither(
+        &mut self,
+        pixels: f64,
+        ra_only: bool,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let method = "dither";
+        let params = Some(serde_json::json!([ pixels, ra_only ]));
+        let response = self.rpc_request_4700(method, params).await;
+        if let Ok(value) = response {
+            if value.as_i64() == Some(0) {
+                Ok(())
+            } else {
+                Err("unexpected response".into())
+            }
+        } else {
+            response.map(|_| ()).map_err(|e| {
+                log::debug!("{} failed: {}", method, e);
+                e
+            })
+        }
+    }
+
+    /// Dither, then wait for the guide star to settle on the new lock position.
+    /// Returns immediately when not guiding
+    pub async fn dither_and_settle(
+        &mut self,
+        pixels: f64,
+        ra_only: bool,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let mut guide_rx = self.subscribe_events_of::<GuideEvent>("Guide");
+
+        self.dither(pixels, ra_only).await?;
+        if self.guide_get_state().await?.state == GuideState::Idle {
+            return Ok(());
+        }
+
+        let settled = tokio::time::timeout(SETTLE_TIMEOUT, async {
+            loop {
+                let Some(event) = guide_rx.recv().await else {
+                    return Err("Connection closed".to_string());
+                };
+                match event.state {
+                    GuideState::Settled => return Ok(()),
+                    GuideState::SettleFailed => {
+                        return Err(event.error.clone().unwrap_or_else(|| "settle failed".to_string()));
+                    }
+                    GuideState::Stop => return Err("guiding stopped".to_string()),
+                    _ => {}
+                }
+            }
+        })
+        .await;
+        match settled {
+            Ok(result) => result.map_err(|e| e.into()),
+            Err(_) => Err("settle timeout".into()),
+        }
+    }
+}
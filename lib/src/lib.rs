@@ -1,19 +1,132 @@
 mod connection;
+mod fits;
 mod settings;
+pub mod annotate;
+pub mod app;
+pub mod auto_exp;
 pub mod camera;
+pub mod clock;
+pub mod find_star;
+pub mod focuser;
+pub mod guide;
+pub mod merid_flip;
+pub mod mount;
+pub mod plan;
+pub mod polar;
+pub mod record;
+pub mod rtmp;
+pub mod sequencer;
+pub mod solve;
+pub mod stack;
+pub mod storage;
+
+pub use settings::{Settings, TemperatureUnit, WifiBand};
 
 use serde::{Serialize, Deserialize};
+use serde::de::DeserializeOwned;
 use byteorder::{BigEndian, ByteOrder};
+use chrono::{DateTime, FixedOffset};
 use serde_json::Value;
 use std::collections::HashMap;
+use std::marker::PhantomData;
 use std::net::Ipv4Addr;
 use std::str::FromStr;
 use std::sync::{Arc, Mutex, atomic::AtomicBool};
-use tokio::sync::{mpsc, oneshot, watch};
+use tokio::sync::{broadcast, mpsc, oneshot, watch};
 use tokio::time::Duration;
 
 type Responder<T> = oneshot::Sender<Result<T, Box<dyn std::error::Error + Send + Sync>>>;
 
+/// Response or event of the device with the time of the clock of the device when it was sent,
+/// None when the device sent no Timestamp or one that can't be parsed
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Timestamped<T> {
+    pub timestamp: Option<DateTime<FixedOffset>>,
+    pub value: T,
+}
+
+impl Timestamped<Value> {
+    // Deserialize the value of a response, keeping its timestamp
+    fn deserialize<T: DeserializeOwned>(self) -> Result<Timestamped<T>, serde_json::Error> {
+        Ok(Timestamped {
+            timestamp: self.timestamp,
+            value: serde_json::from_value(self.value)?,
+        })
+    }
+}
+
+impl<T> std::ops::Deref for Timestamped<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+// Parse the Timestamp of a response or an event
+fn parse_timestamp(message: &Value) -> Option<DateTime<FixedOffset>> {
+    message
+        .get("Timestamp")
+        .and_then(|timestamp| timestamp.as_str())
+        .and_then(|timestamp| DateTime::parse_from_rfc3339(timestamp).ok())
+}
+
+// Events of one kind from the broadcast channel, deserialized. Unlike the watch channels, which
+// only keep the last event, every event is received even when several come in a row
+struct EventReceiver<T> {
+    events_rx: broadcast::Receiver<Timestamped<Value>>,
+    name: &'static str,
+    _event: PhantomData<T>,
+}
+
+impl<T: DeserializeOwned> EventReceiver<T> {
+    // Next event, None once the connection is dropped
+    async fn recv(&mut self) -> Option<Timestamped<T>> {
+        loop {
+            match self.events_rx.recv().await {
+                Ok(event) if event.value["Event"] == self.name => match event.deserialize() {
+                    Ok(event) => return Some(event),
+                    Err(e) => log::warn!("Invalid {} event: {}", self.name, e),
+                },
+                Ok(_) => {}
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    log::warn!("{} events missed while waiting for {}", missed, self.name);
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    }
+}
+
+/// Failure reported by the device in the `error` field of a response, as opposed to a failure
+/// to reach the device
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeviceError {
+    pub reason: String,
+}
+
+impl std::fmt::Display for DeviceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.reason)
+    }
+}
+
+impl std::error::Error for DeviceError {}
+
+impl DeviceError {
+    // The reason of a failure reported by the device becomes the error `from_reason` makes of it,
+    // failures to reach the device are passed through unchanged
+    pub(crate) fn map_reason<E: std::error::Error + Send + Sync + 'static>(
+        error: Box<dyn std::error::Error + Send + Sync>,
+        from_reason: fn(&str) -> E,
+    ) -> Box<dyn std::error::Error + Send + Sync> {
+        match error.downcast::<DeviceError>() {
+            Ok(error) => Box::new(from_reason(&error.reason)),
+            Err(error) => error,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct BinaryResult {
     pub data: Vec<u8>,
@@ -31,7 +144,7 @@ enum ASIAirCommand {
     Get {
         method: String,
         params: Option<Value>,
-        tx: Responder<Value>,
+        tx: Responder<Timestamped<Value>>,
     },
     Set {
         method: String,
@@ -39,13 +152,32 @@ enum ASIAirCommand {
     },
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ASIAirLanguage {
     #[default]
+    #[serde(rename = "en")]
     English,
+    #[serde(rename = "zh_CN")]
+    SimplifiedChinese,
+    #[serde(rename = "zh_TW")]
+    TraditionalChinese,
+    #[serde(rename = "ja")]
+    Japanese,
+    #[serde(rename = "ko")]
+    Korean,
+    #[serde(rename = "de")]
+    German,
+    #[serde(rename = "fr")]
+    French,
+    #[serde(rename = "es")]
+    Spanish,
+    #[serde(rename = "it")]
+    Italian,
+    #[serde(rename = "ru")]
+    Russian,
 }
 
-#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum ASIAirPage {
     #[default]
@@ -90,7 +222,7 @@ impl FromStr for ASIAirPage {
 }
 
 
-#[derive(Default, Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "state")]
 pub enum ExposureEvent {
     #[serde(rename = "start")]
@@ -101,6 +233,12 @@ pub enum ExposureEvent {
     },
     #[serde(rename = "downloading")]
     Downloading,
+    #[serde(rename = "cancel")]
+    Cancel,
+    #[serde(rename = "fail")]
+    Fail {
+        error: String,
+    },
     #[default]
     #[serde(rename = "complete")]
     Complete
@@ -196,7 +334,7 @@ pub struct ASIAir {
     tx_4800: Option<mpsc::Sender<ASIAirCommand>>,
 
     // Map of pending responses, keyed by request ID
-    pending_responses: Arc<Mutex<HashMap<u32, Responder<Value>>>>,
+    pending_responses: Arc<Mutex<HashMap<u32, Responder<Timestamped<Value>>>>>,
     // Map of pending responses, keyed by request ID
     pending_responses_4500: Arc<Mutex<HashMap<u32, Responder<Value>>>>,
     // Map of pending responses, keyed by request ID
@@ -210,16 +348,62 @@ pub struct ASIAir {
 
     // Tracks if the ASIAir is connected
     pub connected: Arc<AtomicBool>,
+    // Reopen the main camera when it is plugged back in
+    reopen_main_camera: Arc<AtomicBool>,
+    // Periodic check of the clock of the device, off when None
+    clock_sync_tx: watch::Sender<Option<clock::ClockSyncConfig>>,
 
     // Channels to publish events
     // Publicly accessible channel for connection state
     pub connection_state_tx: watch::Sender<bool>,
+    // Every event of the device in the order it was received, with its timestamp
+    pub events_tx: broadcast::Sender<Timestamped<Value>>,
     pub camera_temperature_tx: watch::Sender<f32>,
     pub camera_state_change_tx: watch::Sender<()>,
+    pub camera_list_tx: watch::Sender<camera::CameraListEvent>,
     pub cooler_power_tx: watch::Sender<i32>,
     pub camera_control_change_tx: watch::Sender<()>,
     pub exposure_tx: watch::Sender<ExposureEvent>,
     pub pi_status_tx: watch::Sender<PiStatusEvent>,
     pub annotate_tx: watch::Sender<AnnotateEvent>,
     pub plate_solve_tx: watch::Sender<PlateSolveEvent>,
+    pub plan_tx: watch::Sender<plan::PlanEvent>,
+    pub mount_tx: watch::Sender<mount::MountEvent>,
+    pub polar_align_tx: watch::Sender<polar::PolarAlignEvent>,
+    pub focuser_tx: watch::Sender<focuser::FocuserEvent>,
+    pub auto_focus_tx: watch::Sender<focuser::AutoFocusEvent>,
+    pub guide_tx: watch::Sender<guide::GuideEvent>,
+    pub merid_flip_tx: watch::Sender<merid_flip::MeridFlipEvent>,
+    pub stack_tx: watch::Sender<stack::StackEvent>,
+    pub format_drive_tx: watch::Sender<storage::FormatDriveEvent>,
+    pub export_tx: watch::Sender<storage::ExportEvent>,
+    pub avi_record_tx: watch::Sender<record::AviRecordEvent>,
+    pub rtmp_tx: watch::Sender<rtmp::RtmpEvent>,
+    pub auto_exp_tx: watch::Sender<auto_exp::AutoExpEvent>,
+    pub find_star_tx: watch::Sender<find_star::FindStarEvent>,
+    // The same events with the timestamp of the device
+    pub camera_temperature_timestamped_tx: watch::Sender<Timestamped<f32>>,
+    pub camera_state_change_timestamped_tx: watch::Sender<Timestamped<()>>,
+    pub camera_list_timestamped_tx: watch::Sender<Timestamped<camera::CameraListEvent>>,
+    pub cooler_power_timestamped_tx: watch::Sender<Timestamped<i32>>,
+    pub camera_control_change_timestamped_tx: watch::Sender<Timestamped<()>>,
+    pub exposure_timestamped_tx: watch::Sender<Timestamped<ExposureEvent>>,
+    pub pi_status_timestamped_tx: watch::Sender<Timestamped<PiStatusEvent>>,
+    pub annotate_timestamped_tx: watch::Sender<Timestamped<AnnotateEvent>>,
+    pub plate_solve_timestamped_tx: watch::Sender<Timestamped<PlateSolveEvent>>,
+    pub plan_timestamped_tx: watch::Sender<Timestamped<plan::PlanEvent>>,
+    pub mount_timestamped_tx: watch::Sender<Timestamped<mount::MountEvent>>,
+    pub polar_align_timestamped_tx: watch::Sender<Timestamped<polar::PolarAlignEvent>>,
+    pub focuser_timestamped_tx: watch::Sender<Timestamped<focuser::FocuserEvent>>,
+    pub auto_focus_timestamped_tx: watch::Sender<Timestamped<focuser::AutoFocusEvent>>,
+    pub guide_timestamped_tx: watch::Sender<Timestamped<guide::GuideEvent>>,
+    pub merid_flip_timestamped_tx: watch::Sender<Timestamped<merid_flip::MeridFlipEvent>>,
+    pub stack_timestamped_tx: watch::Sender<Timestamped<stack::StackEvent>>,
+    pub format_drive_timestamped_tx: watch::Sender<Timestamped<storage::FormatDriveEvent>>,
+    pub export_timestamped_tx: watch::Sender<Timestamped<storage::ExportEvent>>,
+    pub avi_record_timestamped_tx: watch::Sender<Timestamped<record::AviRecordEvent>>,
+    pub rtmp_timestamped_tx: watch::Sender<Timestamped<rtmp::RtmpEvent>>,
+    pub auto_exp_timestamped_tx: watch::Sender<Timestamped<auto_exp::AutoExpEvent>>,
+    pub find_star_timestamped_tx: watch::Sender<Timestamped<find_star::FindStarEvent>>,
+    pub clock_offset_tx: watch::Sender<clock::ClockOffset>,
 }
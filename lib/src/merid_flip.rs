@@ -0,0 +1,95 @@
+use super::ASIAir;
+use serde::Deserialize;
+
+/// When the device flips the mount to the other side of the pier
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MeridFlipSettings {
+    pub auto_flip: bool,
+    // Time the mount tracks past the meridian before flipping, in minutes
+    pub minutes_past_meridian: f64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MeridFlipState {
+    #[default]
+    Idle,
+    // Capture and guiding are stopped
+    Start,
+    // Slewing to the other side of the pier
+    Flip,
+    // Plate solving and centering the target again
+    Solve,
+    Center,
+    // Restarting guiding
+    Guide,
+    // The flip is done and capture can resume
+    Complete,
+    Fail,
+    Cancel,
+}
+
+impl MeridFlipState {
+    /// True while a flip is in progress and capture is suspended
+    pub fn is_working(&self) -> bool {
+        matches!(
+            self,
+            MeridFlipState::Start
+                | MeridFlipState::Flip
+                | MeridFlipState::Solve
+                | MeridFlipState::Center
+                | MeridFlipState::Guide
+        )
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct MeridFlipEvent {
+    pub state: MeridFlipState,
+    // Reason of a failure
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+impl ASIAir {
+    pub async fn merid_flip_get_settings(
+        &mut self,
+    ) -> Result<MeridFlipSettings, Box<dyn std::error::Error + Send + Sync>> {
+        let setting = self.get_app_setting().await?;
+        Ok(MeridFlipSettings {
+            auto_flip: setting.auto_merid_flip,
+            minutes_past_meridian: setting.merid_flip_minutes,
+        })
+    }
+
+    pub async fn merid_flip_set_settings(
+        &mut self,
+        settings: MeridFlipSettings,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let method = "set_app_setting";
+        let params = Some(serde_json::json!([ {
+            "auto_merid_flip": settings.auto_flip,
+            "merid_flip_minutes": settings.minutes_past_meridian,
+        } ]));
+        self.rpc_request_4700(method, params).await?;
+        Ok(())
+    }
+
+    /// Flip the mount now, it has to track a target past the meridian.
+    /// The progress is reported by `MeridFlipEvent` events
+    pub async fn start_merid_flip(
+        &mut self,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let method = "start_merid_flip";
+        self.rpc_request_4700(method, None).await?;
+        Ok(())
+    }
+
+    pub async fn stop_merid_flip(
+        &mut self,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let method = "stop_merid_flip";
+        self.rpc_request_4700(method, None).await?;
+        Ok(())
+    }
+}
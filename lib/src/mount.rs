@@ -0,0 +1,263 @@
+use super::ASIAir;
+use super::Timestamped;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrackMode {
+    #[default]
+    Sidereal,
+    Lunar,
+    Solar,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PierSide {
+    // Telescope on the east side of the pier, pointing west of the meridian
+    East,
+    // Telescope on the west side of the pier, pointing east of the meridian
+    #[default]
+    West,
+}
+
+/// Where the mount points, as reported by position events
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct MountPosition {
+    // Right ascension in hours
+    pub ra: f64,
+    // Declination in degrees
+    pub dec: f64,
+    // Altitude above the horizon in degrees
+    pub alt: f64,
+    pub pier_side: PierSide,
+    pub tracking: bool,
+    pub slewing: bool,
+    pub parked: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct MountState {
+    #[serde(flatten)]
+    pub position: MountPosition,
+    // Hour angle of the current position in hours, negative east of the meridian
+    pub hour_angle: f64,
+    pub track_mode: TrackMode,
+    // Slew speed in degrees per second
+    pub slew_rate: f64,
+    // Site location in degrees, east longitudes are positive
+    pub latitude: f64,
+    pub longitude: f64,
+    // Lowest altitude the mount slews or tracks to, in degrees
+    pub min_altitude: f64,
+    // How far past the meridian the mount tracks, in minutes
+    pub meridian_limit_min: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GotoState {
+    Start,
+    Complete,
+    Abort,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ParkState {
+    Start,
+    Complete,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrackReason {
+    // Tracking changed on request
+    User,
+    // Tracking stopped before the telescope hits the pier
+    Meridian,
+    // Tracking stopped at the altitude limit
+    Horizon,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "Event")]
+pub enum MountEvent {
+    #[serde(rename = "ScopeGoto")]
+    Goto { state: GotoState, ra: f64, dec: f64 },
+    #[serde(rename = "ScopePark")]
+    Park { state: ParkState },
+    #[serde(rename = "ScopePosition")]
+    Position(MountPosition),
+    #[serde(rename = "ScopeTrack")]
+    Track { tracking: bool, reason: TrackReason },
+}
+
+impl Default for MountEvent {
+    fn default() -> Self {
+        MountEvent::Position(MountPosition::default())
+    }
+}
+
+impl ASIAir {
+    /// Slew to a position, right ascension in hours and declination in degrees.
+    /// Returns once the slew started, the end of the slew is reported by a `MountEvent::Goto` event
+    pub async fn mount_goto(
+        &mut self,
+        ra: f64,
+        dec: f64,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let method = "scope_goto";
+        let params = Some(serde_json::json!([ ra, dec ]));
+        self.rpc_request_4700(method, params).await?;
+        Ok(())
+    }
+
+    /// Slew to a named target, the frames taken after the goto are saved under that name
+    pub async fn mount_goto_named(
+        &mut self,
+        ra: f64,
+        dec: f64,
+        name: &str,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let method = "scope_goto";
+        let params = Some(serde_json::json!([ ra, dec, name ]));
+        self.rpc_request_4700(method, params).await?;
+        Ok(())
+    }
+
+    pub async fn mount_abort(
+        &mut self,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let method = "scope_abort_slew";
+        self.rpc_request_4700(method, None).await?;
+        Ok(())
+    }
+
+    pub async fn mount_park(
+        &mut self,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let method = "scope_park";
+        self.rpc_request_4700(method, None).await?;
+        Ok(())
+    }
+
+    pub async fn mount_unpark(
+        &mut self,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let method = "scope_unpark";
+        self.rpc_request_4700(method, None).await?;
+        Ok(())
+    }
+
+    pub async fn mount_get_position(
+        &mut self,
+    ) -> Result<(f64, f64), Box<dyn std::error::Error + Send + Sync>> {
+        let method = "scope_get_equ_coord";
+        let result = self.rpc_request_4700(method, None).await?;
+
+        let ra: f64 = serde_json::from_value(result["ra"].clone())?;
+        let dec: f64 = serde_json::from_value(result["dec"].clone())?;
+        Ok((ra, dec))
+    }
+
+    /// `mount_get_position` with the Timestamp of the response
+    pub async fn mount_get_position_timestamped(
+        &mut self,
+    ) -> Result<Timestamped<(f64, f64)>, Box<dyn std::error::Error + Send + Sync>> {
+        let method = "scope_get_equ_coord";
+        let response = self.rpc_request_4700_timestamped(method, None).await?;
+
+        let ra: f64 = serde_json::from_value(response.value["ra"].clone())?;
+        let dec: f64 = serde_json::from_value(response.value["dec"].clone())?;
+        Ok(Timestamped {
+            timestamp: response.timestamp,
+            value: (ra, dec),
+        })
+    }
+
+    pub async fn mount_get_state(
+        &mut self,
+    ) -> Result<MountState, Box<dyn std::error::Error + Send + Sync>> {
+        let method = "scope_get_state";
+        let result = self.rpc_request_4700(method, None).await?;
+
+        let state: MountState = serde_json::from_value(result)?;
+        Ok(state)
+    }
+
+    /// `mount_get_state` with the Timestamp of the response
+    pub async fn mount_get_state_timestamped(
+        &mut self,
+    ) -> Result<Timestamped<MountState>, Box<dyn std::error::Error + Send + Sync>> {
+        let method = "scope_get_state";
+        let response = self.rpc_request_4700_timestamped(method, None).await?;
+
+        Ok(response.deserialize()?)
+    }
+
+    pub async fn mount_get_tracking(
+        &mut self,
+    ) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+        let method = "scope_get_track_state";
+        let result = self.rpc_request_4700(method, None).await?;
+
+        let tracking: bool = serde_json::from_value(result)?;
+        Ok(tracking)
+    }
+
+    pub async fn mount_set_tracking(
+        &mut self,
+        tracking: bool,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let method = "scope_set_track_state";
+        let params = Some(serde_json::json!([ tracking ]));
+        self.rpc_request_4700(method, params).await?;
+        Ok(())
+    }
+
+    pub async fn mount_set_track_mode(
+        &mut self,
+        mode: TrackMode,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let method = "scope_set_track_mode";
+        let params = Some(serde_json::json!([ mode ]));
+        self.rpc_request_4700(method, params).await?;
+        Ok(())
+    }
+
+    /// Set the slew speed in degrees per second
+    pub async fn mount_set_slew_rate(
+        &mut self,
+        rate: f64,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let method = "scope_set_slew_rate";
+        let params = Some(serde_json::json!([ rate ]));
+        self.rpc_request_4700(method, params).await?;
+        Ok(())
+    }
+
+    /// Set the site location in degrees, east longitudes are positive
+    pub async fn mount_set_location(
+        &mut self,
+        latitude: f64,
+        longitude: f64,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let method = "scope_set_location";
+        let params = Some(serde_json::json!([ latitude, longitude ]));
+        self.rpc_request_4700(method, params).await?;
+        Ok(())
+    }
+
+    /// Set the lowest altitude in degrees, and how far past the meridian in minutes the mount can go
+    pub async fn mount_set_limits(
+        &mut self,
+        min_altitude: f64,
+        meridian_limit_min: f64,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let method = "scope_set_limits";
+        let params = Some(serde_json::json!([ min_altitude, meridian_limit_min ]));
+        self.rpc_request_4700(method, params).await?;
+        Ok(())
+    }
+}
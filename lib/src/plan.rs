@@ -0,0 +1,108 @@
+use super::ASIAir;
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlanExposure {
+    pub filter: String,
+    pub exp_us: u64,
+    pub count: u32,
+    // Number of frames already captured, updated by the device while the plan runs
+    #[serde(default)]
+    pub done: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlanTarget {
+    pub name: String,
+    // Right ascension in hours
+    pub ra: f64,
+    // Declination in degrees
+    pub dec: f64,
+    pub start_time: DateTime<FixedOffset>,
+    pub end_time: DateTime<FixedOffset>,
+    pub exposures: Vec<PlanExposure>,
+}
+
+/// A named list of targets, imaged in order by the device during their time window
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Plan {
+    pub name: String,
+    pub targets: Vec<PlanTarget>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PlanState {
+    #[default]
+    Idle,
+    // Waiting for the time window of the target to start
+    Waiting,
+    Goto,
+    Working,
+    // Target skipped because its time window is over
+    Skip,
+    Complete,
+    Stop,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct PlanEvent {
+    pub state: PlanState,
+    pub plan: String,
+    pub target: String,
+    pub filter: String,
+    pub done: u32,
+    pub count: u32,
+}
+
+impl ASIAir {
+    /// Store a plan on the device, replacing any plan with the same name
+    pub async fn create_plan(
+        &mut self,
+        plan: &Plan,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let method = "set_plan";
+        let params = Some(serde_json::json!([ plan ]));
+        self.rpc_request_4700(method, params).await?;
+        Ok(())
+    }
+
+    pub async fn list_plans(
+        &mut self,
+    ) -> Result<Vec<Plan>, Box<dyn std::error::Error + Send + Sync>> {
+        let method = "get_plan_list";
+        let result = self.rpc_request_4700(method, None).await?;
+
+        let plans: Vec<Plan> = serde_json::from_value(result)?;
+        Ok(plans)
+    }
+
+    pub async fn delete_plan(
+        &mut self,
+        name: &str,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let method = "delete_plan";
+        let params = Some(serde_json::json!([ name ]));
+        self.rpc_request_4700(method, params).await?;
+        Ok(())
+    }
+
+    pub async fn start_plan(
+        &mut self,
+        name: &str,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let method = "start_plan";
+        let params = Some(serde_json::json!([ name ]));
+        self.rpc_request_4700(method, params).await?;
+        Ok(())
+    }
+
+    pub async fn stop_plan(
+        &mut self,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let method = "stop_plan";
+        self.rpc_request_4700(method, None).await?;
+        Ok(())
+    }
+}
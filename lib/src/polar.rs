@@ -0,0 +1,112 @@
+use super::ASIAir;
+use super::Timestamped;
+use serde::Deserialize;
+use tokio::sync::mpsc;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PolarAlignState {
+    #[default]
+    Idle,
+    Start,
+    // Capturing and solving the frame of a step
+    Capture,
+    // Rotating the RA axis to the position of the next step
+    Rotate,
+    // New measure of the polar axis error
+    Update,
+    Fail,
+    Stop,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct PolarAlignEvent {
+    pub state: PolarAlignState,
+    pub step: u32,
+    // Polar axis error in arcminutes, only set by update events
+    #[serde(default)]
+    pub alt_error: f64,
+    #[serde(default)]
+    pub az_error: f64,
+    #[serde(default)]
+    pub total_error: f64,
+    // Reason of a failure
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+/// Measure of the polar axis error, in arcminutes.
+/// Positive values mean the axis points above, and east of the pole
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PolarAlignReading {
+    pub alt_error: f64,
+    pub az_error: f64,
+    pub total_error: f64,
+}
+
+impl ASIAir {
+    /// Run the polar alignment routine, returning the stream of error readings.
+    /// The stream ends when the routine is stopped, with an error if it failed.
+    /// Dropping the receiver stops the routine
+    pub async fn polar_align(
+        &mut self,
+    ) -> Result<mpsc::Receiver<Result<PolarAlignReading, Box<dyn std::error::Error + Send + Sync>>>, Box<dyn std::error::Error + Send + Sync>> {
+        let mut polar_align_rx = self.subscribe_events_of::<PolarAlignEvent>("PolarAlign");
+
+        self.start_polar_align().await?;
+
+        let (tx, rx) = mpsc::channel(16);
+        let mut asiair = self.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    _ = tx.closed() => {
+                        let _ = asiair.stop_polar_align().await;
+                        break;
+                    }
+                    event = polar_align_rx.recv() => {
+                        let Some(Timestamped { value: event, .. }) = event else {
+                            break;
+                        };
+                        match event.state {
+                            PolarAlignState::Update => {
+                                let reading = PolarAlignReading {
+                                    alt_error: event.alt_error,
+                                    az_error: event.az_error,
+                                    total_error: event.total_error,
+                                };
+                                let _ = tx.send(Ok(reading)).await;
+                            }
+                            PolarAlignState::Fail => {
+                                let error = event.error.unwrap_or_else(|| "polar alignment failed".to_string());
+                                let _ = tx.send(Err(error.into())).await;
+                                break;
+                            }
+                            PolarAlignState::Stop => break,
+                            _ => {}
+                        }
+                    }
+                }
+            }
+        });
+
+        Ok(rx)
+    }
+
+    /// Start the polar alignment routine, the progress is reported by `PolarAlignEvent` events
+    pub async fn start_polar_align(
+        &mut self,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let method = "start_polar_align";
+        self.rpc_request_4700(method, None).await?;
+        Ok(())
+    }
+
+    pub async fn stop_polar_align(
+        &mut self,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let method = "stop_polar_align";
+        self.rpc_request_4700(method, None).await?;
+        Ok(())
+    }
+}
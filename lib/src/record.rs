@@ -0,0 +1,103 @@
+use super::ASIAir;
+use super::Timestamped;
+use serde::{Deserialize, Serialize};
+
+/// File format of the videos
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VideoFormat {
+    #[default]
+    Ser,
+    Avi,
+}
+
+/// State of the video recording
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+pub struct AviRecordStatus {
+    pub is_working: bool,
+    // Time since the recording started, in seconds
+    pub lapse_sec: u32,
+    // Frames read from the camera, and written to the file, per second
+    pub fps: f32,
+    pub write_file_fps: f32,
+    pub frame_count: u32,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AviRecordState {
+    #[default]
+    Idle,
+    Start,
+    Working,
+    // The video is saved
+    Complete,
+    Fail,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct AviRecordEvent {
+    pub state: AviRecordState,
+    #[serde(default)]
+    pub lapse_sec: u32,
+    #[serde(default)]
+    pub fps: f32,
+    #[serde(default)]
+    pub write_file_fps: f32,
+    #[serde(default)]
+    pub frame_count: u32,
+    // Path of the saved video
+    #[serde(default)]
+    pub path: Option<String>,
+    // Reason of a failure, the frames recorded until then may still be saved
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+impl ASIAir {
+    /// Record a video from the main camera, with its current exposure, until stopped or
+    /// for `duration_sec` seconds. The progress is reported by `AviRecordEvent` events
+    pub async fn record_start(
+        &mut self,
+        format: VideoFormat,
+        duration_sec: Option<f64>,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let method = "start_avi_record";
+        let params = Some(serde_json::json!([ {
+            "format": format,
+            "duration_sec": duration_sec,
+        } ]));
+        self.rpc_request_4700(method, params).await?;
+        Ok(())
+    }
+
+    /// Stop the recording and return the path of the saved video
+    pub async fn record_stop(
+        &mut self,
+    ) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        let method = "stop_avi_record";
+        let result = self.rpc_request_4700(method, None).await?;
+
+        let path: String = serde_json::from_value(result["path"].clone())?;
+        Ok(path)
+    }
+
+    pub async fn record_get_state(
+        &mut self,
+    ) -> Result<AviRecordStatus, Box<dyn std::error::Error + Send + Sync>> {
+        let method = "get_avi_record_state";
+        let result = self.rpc_request_4700(method, None).await?;
+
+        Ok(serde_json::from_value(result)?)
+    }
+
+    /// `record_get_state` with the Timestamp of the response
+    pub async fn record_get_state_timestamped(
+        &mut self,
+    ) -> Result<Timestamped<AviRecordStatus>, Box<dyn std::error::Error + Send + Sync>> {
+        let method = "get_avi_record_state";
+        let response = self.rpc_request_4700_timestamped(method, None).await?;
+
+        Ok(response.deserialize()?)
+    }
+}
@@ -0,0 +1,108 @@
+use super::ASIAir;
+use super::Timestamped;
+use serde::{Deserialize, Serialize};
+
+/// Server the main camera is streamed to
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RtmpConfig {
+    // rtmp://host[:port]/app
+    pub url: String,
+    // Stream key given by the server
+    pub key: String,
+    // Size of the streamed frames, the camera image is scaled to fit
+    pub width: u32,
+    pub height: u32,
+}
+
+/// State of the stream
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub struct RtmpStatus {
+    pub is_working: bool,
+    // Frames sent since the stream started
+    #[serde(default)]
+    pub frames: u32,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RtmpState {
+    #[default]
+    Idle,
+    // Connecting to the server
+    Connect,
+    // The server accepted the stream
+    Start,
+    // A frame was sent
+    Working,
+    Stop,
+    Fail,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct RtmpEvent {
+    pub state: RtmpState,
+    #[serde(default)]
+    pub frames: u32,
+    // Reason of a failure, the stream is over
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+impl ASIAir {
+    /// Set the server and the resolution of the stream, it can't change while streaming
+    pub async fn rtmp_set_config(
+        &mut self,
+        config: &RtmpConfig,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let method = "set_rtmp_config";
+        let params = Some(serde_json::json!([config]));
+        self.rpc_request_4700(method, params).await?;
+        Ok(())
+    }
+
+    pub async fn rtmp_get_config(
+        &mut self,
+    ) -> Result<RtmpConfig, Box<dyn std::error::Error + Send + Sync>> {
+        let method = "get_rtmp_config";
+        let result = self.rpc_request_4700(method, None).await?;
+
+        Ok(serde_json::from_value(result)?)
+    }
+
+    /// Stream the main camera until stopped. The connection to the server happens in the
+    /// background, its outcome is reported by `RtmpEvent` events
+    pub async fn rtmp_start(
+        &mut self,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let method = "start_rtmp";
+        self.rpc_request_4700(method, None).await?;
+        Ok(())
+    }
+
+    pub async fn rtmp_stop(
+        &mut self,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let method = "stop_rtmp";
+        self.rpc_request_4700(method, None).await?;
+        Ok(())
+    }
+
+    pub async fn rtmp_get_state(
+        &mut self,
+    ) -> Result<RtmpStatus, Box<dyn std::error::Error + Send + Sync>> {
+        let method = "get_rtmp_state";
+        let result = self.rpc_request_4700(method, None).await?;
+
+        Ok(serde_json::from_value(result)?)
+    }
+
+    /// `rtmp_get_state` with the Timestamp of the response
+    pub async fn rtmp_get_state_timestamped(
+        &mut self,
+    ) -> Result<Timestamped<RtmpStatus>, Box<dyn std::error::Error + Send + Sync>> {
+        let method = "get_rtmp_state";
+        let response = self.rpc_request_4700_timestamped(method, None).await?;
+
+        Ok(response.deserialize()?)
+    }
+}
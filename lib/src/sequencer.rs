@@ -0,0 +1,451 @@
+use super::ASIAir;
+use super::DeviceError;
+use super::ExposureEvent;
+use super::camera::FrameType;
+use super::merid_flip::MeridFlipState;
+use super::fits::{FitsValue, FitsWriter};
+use std::path::{Path, PathBuf};
+use tokio::sync::watch;
+use tokio::time::Duration;
+
+// Extra time given to the device over the exposure time to download a frame
+const EXPOSURE_TIMEOUT_MARGIN: Duration = Duration::from_secs(60);
+
+/// A block of identical frames to capture
+#[derive(Debug, Clone)]
+pub struct SequenceStep {
+    pub frame_type: FrameType,
+    pub count: u32,
+    pub exposure_us: u64,
+    pub gain: i64,
+    pub bin: u32,
+    // Cooler set point for the step, the cooler is left untouched when None
+    pub target_temperature: Option<f64>,
+    // Dither before every N-th frame of the step, never when None
+    pub dither_every: Option<u32>,
+}
+
+impl Default for SequenceStep {
+    fn default() -> Self {
+        SequenceStep {
+            frame_type: FrameType::Light,
+            count: 1,
+            exposure_us: 1000000,
+            gain: 0,
+            bin: 1,
+            target_temperature: None,
+            dither_every: None,
+        }
+    }
+}
+
+/// Declarative description of an imaging session, run in order by a `Sequencer`
+///
+/// Frames are written in `output_dir` with a name built from `name_template`,
+/// where the following placeholders are replaced:
+/// - `{type}`: frame type (Light, Dark, Flat, Bias)
+/// - `{exposure}`: exposure time in seconds
+/// - `{gain}`: camera gain
+/// - `{bin}`: binning
+/// - `{index}`: 4 digits frame number in the whole sequence, starting at 1
+#[derive(Debug, Clone)]
+pub struct SequencePlan {
+    pub steps: Vec<SequenceStep>,
+    pub output_dir: PathBuf,
+    pub name_template: String,
+    // Maximum dither offset in pixels
+    pub dither_pixels: f64,
+}
+
+impl Default for SequencePlan {
+    fn default() -> Self {
+        SequencePlan {
+            steps: Vec::new(),
+            output_dir: PathBuf::from("."),
+            name_template: "{type}_{exposure}s_Bin{bin}_gain{gain}_{index}.fit".to_string(),
+            dither_pixels: 5.0,
+        }
+    }
+}
+
+impl SequencePlan {
+    pub fn total_frames(&self) -> u32 {
+        self.steps.iter().map(|step| step.count).sum()
+    }
+
+    fn file_name(&self, step: &SequenceStep, index: u32) -> PathBuf {
+        let frame_type = match step.frame_type {
+            FrameType::Light => "Light",
+            FrameType::Dark => "Dark",
+            FrameType::Flat => "Flat",
+            FrameType::Bias => "Bias",
+        };
+        let name = self
+            .name_template
+            .replace("{type}", frame_type)
+            .replace("{exposure}", &format!("{}", step.exposure_us as f64 / 1e6))
+            .replace("{gain}", &step.gain.to_string())
+            .replace("{bin}", &step.bin.to_string())
+            .replace("{index}", &format!("{:04}", index));
+        self.output_dir.join(name)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum SequenceState {
+    #[default]
+    Idle,
+    Running,
+    Paused,
+    Dithering,
+    // Waiting for the device to flip the mount, the frame interrupted by the flip is captured again after
+    MeridianFlip,
+    Aborted,
+    Completed,
+    Failed(String),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SequenceProgress {
+    pub state: SequenceState,
+    // Index of the step being run
+    pub step: usize,
+    // Frame being captured in the current step, starting at 1
+    pub step_frame: u32,
+    pub completed_frames: u32,
+    pub total_frames: u32,
+    pub last_file: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CaptureOutcome {
+    Saved,
+    Aborted,
+    // The device canceled the exposure to flip the mount
+    Interrupted,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SequenceControl {
+    Run,
+    Pause,
+    Abort,
+}
+
+/// Runs a `SequencePlan` against an ASIAir.
+///
+/// The sequencer can be cloned and shared with other tasks to pause, resume or
+/// abort a running sequence, and to follow its progress.
+#[derive(Debug, Clone)]
+pub struct Sequencer {
+    asiair: ASIAir,
+    plan: SequencePlan,
+    control_tx: watch::Sender<SequenceControl>,
+    progress_tx: watch::Sender<SequenceProgress>,
+}
+
+impl Sequencer {
+    pub fn new(asiair: ASIAir, plan: SequencePlan) -> Self {
+        let (control_tx, _) = watch::channel(SequenceControl::Run);
+        let (progress_tx, _) = watch::channel(SequenceProgress {
+            total_frames: plan.total_frames(),
+            ..Default::default()
+        });
+
+        Sequencer {
+            asiair,
+            plan,
+            control_tx,
+            progress_tx,
+        }
+    }
+
+    pub fn plan(&self) -> &SequencePlan {
+        &self.plan
+    }
+
+    /// Pause the sequence once the frame being captured is saved
+    pub fn pause(&self) {
+        self.control_tx.send_replace(SequenceControl::Pause);
+    }
+
+    pub fn resume(&self) {
+        self.control_tx.send_replace(SequenceControl::Run);
+    }
+
+    /// Abort the sequence, canceling the exposure in progress
+    pub fn abort(&self) {
+        self.control_tx.send_replace(SequenceControl::Abort);
+    }
+
+    pub fn progress(&self) -> SequenceProgress {
+        self.progress_tx.borrow().clone()
+    }
+
+    pub fn subscribe_progress(&self) -> watch::Receiver<SequenceProgress> {
+        self.progress_tx.subscribe()
+    }
+
+    /// Run the whole plan, returning the final progress once it is completed or aborted
+    pub async fn run(&self) -> Result<SequenceProgress, Box<dyn std::error::Error + Send + Sync>> {
+        let mut asiair = self.asiair.clone();
+        let mut control_rx = self.control_tx.subscribe();
+
+        let mut progress = SequenceProgress {
+            state: SequenceState::Running,
+            total_frames: self.plan.total_frames(),
+            ..Default::default()
+        };
+        self.progress_tx.send_replace(progress.clone());
+
+        let result = self.run_steps(&mut asiair, &mut control_rx, &mut progress).await;
+
+        // Leave the sequencer ready to be run again
+        self.control_tx.send_replace(SequenceControl::Run);
+
+        match result {
+            Ok(()) => Ok(progress),
+            Err(e) => {
+                log::warn!("Sequence failed: {}", e);
+                progress.state = SequenceState::Failed(e.to_string());
+                self.progress_tx.send_replace(progress);
+                Err(e)
+            }
+        }
+    }
+
+    async fn run_steps(
+        &self,
+        asiair: &mut ASIAir,
+        control_rx: &mut watch::Receiver<SequenceControl>,
+        progress: &mut SequenceProgress,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        tokio::fs::create_dir_all(&self.plan.output_dir).await?;
+
+        for (step_index, step) in self.plan.steps.iter().enumerate() {
+            progress.step = step_index;
+            progress.step_frame = 0;
+            self.progress_tx.send_replace(progress.clone());
+
+            Self::configure_step(asiair, step).await?;
+
+            for frame in 0..step.count {
+                if !self.wait_while_paused(control_rx, progress).await {
+                    self.finish(progress, SequenceState::Aborted);
+                    return Ok(());
+                }
+
+                let dither = step
+                    .dither_every
+                    .is_some_and(|every| every > 0 && frame > 0 && frame.is_multiple_of(every));
+                if dither {
+                    progress.state = SequenceState::Dithering;
+                    self.progress_tx.send_replace(progress.clone());
+                    asiair.dither_and_settle(self.plan.dither_pixels, false).await?;
+                    progress.state = SequenceState::Running;
+                }
+
+                progress.step_frame = frame + 1;
+                self.progress_tx.send_replace(progress.clone());
+
+                let path = self.plan.file_name(step, progress.completed_frames + 1);
+                loop {
+                    if !self.wait_for_merid_flip(asiair, control_rx, progress).await? {
+                        self.finish(progress, SequenceState::Aborted);
+                        return Ok(());
+                    }
+                    match Self::capture_frame(asiair, step, control_rx, &path).await? {
+                        CaptureOutcome::Saved => break,
+                        CaptureOutcome::Aborted => {
+                            self.finish(progress, SequenceState::Aborted);
+                            return Ok(());
+                        }
+                        CaptureOutcome::Interrupted => log::info!("Frame interrupted by a meridian flip"),
+                    }
+                }
+
+                log::info!("Sequence frame saved to {}", path.display());
+                progress.completed_frames += 1;
+                progress.last_file = Some(path);
+                self.progress_tx.send_replace(progress.clone());
+            }
+        }
+
+        self.finish(progress, SequenceState::Completed);
+        Ok(())
+    }
+
+    fn finish(&self, progress: &mut SequenceProgress, state: SequenceState) {
+        progress.state = state;
+        self.progress_tx.send_replace(progress.clone());
+    }
+
+    async fn configure_step(
+        asiair: &mut ASIAir,
+        step: &SequenceStep,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        asiair.main_camera_set_exposure(step.exposure_us).await?;
+        asiair.main_camera_set_gain(step.gain).await?;
+        asiair.main_camera_set_bin(step.bin).await?;
+        if let Some(target_temperature) = step.target_temperature {
+            asiair.main_camera_set_cooler(true).await?;
+            asiair.main_camera_set_target_temperature(target_temperature).await?;
+        }
+        Ok(())
+    }
+
+    // Blocks while the sequence is paused, returns false if it was aborted
+    async fn wait_while_paused(
+        &self,
+        control_rx: &mut watch::Receiver<SequenceControl>,
+        progress: &mut SequenceProgress,
+    ) -> bool {
+        loop {
+            let control = *control_rx.borrow_and_update();
+            match control {
+                SequenceControl::Run => {
+                    if progress.state == SequenceState::Paused {
+                        progress.state = SequenceState::Running;
+                        self.progress_tx.send_replace(progress.clone());
+                    }
+                    return true;
+                }
+                SequenceControl::Abort => return false,
+                SequenceControl::Pause => {
+                    if progress.state != SequenceState::Paused {
+                        progress.state = SequenceState::Paused;
+                        self.progress_tx.send_replace(progress.clone());
+                    }
+                    if control_rx.changed().await.is_err() {
+                        return false;
+                    }
+                }
+            }
+        }
+    }
+
+    // Blocks while the device flips the mount, returns false if the sequence was aborted meanwhile
+    async fn wait_for_merid_flip(
+        &self,
+        asiair: &ASIAir,
+        control_rx: &mut watch::Receiver<SequenceControl>,
+        progress: &mut SequenceProgress,
+    ) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+        let mut merid_flip_rx = asiair.subscribe_merid_flip();
+        if !merid_flip_rx.borrow_and_update().state.is_working() {
+            return Ok(true);
+        }
+
+        let state = std::mem::replace(&mut progress.state, SequenceState::MeridianFlip);
+        self.progress_tx.send_replace(progress.clone());
+        loop {
+            tokio::select! {
+                changed = merid_flip_rx.changed() => {
+                    changed?;
+                    let event = merid_flip_rx.borrow_and_update().clone();
+                    match event.state {
+                        MeridFlipState::Fail => {
+                            let error = event.error.unwrap_or_default();
+                            return Err(format!("meridian flip failed: {}", error).into());
+                        }
+                        state if state.is_working() => {}
+                        _ => break,
+                    }
+                }
+                changed = control_rx.changed() => {
+                    changed?;
+                    if *control_rx.borrow() == SequenceControl::Abort {
+                        return Ok(false);
+                    }
+                }
+            }
+        }
+        progress.state = state;
+        self.progress_tx.send_replace(progress.clone());
+        Ok(true)
+    }
+
+    // Expose and save a single frame
+    async fn capture_frame(
+        asiair: &mut ASIAir,
+        step: &SequenceStep,
+        control_rx: &mut watch::Receiver<SequenceControl>,
+        path: &Path,
+    ) -> Result<CaptureOutcome, Box<dyn std::error::Error + Send + Sync>> {
+        let mut exposure_rx = asiair.subscribe_exposure();
+        exposure_rx.borrow_and_update();
+        let merid_flip_rx = asiair.subscribe_merid_flip();
+
+        // The device refuses exposures while it flips the mount
+        if let Err(e) = asiair.main_camera_start_frame_exposure(step.frame_type).await {
+            let flipping = e
+                .downcast_ref::<DeviceError>()
+                .is_some_and(|e| e.reason == "meridian flip in progress");
+            if flipping || merid_flip_rx.borrow().state.is_working() {
+                return Ok(CaptureOutcome::Interrupted);
+            }
+            return Err(e);
+        }
+
+        let deadline = tokio::time::sleep(Duration::from_micros(step.exposure_us) + EXPOSURE_TIMEOUT_MARGIN);
+        tokio::pin!(deadline);
+        loop {
+            tokio::select! {
+                changed = exposure_rx.changed() => {
+                    changed?;
+                    match *exposure_rx.borrow_and_update() {
+                        ExposureEvent::Complete => break,
+                        ExposureEvent::Cancel => {
+                            if merid_flip_rx.borrow().state.is_working() {
+                                return Ok(CaptureOutcome::Interrupted);
+                            }
+                            return Err("exposure canceled by the device".into());
+                        }
+                        ExposureEvent::Fail { ref error } => {
+                            return Err(format!("exposure failed: {}", error).into());
+                        }
+                        _ => {}
+                    }
+                }
+                changed = control_rx.changed() => {
+                    changed?;
+                    if *control_rx.borrow() == SequenceControl::Abort {
+                        asiair.main_camera_stop_exposure().await?;
+                        return Ok(CaptureOutcome::Aborted);
+                    }
+                }
+                _ = &mut deadline => {
+                    return Err("timeout waiting for the exposure to complete".into());
+                }
+            }
+        }
+
+        let (data, width, height) = asiair.main_camera_get_current_img().await?;
+        if data.len() != width as usize * height as usize * 2 {
+            return Err(format!("unexpected image size {} for {}x{}", data.len(), width, height).into());
+        }
+
+        let image_type = match step.frame_type {
+            FrameType::Light => "Light Frame",
+            FrameType::Dark => "Dark Frame",
+            FrameType::Flat => "Flat Field",
+            FrameType::Bias => "Bias Frame",
+        };
+        let mut fits = FitsWriter::new(width, height)
+            .card("IMAGETYP", FitsValue::Text(image_type.to_string()))
+            .card("EXPTIME", FitsValue::Real(step.exposure_us as f64 / 1e6))
+            .card("GAIN", FitsValue::Integer(step.gain))
+            .card("XBINNING", FitsValue::Integer(step.bin as i64))
+            .card("YBINNING", FitsValue::Integer(step.bin as i64));
+        if let Some(target_temperature) = step.target_temperature {
+            fits = fits.card("SET-TEMP", FitsValue::Real(target_temperature));
+        }
+
+        // The file is assembled in memory so that only the write goes through the blocking pool
+        let mut file = Vec::with_capacity(data.len() + 2 * 2880);
+        fits.write(&mut file, &data)?;
+        tokio::fs::write(path, file).await?;
+
+        Ok(CaptureOutcome::Saved)
+    }
+}
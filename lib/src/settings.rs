@@ -2,7 +2,7 @@ use chrono::DateTime;
 use chrono::Datelike;
 use chrono::Timelike;
 use chrono_tz::Tz;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use serde_json::json;
 
 use super::ASIAir;
@@ -23,10 +23,48 @@ impl ASIAirLanguage {
     pub fn as_str(&self) -> &'static str {
         match self {
             ASIAirLanguage::English => "en",
+            ASIAirLanguage::SimplifiedChinese => "zh_CN",
+            ASIAirLanguage::TraditionalChinese => "zh_TW",
+            ASIAirLanguage::Japanese => "ja",
+            ASIAirLanguage::Korean => "ko",
+            ASIAirLanguage::German => "de",
+            ASIAirLanguage::French => "fr",
+            ASIAirLanguage::Spanish => "es",
+            ASIAirLanguage::Italian => "it",
+            ASIAirLanguage::Russian => "ru",
         }
     }
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TemperatureUnit {
+    #[default]
+    #[serde(rename = "C")]
+    Celsius,
+    #[serde(rename = "F")]
+    Fahrenheit,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WifiBand {
+    #[serde(rename = "2.4G")]
+    Band2G4,
+    #[default]
+    #[serde(rename = "5G")]
+    Band5G,
+}
+
+/// System settings of the device
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Settings {
+    pub lang: ASIAirLanguage,
+    pub temp_unit: TemperatureUnit,
+    pub beep: bool,
+    // Minutes of inactivity before the device shuts down, never when 0
+    pub auto_shutdown_min: u32,
+    pub wifi_band: WifiBand,
+}
+
 impl ASIAir {
     pub async fn set_time(
         &mut self,
@@ -82,4 +120,30 @@ impl ASIAir {
             })
         }
     }
+
+    /// Read the system settings of the device
+    pub async fn get_settings(&self) -> Result<Settings, Box<dyn std::error::Error + Send + Sync>> {
+        let response = self.rpc_request_4700("get_setting", None).await?;
+        Ok(serde_json::from_value(response)?)
+    }
+
+    /// Change the system settings of the device, all of them at once
+    pub async fn set_settings(
+        &mut self,
+        settings: &Settings,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let method = "set_setting";
+        let value = self
+            .rpc_request_4700(method, Some(serde_json::to_value(settings)?))
+            .await
+            .map_err(|e| {
+                log::debug!("{} failed: {}", method, e);
+                e
+            })?;
+        if value.as_i64() == Some(0) {
+            Ok(())
+        } else {
+            Err("unexpected response".into())
+        }
+    }
 }
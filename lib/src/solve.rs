@@ -0,0 +1,183 @@
+use super::ASIAir;
+use super::Timestamped;
+use super::DeviceError;
+use serde::Deserialize;
+use std::fmt;
+use tokio::time::Duration;
+
+// Longest time to wait for the device to report the end of a solve
+const SOLVE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Plate solving solution of an image
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct SolveResult {
+    // Right ascension of the image center in hours
+    pub ra: f64,
+    // Declination of the image center in degrees
+    pub dec: f64,
+    // Rotation of the image in degrees, east of north
+    pub angle: f64,
+    // Pixel scale in arcseconds per pixel
+    pub scale: f64,
+    // Field of view width and height in degrees
+    pub fov: [f64; 2],
+    pub star_number: u32,
+    // Time the device spent solving
+    pub lapse_ms: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SolveError {
+    // Not enough stars detected in the image
+    NoStars,
+    // The solve didn't finish in time
+    Timeout,
+    // The solve was stopped before the end
+    Cancelled,
+    Failed(String),
+}
+
+impl fmt::Display for SolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SolveError::NoStars => write!(f, "plate solve failed: no stars"),
+            SolveError::Timeout => write!(f, "plate solve failed: timeout"),
+            SolveError::Cancelled => write!(f, "plate solve cancelled"),
+            SolveError::Failed(reason) => write!(f, "plate solve failed: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for SolveError {}
+
+impl SolveError {
+    pub(crate) fn from_reason(reason: &str) -> Self {
+        match reason {
+            "no stars" => SolveError::NoStars,
+            "timeout" => SolveError::Timeout,
+            "cancel" => SolveError::Cancelled,
+            _ => SolveError::Failed(reason.to_string()),
+        }
+    }
+
+    // The reason of a failure reported by the device becomes a SolveError, failures to reach the
+    // device are passed through unchanged
+    pub(crate) fn from_request_error(
+        error: Box<dyn std::error::Error + Send + Sync>,
+    ) -> Box<dyn std::error::Error + Send + Sync> {
+        DeviceError::map_reason(error, SolveError::from_reason)
+    }
+}
+
+// A plain solve and an annotation run the same way on the device, they only differ by their
+// methods and the events reporting their progress
+pub(crate) struct SolverJob {
+    pub(crate) start: &'static str,
+    pub(crate) stop: &'static str,
+    pub(crate) event: &'static str,
+    pub(crate) tag: &'static str,
+    // Longest time to wait for the device to report the end of the job
+    pub(crate) timeout: Duration,
+}
+
+const SOLVE_JOB: SolverJob = SolverJob {
+    start: "start_solve",
+    stop: "stop_solve",
+    event: "PlateSolve",
+    tag: "Solve",
+    timeout: SOLVE_TIMEOUT,
+};
+
+// Progress of a job of the solver
+#[derive(Deserialize)]
+struct SolverJobEvent {
+    tag: String,
+    state: String,
+}
+
+impl ASIAir {
+    /// Plate solve the last image of the main camera, waiting for the solution.
+    /// Failures are reported as a `SolveError`
+    pub async fn solve_current_image(
+        &mut self,
+    ) -> Result<SolveResult, Box<dyn std::error::Error + Send + Sync>> {
+        if !self.run_solver_job(&SOLVE_JOB).await? {
+            return Err(Box::new(SolveError::Timeout));
+        }
+
+        self.get_solve_result().await
+    }
+
+    // Start a job of the solver and wait for the device to report its end, whether it succeeded or
+    // not. Returns false when it didn't end in time, the job is then stopped
+    pub(crate) async fn run_solver_job(
+        &mut self,
+        job: &SolverJob,
+    ) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+        let mut events = self.subscribe_events_of::<SolverJobEvent>(job.event);
+
+        self.rpc_request_4700(job.start, None).await?;
+
+        let finished = tokio::time::timeout(job.timeout, async {
+            while let Some(event) = events.recv().await {
+                if event.tag == job.tag && matches!(event.state.as_str(), "complete" | "fail" | "cancel") {
+                    return true;
+                }
+            }
+            false
+        })
+        .await;
+        match finished {
+            Ok(true) => Ok(true),
+            Ok(false) => Err("Connection closed".into()),
+            Err(_) => {
+                let _ = self.rpc_request_4700(job.stop, None).await;
+                Ok(false)
+            }
+        }
+    }
+
+    /// Start solving the last image, the progress is reported by `PlateSolveEvent` events
+    pub async fn start_solve(
+        &mut self,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let method = "start_solve";
+        self.rpc_request_4700(method, None).await?;
+        Ok(())
+    }
+
+    pub async fn stop_solve(
+        &mut self,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let method = "stop_solve";
+        self.rpc_request_4700(method, None).await?;
+        Ok(())
+    }
+
+    /// Result of the last solve
+    pub async fn get_solve_result(
+        &mut self,
+    ) -> Result<SolveResult, Box<dyn std::error::Error + Send + Sync>> {
+        let method = "get_solve_result";
+        let result = self
+            .rpc_request_4700(method, None)
+            .await
+            .map_err(SolveError::from_request_error)?;
+
+        let solution: SolveResult = serde_json::from_value(result)?;
+        Ok(solution)
+    }
+
+    /// `get_solve_result` with the Timestamp of the response
+    pub async fn get_solve_result_timestamped(
+        &mut self,
+    ) -> Result<Timestamped<SolveResult>, Box<dyn std::error::Error + Send + Sync>> {
+        let method = "get_solve_result";
+        let response = self
+            .rpc_request_4700_timestamped(method, None)
+            .await
+            .map_err(SolveError::from_request_error)?;
+
+        Ok(response.deserialize()?)
+    }
+}
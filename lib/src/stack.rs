@@ -0,0 +1,147 @@
+use super::ASIAir;
+use super::Timestamped;
+use serde::Deserialize;
+use std::io::Cursor;
+use std::io::Read;
+use tokio::sync::mpsc;
+use zip::ZipArchive;
+
+/// Progress of the live stack
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+pub struct StackProgress {
+    pub is_working: bool,
+    pub stacked_frame: u32,
+    pub dropped_frame: u32,
+    pub total_frame: u32,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StackEventState {
+    #[default]
+    Idle,
+    Start,
+    // A frame was registered and added to the stack
+    Stacked,
+    // A frame was rejected, see the error of the event
+    Dropped,
+    Stop,
+    Reset,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct StackEvent {
+    pub state: StackEventState,
+    #[serde(default)]
+    pub stacked_frame: u32,
+    #[serde(default)]
+    pub dropped_frame: u32,
+    #[serde(default)]
+    pub total_frame: u32,
+    // Stars detected in the last frame
+    #[serde(default)]
+    pub star_number: u32,
+    // Reason a frame was dropped
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+impl ASIAir {
+    /// Stack the light frames completed from now on, on top of the current stack
+    pub async fn stack_start(
+        &mut self,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let method = "start_stack";
+        self.rpc_request_4700(method, None).await?;
+        Ok(())
+    }
+
+    /// Stop stacking, frames not yet stacked are dropped
+    pub async fn stack_stop(
+        &mut self,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let method = "stop_stack";
+        self.rpc_request_4700(method, None).await?;
+        Ok(())
+    }
+
+    /// Drop the stacked image and the counters, the next frame becomes the reference
+    pub async fn stack_reset(
+        &mut self,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let method = "reset_stack";
+        self.rpc_request_4700(method, None).await?;
+        Ok(())
+    }
+
+    pub async fn stack_get_state(
+        &mut self,
+    ) -> Result<StackProgress, Box<dyn std::error::Error + Send + Sync>> {
+        let method = "get_stack_state";
+        let result = self.rpc_request_4700(method, None).await?;
+
+        Ok(serde_json::from_value(result)?)
+    }
+
+    /// `stack_get_state` with the Timestamp of the response
+    pub async fn stack_get_state_timestamped(
+        &mut self,
+    ) -> Result<Timestamped<StackProgress>, Box<dyn std::error::Error + Send + Sync>> {
+        let method = "get_stack_state";
+        let response = self.rpc_request_4700_timestamped(method, None).await?;
+
+        Ok(response.deserialize()?)
+    }
+
+    /// Download the average of the stacked frames, as raw 16 bits pixels with the image size
+    pub async fn stack_get_image(
+        &mut self,
+    ) -> Result<(Vec<u8>, u16, u16), Box<dyn std::error::Error + Send + Sync>> {
+        // The device doesn't answer on the binary port when there is no image
+        if self.stack_get_state().await?.stacked_frame == 0 {
+            return Err("nothing stacked".into());
+        }
+
+        let method = "get_stacked_img";
+        let result = self.rpc_request_4800(method, None).await?;
+
+        let cursor = Cursor::new(&result.data);
+        let mut archive = ZipArchive::new(cursor)?;
+        if archive.is_empty() {
+            return Err("Zip archive is empty".into());
+        }
+        let mut file = archive.by_index(0)?;
+        let mut extracted_data = Vec::new();
+        file.read_to_end(&mut extracted_data)?;
+
+        Ok((extracted_data, result.width, result.height))
+    }
+
+    /// Stream of the stacked and dropped frames, ends when stacking stops
+    pub fn stack_events(&self) -> mpsc::Receiver<StackEvent> {
+        let mut stack_rx = self.subscribe_events_of::<StackEvent>("Stack");
+
+        let (tx, rx) = mpsc::channel(64);
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    _ = tx.closed() => break,
+                    event = stack_rx.recv() => {
+                        let Some(event) = event else {
+                            break;
+                        };
+                        match event.state {
+                            StackEventState::Stop => break,
+                            StackEventState::Stacked | StackEventState::Dropped => {
+                                let _ = tx.send(event.value).await;
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+            }
+        });
+
+        rx
+    }
+}
@@ -0,0 +1,291 @@
+use super::ASIAir;
+use super::Timestamped;
+use super::camera::FrameType;
+use chrono::{DateTime, FixedOffset};
+use serde::Deserialize;
+use std::io::Cursor;
+use std::io::Read;
+use std::time::Duration;
+use zip::ZipArchive;
+
+// Longest time a format of the storage is waited for
+const FORMAT_TIMEOUT: Duration = Duration::from_secs(60);
+// Longest time an export is waited for
+const EXPORT_TIMEOUT: Duration = Duration::from_secs(3600);
+
+/// Name of the internal storage of the device
+pub const INTERNAL_STORAGE: &str = "emmc";
+
+/// Image saved by the device, in its folder layout (`Autosave/Light/<target>/...`),
+/// or video recorded in `Video/<target>/...`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ImageFile {
+    pub path: String,
+    pub name: String,
+    // File size in bytes
+    pub size: u64,
+    // Frames in the file, more than one for videos
+    pub frame_count: u32,
+    pub frame_type: FrameType,
+    pub target: String,
+    pub exposure_us: u64,
+    pub gain: i64,
+    pub bin: u32,
+    // Image size in binned pixels
+    pub width: u32,
+    pub height: u32,
+    // Sensor temperature in degrees Celsius
+    pub temperature: i64,
+    // Pointing of the mount, in hours and degrees
+    pub ra: f64,
+    pub dec: f64,
+    // Start of the exposure
+    pub date_time: DateTime<FixedOffset>,
+}
+
+/// Usage of a storage of the device, in bytes. The internal storage is `emmc`,
+/// the removable ones are `usb` and `sd` when plugged
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct StorageInfo {
+    pub name: String,
+    pub total: u64,
+    pub used: u64,
+    pub free: u64,
+    pub image_count: u32,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FormatDriveState {
+    #[default]
+    Idle,
+    Start,
+    Complete,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct FormatDriveEvent {
+    pub state: FormatDriveState,
+}
+
+/// Progress of the last export to a removable storage
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct ExportProgress {
+    pub is_working: bool,
+    pub success_frame: u32,
+    pub total_frame: u32,
+    // False when the images are deleted from the internal storage once copied
+    pub keep: bool,
+    pub dst_storage: String,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportState {
+    #[default]
+    Idle,
+    Start,
+    // An image was copied
+    Working,
+    Complete,
+    Fail,
+    Cancel,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct ExportEvent {
+    pub state: ExportState,
+    #[serde(default)]
+    pub success_frame: u32,
+    #[serde(default)]
+    pub total_frame: u32,
+    #[serde(default)]
+    pub dst_storage: String,
+    // Reason of a failure, like the destination running out of space
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+impl ASIAir {
+    /// List the images saved in a folder and its subfolders, the whole autosave folder when
+    /// `dir` is None. Images are sorted from the oldest
+    pub async fn storage_list_images(
+        &mut self,
+        dir: Option<&str>,
+    ) -> Result<Vec<ImageFile>, Box<dyn std::error::Error + Send + Sync>> {
+        self.storage_list_images_on(INTERNAL_STORAGE, dir).await
+    }
+
+    /// List the images of a folder of any storage, like the images exported to `usb`
+    pub async fn storage_list_images_on(
+        &mut self,
+        storage: &str,
+        dir: Option<&str>,
+    ) -> Result<Vec<ImageFile>, Box<dyn std::error::Error + Send + Sync>> {
+        let method = "get_img_file_list";
+        let params = Some(serde_json::json!([ dir, storage ]));
+        let result = self.rpc_request_4700(method, params).await?;
+
+        Ok(serde_json::from_value(result)?)
+    }
+
+    /// Download a saved image, as raw 16 bits pixels with the image size
+    pub async fn storage_download_image(
+        &mut self,
+        path: &str,
+    ) -> Result<(Vec<u8>, u16, u16), Box<dyn std::error::Error + Send + Sync>> {
+        // The device doesn't answer on the binary port for a missing file
+        if !self.storage_list_images(Some(path)).await?.iter().any(|image| image.path == path) {
+            return Err("file not found".into());
+        }
+
+        let method = "get_img_file";
+        let params = Some(serde_json::json!([ path ]));
+        let result = self.rpc_request_4800(method, params).await?;
+
+        let cursor = Cursor::new(&result.data);
+        let mut archive = ZipArchive::new(cursor)?;
+        if archive.is_empty() {
+            return Err("Zip archive is empty".into());
+        }
+        let mut file = archive.by_index(0)?;
+        let mut extracted_data = Vec::new();
+        file.read_to_end(&mut extracted_data)?;
+
+        Ok((extracted_data, result.width, result.height))
+    }
+
+    /// Delete an image, or a folder with all its images, returning the number of images deleted
+    pub async fn storage_delete(
+        &mut self,
+        path: &str,
+    ) -> Result<u32, Box<dyn std::error::Error + Send + Sync>> {
+        let method = "delete_img_file";
+        let params = Some(serde_json::json!([ path ]));
+        let result = self.rpc_request_4700(method, params).await?;
+
+        let deleted: u32 = serde_json::from_value(result["deleted"].clone())?;
+        Ok(deleted)
+    }
+
+    pub async fn storage_get_info(
+        &mut self,
+    ) -> Result<Vec<StorageInfo>, Box<dyn std::error::Error + Send + Sync>> {
+        let method = "get_storage_info";
+        let result = self.rpc_request_4700(method, None).await?;
+
+        Ok(serde_json::from_value(result)?)
+    }
+
+    /// Erase every image of the internal storage and wait until it's done
+    pub async fn format_drive(
+        &mut self,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let mut format_rx = self.subscribe_format_drive();
+        format_rx.mark_unchanged();
+
+        let method = "format_drive";
+        self.rpc_request_4700(method, None).await?;
+
+        tokio::time::timeout(FORMAT_TIMEOUT, async {
+            loop {
+                if format_rx.changed().await.is_err() {
+                    return Err("connection closed");
+                }
+                if format_rx.borrow_and_update().state == FormatDriveState::Complete {
+                    return Ok(());
+                }
+            }
+        })
+        .await
+        .map_err(|_| "format timeout")??;
+
+        Ok(())
+    }
+
+    /// Copy an image or a folder of the internal storage to a removable storage and wait for
+    /// the end of the copy, returning the number of images exported. The images are deleted
+    /// from the internal storage once copied unless `keep` is true
+    pub async fn export_images(
+        &mut self,
+        src: &str,
+        dst_storage: &str,
+        keep: bool,
+    ) -> Result<u32, Box<dyn std::error::Error + Send + Sync>> {
+        let mut export_rx = self.subscribe_export();
+        export_rx.mark_unchanged();
+
+        self.export_start(src, dst_storage, keep).await?;
+
+        let finished = tokio::time::timeout(EXPORT_TIMEOUT, async {
+            loop {
+                if export_rx.changed().await.is_err() {
+                    return None;
+                }
+                let event = export_rx.borrow_and_update();
+                if matches!(event.state, ExportState::Complete | ExportState::Fail | ExportState::Cancel) {
+                    return Some(event.clone());
+                }
+            }
+        })
+        .await;
+        let event = match finished {
+            Ok(Some(event)) => event,
+            Ok(None) => return Err("Connection closed".into()),
+            Err(_) => {
+                let _ = self.export_stop().await;
+                return Err("export timeout".into());
+            }
+        };
+
+        match event.state {
+            ExportState::Complete => Ok(event.success_frame),
+            ExportState::Cancel => Err("export cancelled".into()),
+            _ => Err(event.error.unwrap_or_else(|| "export failed".to_string()).into()),
+        }
+    }
+
+    /// Start an export, the progress is reported by `ExportEvent` events
+    pub async fn export_start(
+        &mut self,
+        src: &str,
+        dst_storage: &str,
+        keep: bool,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let method = "start_export_image";
+        let params = Some(serde_json::json!([ {
+            "src": src,
+            "dst_storage": dst_storage,
+            "keep": keep,
+        } ]));
+        self.rpc_request_4700(method, params).await?;
+        Ok(())
+    }
+
+    pub async fn export_stop(
+        &mut self,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let method = "stop_export_image";
+        self.rpc_request_4700(method, None).await?;
+        Ok(())
+    }
+
+    pub async fn export_get_progress(
+        &mut self,
+    ) -> Result<ExportProgress, Box<dyn std::error::Error + Send + Sync>> {
+        let method = "get_export_image_state";
+        let result = self.rpc_request_4700(method, None).await?;
+
+        Ok(serde_json::from_value(result)?)
+    }
+
+    /// `export_get_progress` with the Timestamp of the response
+    pub async fn export_get_progress_timestamped(
+        &mut self,
+    ) -> Result<Timestamped<ExportProgress>, Box<dyn std::error::Error + Send + Sync>> {
+        let method = "get_export_image_state";
+        let response = self.rpc_request_4700_timestamped(method, None).await?;
+
+        Ok(response.deserialize()?)
+    }
+}
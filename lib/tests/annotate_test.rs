@@ -0,0 +1,82 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::init_logger;
+
+    use asiair::ASIAir;
+    use asiair::annotate::{AnnotateError, ObjectType};
+    use asiair::mount::{GotoState, MountEvent};
+    use asisim::{ASIAirSim, SolveFailure, SolverConfig};
+    use chrono::TimeZone;
+    use std::net::Ipv4Addr;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_annotate() {
+        init_logger();
+
+        // Create a new ASIAir instance
+        let addr: Ipv4Addr = Ipv4Addr::from([127, 0, 0, 1]);
+        let mut asiair = ASIAir::new(addr);
+
+        // Create a new ASIAir simulator instance
+        let mut asiair_sim = ASIAirSim::new();
+        asiair_sim.start().await.unwrap();
+        tokio::time::sleep(Duration::from_secs(1)).await; // Give the simulator some time to start
+
+        asiair_sim.set_solver_config(SolverConfig {
+            latency: Duration::from_millis(300),
+            noise_arcsec: 0.0,
+            ..Default::default()
+        });
+
+        asiair.connect().await.unwrap();
+        asiair.main_camera_open(0).await.unwrap();
+
+        let now = chrono_tz::UTC.with_ymd_and_hms(2025, 5, 6, 20, 0, 0).unwrap();
+        asiair.set_time(now).await.unwrap();
+        asiair.mount_set_slew_rate(180.0).await.unwrap();
+        let mut mount_rx = asiair.subscribe_mount();
+        asiair.mount_goto(5.5881, -5.3911).await.unwrap();
+        mount_rx
+            .wait_for(|event| matches!(event, MountEvent::Goto { state: GotoState::Complete, .. }))
+            .await
+            .unwrap();
+
+        let mut annotate_rx = asiair.subscribe_annotate();
+        let result = asiair.annotate_current_image().await.unwrap();
+        assert_eq!(annotate_rx.borrow_and_update().state, "complete");
+        assert!((result.ra - 5.5881).abs() < 0.001);
+
+        let names: Vec<&str> = result.objects.iter().map(|o| o.name.as_str()).collect();
+        assert!(names.contains(&"M42"));
+        assert!(names.contains(&"M43"));
+        assert!(names.contains(&"NGC 1977"));
+        assert!(!names.contains(&"NGC 2024"));
+        assert!(!names.contains(&"M31"));
+
+        // M42 is at the center of the 6248x4176 image, with north up and east left
+        let m42 = result.objects.iter().find(|o| o.name == "M42").unwrap();
+        assert_eq!(m42.r#type, ObjectType::Nebula);
+        assert!((m42.x - 3124.0).abs() < 1.0);
+        assert!((m42.y - 2088.0).abs() < 1.0);
+        assert!((m42.radius - 85.0 * 30.0 / result.scale).abs() < 1.0);
+        let m43 = result.objects.iter().find(|o| o.name == "M43").unwrap();
+        assert!(m43.y < m42.y);
+        assert!(m43.x < m42.x);
+
+        // Annotations fail when the image can't be solved
+        asiair_sim.set_solver_config(SolverConfig {
+            failure: Some(SolveFailure::NoStars),
+            ..asiair_sim.solver_config()
+        });
+        let error = asiair.annotate_current_image().await.unwrap_err();
+        assert_eq!(error.downcast_ref::<AnnotateError>(), Some(&AnnotateError::NoStars));
+        assert_eq!(annotate_rx.borrow_and_update().state, "fail");
+
+        // Final cleanup
+        asiair.disconnect().await;
+        asiair_sim.shutdown();
+    }
+}
@@ -0,0 +1,88 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::init_logger;
+
+    use asiair::app::AppSetting;
+    use asiair::{ASIAir, ASIAirPage};
+    use asisim::ASIAirSim;
+    use serde_json::json;
+    use std::net::Ipv4Addr;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_app_state_and_setting() {
+        init_logger();
+
+        // Create a new ASIAir instance
+        let addr: Ipv4Addr = Ipv4Addr::from([127, 0, 0, 1]);
+        let mut asiair = ASIAir::new(addr);
+
+        // Create a new ASIAir simulator instance
+        let mut asiair_sim = ASIAirSim::new();
+        asiair_sim.start().await.unwrap();
+        tokio::time::sleep(Duration::from_secs(1)).await; // Give the simulator some time to start
+
+        asiair.connect().await.unwrap();
+
+        // Nothing runs on a fresh device
+        let state = asiair.get_app_state().await.unwrap();
+        assert_eq!(state.page, ASIAirPage::Preview);
+        assert!(state.working().is_empty());
+        assert_eq!(state.stack.details["frame_type"], "Light");
+
+        // A capture in progress shows up in the state
+        asiair.main_camera_open(0).await.unwrap();
+        asiair.main_camera_set_exposure(10_000_000).await.unwrap();
+        asiair.main_camera_start_exposure().await.unwrap();
+        let state = asiair.get_app_state().await.unwrap();
+        assert!(state.capture.is_working);
+        assert_eq!(state.working(), vec!["capture".to_string()]);
+        asiair.main_camera_stop_exposure().await.unwrap();
+        assert!(!asiair.get_app_state().await.unwrap().capture.is_working);
+
+        let setting = asiair.get_app_setting().await.unwrap();
+        assert_eq!(setting.main_camera_name, "ZWO ASI2600MC Pro");
+        assert_eq!(setting.light_bin, 1);
+        assert!(setting.other.is_empty());
+
+        // Only the changed settings are sent
+        let updated = asiair
+            .update_app_setting(|setting| {
+                setting.light_bin = 2;
+                setting.goto_target_name = "M 31".to_string();
+            })
+            .await
+            .unwrap();
+        assert_eq!(updated.light_bin, 2);
+        assert_eq!(updated.goto_target_name, "M 31");
+        assert_eq!(updated.comets_md5, setting.comets_md5);
+        assert_eq!(asiair.get_app_setting().await.unwrap(), updated);
+        assert_eq!(asiair.update_app_setting(|_| {}).await.unwrap(), updated);
+        let cleared = asiair.update_app_setting(|setting| setting.comets_md5.clear()).await.unwrap();
+        assert!(cleared.comets_md5.is_empty());
+
+        // Settings with an invalid value or an unknown key are refused as a whole
+        let params = Some(json!([{ "light_bin": 3, "dark_bin": "two" }]));
+        assert!(asiair.rpc_request_4700("set_app_setting", params).await.is_err());
+        let params = Some(json!([{ "light_bin": 3, "dew_heater": true }]));
+        assert!(asiair.rpc_request_4700("set_app_setting", params).await.is_err());
+        assert_eq!(asiair.get_app_setting().await.unwrap(), cleared);
+
+        // Settings of newer firmwares are kept, and only sent when changed
+        let mut value = serde_json::to_value(&updated).unwrap();
+        value["dew_heater"] = json!({ "enabled": true });
+        let newer: AppSetting = serde_json::from_value(value.clone()).unwrap();
+        assert_eq!(newer.other["dew_heater"], json!({ "enabled": true }));
+        assert_eq!(serde_json::to_value(&newer).unwrap(), value);
+        let mut changed = newer.clone();
+        changed.dark_bin = 2;
+        assert_eq!(newer.changes(&changed), json!({ "dark_bin": 2 }).as_object().unwrap().clone());
+        changed.other.insert("dew_heater".to_string(), json!({ "enabled": false }));
+        assert_eq!(newer.changes(&changed).len(), 2);
+
+        asiair.disconnect().await;
+        asiair_sim.shutdown();
+    }
+}
@@ -0,0 +1,86 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::init_logger;
+
+    use asiair::ASIAir;
+    use asiair::auto_exp::AutoExpState;
+    use asiair::camera::FrameType;
+    use asisim::ASIAirSim;
+    use std::net::Ipv4Addr;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_auto_exp() {
+        init_logger();
+
+        // Create a new ASIAir instance
+        let addr: Ipv4Addr = Ipv4Addr::from([127, 0, 0, 1]);
+        let mut asiair = ASIAir::new(addr);
+
+        // Create a new ASIAir simulator instance
+        let mut asiair_sim = ASIAirSim::new();
+        asiair_sim.start().await.unwrap();
+        tokio::time::sleep(Duration::from_secs(1)).await; // Give the simulator some time to start
+
+        asiair.connect().await.unwrap();
+
+        // Auto exposure needs the main camera
+        assert!(asiair.auto_expose(8000.0, 100.0).await.is_err());
+        asiair.main_camera_open(0).await.unwrap();
+        asiair.main_camera_set_bin(4).await.unwrap();
+        asiair.main_camera_set_gain(0).await.unwrap();
+        asiair.main_camera_set_exposure(1000).await.unwrap();
+
+        // Targets above the saturation of the sensor can't be reached
+        assert!(asiair.auto_expose(20000.0, 100.0).await.is_err());
+
+        // Flats converge to the target within a few frames, the camera keeps the exposure found
+        let result = asiair.auto_expose(8000.0, 100.0).await.unwrap();
+        assert!((result.mean_adu - 8000.0).abs() <= 100.0, "{:?}", result);
+        assert!(result.iterations >= 2 && result.iterations <= 4, "{:?}", result);
+        assert!(result.exposure_us > 600000 && result.exposure_us < 900000, "{:?}", result);
+        assert_eq!(asiair.main_camera_get_exposure().await.unwrap(), result.exposure_us);
+        let status = asiair.auto_exp_get_state().await.unwrap();
+        assert!(!status.is_working);
+        assert_eq!(status.exposure_us, result.exposure_us);
+
+        // A saturated start is brought back down
+        asiair.main_camera_set_exposure(3000000).await.unwrap();
+        let result = asiair.auto_expose(4000.0, 100.0).await.unwrap();
+        assert!((result.mean_adu - 4000.0).abs() <= 100.0, "{:?}", result);
+
+        // Previews expose lights, nothing else can expose meanwhile
+        asiair.main_camera_set_gain(300).await.unwrap();
+        let mut auto_exp_rx = asiair.subscribe_auto_exp();
+        auto_exp_rx.mark_unchanged();
+        asiair.start_auto_exp(FrameType::Light, 1000.0, 50.0).await.unwrap();
+        assert!(asiair.start_auto_exp(FrameType::Light, 1000.0, 50.0).await.is_err());
+        assert!(asiair.main_camera_start_exposure().await.is_err());
+        let event = tokio::time::timeout(Duration::from_secs(20), async {
+            loop {
+                auto_exp_rx.changed().await.unwrap();
+                let event = auto_exp_rx.borrow_and_update().clone();
+                if matches!(event.state, AutoExpState::Complete | AutoExpState::Fail | AutoExpState::Cancel) {
+                    return event;
+                }
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(event.state, AutoExpState::Complete, "{:?}", event);
+        assert!((event.mean_adu - 1000.0).abs() <= 50.0);
+
+        // Auto exposure can be cancelled
+        asiair.main_camera_set_exposure(1000).await.unwrap();
+        asiair.start_auto_exp(FrameType::Light, 1000.0, 50.0).await.unwrap();
+        asiair.stop_auto_exp().await.unwrap();
+        assert!(!asiair.auto_exp_get_state().await.unwrap().is_working);
+        asiair.main_camera_start_exposure().await.unwrap();
+
+        // Final cleanup
+        asiair.disconnect().await;
+        asiair_sim.shutdown();
+    }
+}
@@ -0,0 +1,91 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::init_logger;
+
+    use asiair::ASIAir;
+    use asiair::clock::{ClockOffset, ClockSyncConfig};
+    use asisim::ASIAirSim;
+    use chrono::Utc;
+    use chrono_tz::Tz;
+    use std::net::Ipv4Addr;
+    use std::time::Duration;
+    use tokio::sync::watch;
+
+    async fn wait_offset(clock_offset_rx: &mut watch::Receiver<ClockOffset>, done: impl Fn(chrono::Duration) -> bool) {
+        tokio::time::timeout(Duration::from_secs(10), clock_offset_rx.wait_for(|offset| done(offset.offset)))
+            .await
+            .unwrap()
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_clock_sync() {
+        init_logger();
+
+        // Create a new ASIAir instance
+        let addr: Ipv4Addr = Ipv4Addr::from([127, 0, 0, 1]);
+        let mut asiair = ASIAir::new(addr);
+
+        // Create a new ASIAir simulator instance
+        let mut asiair_sim = ASIAirSim::new();
+        asiair_sim.start().await.unwrap();
+        tokio::time::sleep(Duration::from_secs(1)).await; // Give the simulator some time to start
+
+        asiair.connect().await.unwrap();
+
+        // The device time is read back in its time zone
+        asiair.set_time(Utc::now().with_timezone(&Tz::Asia__Tokyo)).await.unwrap();
+        let device_time = asiair.get_time().await.unwrap();
+        assert_eq!(device_time.offset().local_minus_utc(), 9 * 3600);
+        assert!((device_time.to_utc() - Utc::now()).abs() < chrono::Duration::seconds(2));
+
+        // The offset is measured through the round trip of the request
+        asiair_sim.advance_time(chrono::Duration::seconds(30));
+        let offset = asiair.measure_clock_offset().await.unwrap();
+        assert!(offset.round_trip < Duration::from_secs(1));
+        assert!((offset.offset - chrono::Duration::seconds(30)).abs() < chrono::Duration::seconds(2));
+
+        // Measures can't follow each other without a pause
+        let zero_interval = ClockSyncConfig {
+            interval: Duration::ZERO,
+            ..Default::default()
+        };
+        assert!(asiair.set_clock_sync(Some(zero_interval)).is_err());
+
+        // Without resync, the drift is only published
+        let mut clock_offset_rx = asiair.subscribe_clock_offset();
+        asiair_sim.advance_time(chrono::Duration::seconds(10));
+        asiair.set_clock_sync(Some(ClockSyncConfig {
+            interval: Duration::from_millis(200),
+            ..Default::default()
+        }))
+        .unwrap();
+        wait_offset(&mut clock_offset_rx, |offset| offset > chrono::Duration::seconds(38)).await;
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        assert!(asiair.measure_clock_offset().await.unwrap().offset > chrono::Duration::seconds(38));
+
+        // With resync, the device clock is set again once it drifted past the threshold
+        asiair.set_clock_sync(Some(ClockSyncConfig {
+            interval: Duration::from_millis(200),
+            resync: true,
+            ..Default::default()
+        }))
+        .unwrap();
+        wait_offset(&mut clock_offset_rx, |offset| offset.abs() < chrono::Duration::milliseconds(500)).await;
+        asiair_sim.advance_time(chrono::Duration::seconds(-5));
+        wait_offset(&mut clock_offset_rx, |offset| offset < chrono::Duration::seconds(-4)).await;
+        wait_offset(&mut clock_offset_rx, |offset| offset.abs() < chrono::Duration::milliseconds(500)).await;
+
+        // Small drifts are left alone
+        asiair_sim.advance_time(chrono::Duration::seconds(1));
+        wait_offset(&mut clock_offset_rx, |offset| offset > chrono::Duration::milliseconds(500)).await;
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        assert!(asiair.measure_clock_offset().await.unwrap().offset > chrono::Duration::milliseconds(500));
+
+        asiair.set_clock_sync(None).unwrap();
+        asiair.disconnect().await;
+        asiair_sim.shutdown();
+    }
+}
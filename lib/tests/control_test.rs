@@ -0,0 +1,128 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::init_logger;
+
+    use asiair::ASIAir;
+    use asisim::ASIAirSim;
+    use serde_json::{json, Value};
+    use std::net::Ipv4Addr;
+    use std::time::Duration;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpStream;
+
+    // Sends a request to the control port and returns the status and the JSON body of the response
+    async fn control_request(port: u16, method: &str, path: &str, body: Option<Value>) -> (u16, Value) {
+        let mut stream = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+        let body = body.map(|body| body.to_string()).unwrap_or_default();
+        let request = format!(
+            "{} {} HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+            method,
+            path,
+            body.len(),
+            body
+        );
+        stream.write_all(request.as_bytes()).await.unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        let status = head.split_whitespace().nth(1).unwrap().parse().unwrap();
+        (status, serde_json::from_str(body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_control() {
+        init_logger();
+
+        // Create a new ASIAir instance
+        let addr: Ipv4Addr = Ipv4Addr::from([127, 0, 0, 1]);
+        let mut asiair = ASIAir::new(addr);
+
+        // Create a new ASIAir simulator instance
+        let mut asiair_sim = ASIAirSim::new();
+        asiair_sim.start().await.unwrap();
+        tokio::time::sleep(Duration::from_secs(1)).await; // Give the simulator some time to start
+
+        asiair.connect().await.unwrap();
+
+        // Plugging a camera in lists it and tells the client
+        let mut camera_list_rx = asiair.subscribe_camera_list();
+        asiair_sim.plug_camera("ZWO ASI533MC Pro").unwrap();
+        tokio::time::timeout(Duration::from_secs(5), camera_list_rx.changed())
+            .await
+            .unwrap()
+            .unwrap();
+        let cameras = asiair.get_connected_cameras().await.unwrap();
+        assert_eq!(cameras.len(), 3);
+        assert_eq!(cameras[2].name, "ZWO ASI533MC Pro");
+        assert_eq!(cameras[2].id, 2);
+        assert!(asiair_sim.plug_camera("ZWO ASI533MC Pro").is_err());
+
+        // Unplugging it removes it from the list
+        asiair_sim.unplug_camera("ZWO ASI533MC Pro").unwrap();
+        tokio::time::timeout(Duration::from_secs(5), camera_list_rx.changed())
+            .await
+            .unwrap()
+            .unwrap();
+        let cameras = asiair.get_connected_cameras().await.unwrap();
+        assert_eq!(cameras.len(), 2);
+        assert!(asiair_sim.unplug_camera("ZWO ASI533MC Pro").is_err());
+
+        // The clock of the device jumps forward
+        let before = asiair_sim.now();
+        asiair_sim.advance_time(chrono::Duration::hours(2));
+        let elapsed = asiair_sim.now() - before;
+        assert!(elapsed >= chrono::Duration::hours(2) && elapsed < chrono::Duration::hours(2) + chrono::Duration::seconds(5));
+
+        // The ambient temperature reaches the focuser and the board
+        asiair_sim.set_ambient_temperature(-5.0);
+        assert_eq!(asiair_sim.pi_config().ambient, -5.0);
+        let snapshot = asiair_sim.snapshot();
+        assert_eq!(snapshot["focuser"]["temperature"], json!(-5.0));
+        assert_eq!(snapshot["camera_controls"]["temperature"], json!(-5));
+        assert_eq!(snapshot["connected_cameras"].as_array().unwrap().len(), 2);
+
+        // The same controls are served over HTTP
+        let port = asiair_sim.start_control_server(0).await.unwrap();
+
+        let (status, state) = control_request(port, "GET", "/state", None).await;
+        assert_eq!(status, 200);
+        assert_eq!(state["focuser"]["temperature"], json!(-5.0));
+
+        let (status, cameras) =
+            control_request(port, "POST", "/cameras/plug", Some(json!({ "name": "ZWO ASI120MM Mini" }))).await;
+        assert_eq!(status, 200);
+        assert_eq!(cameras.as_array().unwrap().len(), 3);
+        let cameras = asiair.get_connected_cameras().await.unwrap();
+        assert!(cameras.iter().any(|camera| camera.name == "ZWO ASI120MM Mini"));
+
+        let (status, error) =
+            control_request(port, "POST", "/cameras/unplug", Some(json!({ "name": "ZWO ASI174MM" }))).await;
+        assert_eq!(status, 400);
+        assert!(error["error"].is_string());
+
+        let (status, _) = control_request(port, "POST", "/ambient_temperature", Some(json!({ "temperature": 12.5 }))).await;
+        assert_eq!(status, 200);
+        assert_eq!(asiair_sim.pi_config().ambient, 12.5);
+
+        let before = asiair_sim.now();
+        let (status, _) = control_request(port, "POST", "/time/advance", Some(json!({ "seconds": 600 }))).await;
+        assert_eq!(status, 200);
+        assert!(asiair_sim.now() - before >= chrono::Duration::minutes(10));
+
+        let (status, _) = control_request(port, "POST", "/pi/faults", Some(json!({ "undervolt": true }))).await;
+        assert_eq!(status, 200);
+        assert!(asiair_sim.pi_config().undervolt);
+        assert!(!asiair_sim.pi_config().over_current);
+
+        let (status, _) = control_request(port, "POST", "/ambient_temperature", Some(json!({}))).await;
+        assert_eq!(status, 400);
+        let (status, _) = control_request(port, "GET", "/nowhere", None).await;
+        assert_eq!(status, 404);
+
+        asiair.disconnect().await;
+        asiair_sim.shutdown();
+    }
+}
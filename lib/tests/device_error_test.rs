@@ -0,0 +1,50 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::init_logger;
+
+    use asiair::{ASIAir, DeviceError};
+    use asisim::ASIAirSim;
+    use std::net::Ipv4Addr;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_device_errors() {
+        init_logger();
+
+        // Create a new ASIAir instance
+        let addr: Ipv4Addr = Ipv4Addr::from([127, 0, 0, 1]);
+        let mut asiair = ASIAir::new(addr);
+
+        // Create a new ASIAir simulator instance
+        let mut asiair_sim = ASIAirSim::new();
+        asiair_sim.start().await.unwrap();
+        tokio::time::sleep(Duration::from_secs(1)).await; // Give the simulator some time to start
+
+        asiair.connect().await.unwrap();
+
+        // The reason given by the device is the error of the request, whatever the method
+        let error = asiair.main_camera_open(42).await.unwrap_err();
+        assert_eq!(error.to_string(), "Camera index out of bounds");
+        assert_eq!(
+            error.downcast_ref::<DeviceError>().map(|error| error.reason.as_str()),
+            Some("Camera index out of bounds")
+        );
+        let error = asiair.rpc_request_4700("no_such_method", None).await.unwrap_err();
+        assert_eq!(error.to_string(), "Unknown method");
+
+        asiair.main_camera_open(0).await.unwrap();
+        asiair.main_camera_set_exposure(5_000_000).await.unwrap();
+        asiair.main_camera_start_exposure().await.unwrap();
+        let error = asiair.main_camera_start_exposure().await.unwrap_err();
+        assert_eq!(error.to_string(), "exposure in progress");
+
+        // Successful requests still return their result on the same connection
+        asiair.main_camera_stop_exposure().await.unwrap();
+        asiair.test_connection().await.unwrap();
+
+        asiair.disconnect().await;
+        asiair_sim.shutdown();
+    }
+}
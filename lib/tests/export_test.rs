@@ -0,0 +1,102 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::init_logger;
+
+    use asiair::storage::ExportState;
+    use asiair::{ASIAir, ExposureEvent};
+    use asisim::ASIAirSim;
+    use std::net::Ipv4Addr;
+    use std::time::Duration;
+
+    async fn expose(asiair: &mut ASIAir) {
+        let mut exposure_rx = asiair.subscribe_exposure();
+        exposure_rx.mark_unchanged();
+        asiair.main_camera_start_exposure().await.unwrap();
+        tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                exposure_rx.changed().await.unwrap();
+                if let ExposureEvent::Complete = *exposure_rx.borrow_and_update() {
+                    break;
+                }
+            }
+        })
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_export() {
+        init_logger();
+
+        // Create a new ASIAir instance
+        let addr: Ipv4Addr = Ipv4Addr::from([127, 0, 0, 1]);
+        let mut asiair = ASIAir::new(addr);
+
+        // Create a new ASIAir simulator instance
+        let mut asiair_sim = ASIAirSim::new();
+        asiair_sim.start().await.unwrap();
+        tokio::time::sleep(Duration::from_secs(1)).await; // Give the simulator some time to start
+
+        asiair.connect().await.unwrap();
+        asiair.main_camera_open(0).await.unwrap();
+        asiair.main_camera_set_bin(4).await.unwrap();
+        asiair.main_camera_set_exposure(10000).await.unwrap();
+        for _ in 0..4 {
+            expose(&mut asiair).await;
+        }
+        let images = asiair.storage_list_images(None).await.unwrap();
+        assert_eq!(images.len(), 4);
+
+        // The internal and removable storages are listed with their capacity
+        let info = asiair.storage_get_info().await.unwrap();
+        let names: Vec<&str> = info.iter().map(|storage| storage.name.as_str()).collect();
+        assert_eq!(names, ["emmc", "usb", "sd"]);
+        assert!(info.iter().all(|storage| storage.used + storage.free == storage.total));
+
+        // Only plugged removable storages can be exported to
+        assert!(asiair.export_start("Autosave", "emmc", true).await.is_err());
+        assert!(asiair.export_start("Autosave", "nvme", true).await.is_err());
+        assert!(asiair.export_start("Autosave/Flat", "usb", true).await.is_err());
+
+        // Copies report their progress and keep the folder layout
+        let export_rx = asiair.subscribe_export();
+        let exported = asiair.export_images("Autosave", "usb", true).await.unwrap();
+        assert_eq!(exported, 4);
+        assert_eq!(export_rx.borrow().state, ExportState::Complete);
+        let progress = asiair.export_get_progress().await.unwrap();
+        assert!(!progress.is_working);
+        assert_eq!((progress.success_frame, progress.total_frame), (4, 4));
+        assert!(progress.keep);
+        assert_eq!(progress.dst_storage, "usb");
+        let copies = asiair.storage_list_images_on("usb", None).await.unwrap();
+        assert_eq!(copies, images);
+        assert_eq!(asiair.storage_list_images(None).await.unwrap().len(), 4);
+
+        // Without keep the images are moved
+        let exported = asiair.export_images(&images[0].path, "sd", false).await.unwrap();
+        assert_eq!(exported, 1);
+        assert_eq!(asiair.storage_list_images(None).await.unwrap().len(), 3);
+        assert_eq!(asiair.storage_list_images_on("sd", None).await.unwrap().len(), 1);
+
+        // A storage too small fails the export once full, the images left are kept
+        asiair_sim.attach_storage("usb", images[0].size * 2);
+        let error = asiair.export_images("Autosave", "usb", false).await.unwrap_err();
+        assert_eq!(error.to_string(), "not enough space");
+        let progress = asiair.export_get_progress().await.unwrap();
+        assert_eq!((progress.success_frame, progress.total_frame), (2, 3));
+        assert_eq!(asiair.storage_list_images(None).await.unwrap().len(), 1);
+        assert_eq!(asiair.storage_list_images_on("usb", None).await.unwrap().len(), 2);
+
+        // Unplugged storages disappear
+        asiair_sim.detach_storage("sd");
+        let info = asiair.storage_get_info().await.unwrap();
+        assert!(info.iter().all(|storage| storage.name != "sd"));
+        assert!(asiair.storage_list_images_on("sd", None).await.is_err());
+
+        // Final cleanup
+        asiair.disconnect().await;
+        asiair_sim.shutdown();
+    }
+}
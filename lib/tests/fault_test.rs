@@ -0,0 +1,173 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::init_logger;
+
+    use asiair::ASIAir;
+    use asisim::{ASIAirSim, Fault, FaultConfig, FaultRule, Port, PortFaults};
+    use serde_json::{json, Value};
+    use std::collections::HashMap;
+    use std::net::Ipv4Addr;
+    use std::time::{Duration, Instant};
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::TcpStream;
+    use tokio::sync::watch;
+
+    fn faults(port: Port, port_faults: PortFaults) -> FaultConfig {
+        FaultConfig {
+            seed: 0,
+            ports: HashMap::from([(port, port_faults)]),
+        }
+    }
+
+    fn rules(rules: Vec<FaultRule>) -> PortFaults {
+        PortFaults {
+            rules,
+            ..Default::default()
+        }
+    }
+
+    async fn send(stream: &mut BufReader<TcpStream>, id: u64, method: &str) {
+        let request = json!({ "id": id, "method": method, "params": null });
+        stream.write_all(format!("{}\r\n", request).as_bytes()).await.unwrap();
+    }
+
+    // Next response line sent by the device, skipping the events. None when nothing comes in time
+    async fn read_line(stream: &mut BufReader<TcpStream>) -> Option<String> {
+        loop {
+            let mut line = String::new();
+            tokio::time::timeout(Duration::from_millis(500), stream.read_line(&mut line)).await.ok()?.unwrap();
+            let is_event = serde_json::from_str::<Value>(&line).is_ok_and(|value| value.get("Event").is_some());
+            if !is_event {
+                return Some(line);
+            }
+        }
+    }
+
+    async fn wait_connection_state(connection_state_rx: &mut watch::Receiver<bool>, connected: bool) {
+        tokio::time::timeout(Duration::from_secs(15), connection_state_rx.wait_for(|state| *state == connected))
+            .await
+            .unwrap()
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_faults() {
+        init_logger();
+
+        // Create a new ASIAir instance
+        let addr: Ipv4Addr = Ipv4Addr::from([127, 0, 0, 1]);
+        let mut asiair = ASIAir::new(addr);
+
+        // Create a new ASIAir simulator instance
+        let mut asiair_sim = ASIAirSim::new();
+        asiair_sim.start().await.unwrap();
+        tokio::time::sleep(Duration::from_secs(1)).await; // Give the simulator some time to start
+
+        let mut stream = BufReader::new(TcpStream::connect("127.0.0.1:4700").await.unwrap());
+
+        // Scripted faults only hit their method, and expire
+        asiair_sim.set_fault_config(faults(Port::Tcp4700, rules(vec![FaultRule::method("get_app_state", Fault::Drop).times(1)])));
+        send(&mut stream, 1, "get_app_state").await;
+        assert_eq!(read_line(&mut stream).await, None);
+        send(&mut stream, 2, "test_connection").await;
+        let response: Value = serde_json::from_str(&read_line(&mut stream).await.unwrap()).unwrap();
+        assert_eq!(response["id"], 2);
+        send(&mut stream, 3, "get_app_state").await;
+        let response: Value = serde_json::from_str(&read_line(&mut stream).await.unwrap()).unwrap();
+        assert_eq!(response["id"], 3);
+
+        // Malformed and truncated responses are terminated lines that can't be parsed
+        asiair_sim.set_fault_config(faults(
+            Port::Tcp4700,
+            rules(vec![
+                FaultRule::method("get_app_state", Fault::Malformed),
+                FaultRule::method("test_connection", Fault::Truncate(8)),
+            ]),
+        ));
+        send(&mut stream, 4, "get_app_state").await;
+        let line = read_line(&mut stream).await.unwrap();
+        assert!(line.ends_with("\r\n"));
+        assert!(serde_json::from_str::<Value>(&line).is_err());
+        send(&mut stream, 5, "test_connection").await;
+        assert_eq!(read_line(&mut stream).await.unwrap().len(), 8 + 2);
+
+        // Latency delays the responses
+        asiair_sim.set_fault_config(faults(
+            Port::Tcp4700,
+            PortFaults {
+                latency: Duration::from_millis(300),
+                ..Default::default()
+            },
+        ));
+        let start = Instant::now();
+        send(&mut stream, 6, "test_connection").await;
+        read_line(&mut stream).await.unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(300));
+
+        // The socket can be closed in the middle of a response
+        asiair_sim.set_fault_config(faults(Port::Tcp4700, rules(vec![FaultRule::method("test_connection", Fault::Close)])));
+        send(&mut stream, 7, "test_connection").await;
+        let mut rest = String::new();
+        tokio::time::timeout(Duration::from_secs(1), stream.read_to_string(&mut rest)).await.unwrap().unwrap();
+        assert!(!rest.is_empty() && !rest.ends_with("\r\n"));
+
+        // The 4800 header announces the whole payload of truncated responses
+        asiair_sim.set_fault_config(faults(Port::Tcp4800, rules(vec![FaultRule::method("test_connection", Fault::Truncate(4))])));
+        let mut stream_4800 = TcpStream::connect("127.0.0.1:4800").await.unwrap();
+        stream_4800
+            .write_all(format!("{}\r\n", json!({ "id": 1, "method": "test_connection" })).as_bytes())
+            .await
+            .unwrap();
+        let mut header = [0u8; 80];
+        stream_4800.read_exact(&mut header).await.unwrap();
+        let payload_size = u32::from_be_bytes(header[6..10].try_into().unwrap()) as usize;
+        assert!(payload_size > 4);
+        let mut payload = vec![0u8; payload_size];
+        let read = tokio::time::timeout(Duration::from_millis(500), stream_4800.read_exact(&mut payload)).await;
+        assert!(read.is_err());
+
+        // Random faults are the same for the same seed
+        let mut answered = Vec::new();
+        for _ in 0..2 {
+            let mut stream = BufReader::new(TcpStream::connect("127.0.0.1:4700").await.unwrap());
+            asiair_sim.set_fault_config(FaultConfig {
+                seed: 42,
+                ports: HashMap::from([(Port::Tcp4700, rules(vec![FaultRule::random(0.5, Fault::Drop)]))]),
+            });
+            let mut run = Vec::new();
+            for id in 0..12 {
+                send(&mut stream, id, "test_connection").await;
+                run.push(read_line(&mut stream).await.is_some());
+            }
+            answered.push(run);
+        }
+        assert_eq!(answered[0], answered[1]);
+        assert!(answered[0].contains(&true) && answered[0].contains(&false));
+
+        // Events can be delayed
+        asiair_sim.set_fault_config(faults(
+            Port::Tcp4700,
+            PortFaults {
+                event_delay: Duration::from_millis(500),
+                ..Default::default()
+            },
+        ));
+        asiair.connect().await.unwrap();
+        let mut camera_list_rx = asiair.subscribe_camera_list();
+        let start = Instant::now();
+        asiair_sim.plug_camera("ZWO ASI533MC Pro").unwrap();
+        tokio::time::timeout(Duration::from_secs(5), camera_list_rx.changed()).await.unwrap().unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(500));
+
+        // The watchdog reconnects when test_connection is not answered
+        let mut connection_state_rx = asiair.subscribe_connection_state();
+        asiair_sim.set_fault_config(faults(Port::Tcp4700, rules(vec![FaultRule::method("test_connection", Fault::Drop).times(1)])));
+        wait_connection_state(&mut connection_state_rx, false).await;
+        wait_connection_state(&mut connection_state_rx, true).await;
+
+        asiair.disconnect().await;
+        asiair_sim.shutdown();
+    }
+}
@@ -0,0 +1,96 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::init_logger;
+
+    use asiair::ASIAir;
+    use asiair::mount::{GotoState, MountEvent};
+    use asisim::ASIAirSim;
+    use chrono::TimeZone;
+    use std::net::Ipv4Addr;
+    use std::time::Duration;
+
+    // Frame of the main camera binned 4x4
+    const WIDTH: f64 = 1562.0;
+    const HEIGHT: f64 = 1044.0;
+    // Arcseconds per pixel, 3.76um pixels binned 4x4 at 400mm of focal length
+    const SCALE: f64 = 206.265 * 3.76 * 4.0 / 400.0;
+
+    async fn move_focuser(asiair: &mut ASIAir, position: i32) {
+        asiair.focuser_move(position).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        while asiair.focuser_get_state().await.unwrap().moving {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_find_star() {
+        init_logger();
+
+        // Create a new ASIAir instance
+        let addr: Ipv4Addr = Ipv4Addr::from([127, 0, 0, 1]);
+        let mut asiair = ASIAir::new(addr);
+
+        // Create a new ASIAir simulator instance
+        let mut asiair_sim = ASIAirSim::new();
+        asiair_sim.start().await.unwrap();
+        tokio::time::sleep(Duration::from_secs(1)).await; // Give the simulator some time to start
+
+        asiair.connect().await.unwrap();
+
+        // Finding a star needs the main camera
+        assert!(asiair.find_star(false).await.is_err());
+        assert!(asiair.get_find_star_result().await.is_err());
+        asiair.main_camera_open(0).await.unwrap();
+        asiair.main_camera_set_bin(4).await.unwrap();
+        asiair.main_camera_set_exposure(500000).await.unwrap();
+
+        let now = chrono_tz::UTC.with_ymd_and_hms(2025, 5, 6, 20, 0, 0).unwrap();
+        asiair.set_time(now).await.unwrap();
+        asiair.mount_set_slew_rate(180.0).await.unwrap();
+        let mut mount_rx = asiair.subscribe_mount();
+        asiair.mount_goto(5.58, -5.39).await.unwrap();
+        mount_rx
+            .wait_for(|event| matches!(event, MountEvent::Goto { state: GotoState::Complete, .. }))
+            .await
+            .unwrap();
+        asiair.focuser_open().await.unwrap();
+        move_focuser(&mut asiair, asiair_sim.focus_position().round() as i32).await;
+
+        // The brightest star of the frame is found, nothing else can expose meanwhile
+        asiair.start_find_star(false).await.unwrap();
+        assert!(asiair.start_find_star(false).await.is_err());
+        assert!(asiair.main_camera_start_exposure().await.is_err());
+        let star = asiair.find_star(false).await;
+        assert!(star.is_err());
+        tokio::time::sleep(Duration::from_millis(1500)).await;
+        let star = asiair.get_find_star_result().await.unwrap();
+        assert!(star.x > 0.0 && star.x < WIDTH && star.y > 0.0 && star.y < HEIGHT, "{:?}", star);
+        assert!(star.hfr < 1.5, "{:?}", star);
+        assert!(!star.centered);
+        assert!(star.lapse_ms >= 700, "{:?}", star);
+        let again = asiair.find_star(false).await.unwrap();
+        assert!((again.x - star.x).abs() < 1.0 && (again.y - star.y).abs() < 1.0, "{:?} {:?}", star, again);
+
+        // Stars get bigger out of focus
+        move_focuser(&mut asiair, asiair_sim.focus_position().round() as i32 + 1000).await;
+        let defocused = asiair.find_star(false).await.unwrap();
+        assert!(defocused.hfr > star.hfr * 2.0, "{:?} {:?}", star, defocused);
+        move_focuser(&mut asiair, asiair_sim.focus_position().round() as i32).await;
+
+        // Centering moves the mount on the star, its sky position matches its offset in the frame
+        let star = asiair.find_star(true).await.unwrap();
+        assert!(star.centered);
+        let (ra, dec) = asiair.mount_get_position().await.unwrap();
+        assert!((ra - star.ra).abs() < 1e-6 && (dec - star.dec).abs() < 1e-6, "{:?} {:?}", star, (ra, dec));
+        let distance = ((ra - 5.58) * 15.0 * dec.to_radians().cos()).hypot(dec + 5.39) * 3600.0;
+        let offset = (star.x - WIDTH / 2.0).hypot(star.y - HEIGHT / 2.0) * SCALE;
+        assert!((distance - offset).abs() < offset * 0.01 + SCALE, "{} {}", distance, offset);
+
+        // Final cleanup
+        asiair.disconnect().await;
+        asiair_sim.shutdown();
+    }
+}
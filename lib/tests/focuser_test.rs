@@ -0,0 +1,121 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::init_logger;
+
+    use asiair::focuser::FocuserMoveState;
+    use asiair::{ASIAir, ExposureEvent};
+    use asisim::{ASIAirSim, SolveFailure, SolverConfig};
+    use std::net::Ipv4Addr;
+    use std::time::Duration;
+
+    // Expose a light frame and return its brightest pixel
+    async fn brightest_pixel(asiair: &mut ASIAir) -> u16 {
+        let mut exposure_rx = asiair.subscribe_exposure();
+        exposure_rx.mark_unchanged();
+        asiair.main_camera_start_exposure().await.unwrap();
+        tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                exposure_rx.changed().await.unwrap();
+                if let ExposureEvent::Complete = *exposure_rx.borrow_and_update() {
+                    break;
+                }
+            }
+        })
+        .await
+        .unwrap();
+
+        let (data, width, height) = asiair.main_camera_get_current_img().await.unwrap();
+        assert_eq!(data.len(), width as usize * height as usize * 2);
+        data.chunks_exact(2)
+            .map(|pixel| (i16::from_be_bytes([pixel[0], pixel[1]]) as i32 + 32768) as u16)
+            .max()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_focuser() {
+        init_logger();
+
+        // Create a new ASIAir instance
+        let addr: Ipv4Addr = Ipv4Addr::from([127, 0, 0, 1]);
+        let mut asiair = ASIAir::new(addr);
+
+        // Create a new ASIAir simulator instance
+        let mut asiair_sim = ASIAirSim::new();
+        asiair_sim.start().await.unwrap();
+        tokio::time::sleep(Duration::from_secs(1)).await; // Give the simulator some time to start
+
+        asiair_sim.set_focuser_temperature(10.0, 0.0);
+        asiair.connect().await.unwrap();
+
+        // The focuser has to be opened first
+        assert!(asiair.focuser_get_position().await.is_err());
+        asiair.focuser_open().await.unwrap();
+        let state = asiair.focuser_get_state().await.unwrap();
+        assert_eq!(state.position, 5000);
+        assert_eq!(state.temperature, 10.0);
+        assert!(!state.moving);
+
+        // Moves are reported by events
+        let mut focuser_rx = asiair.subscribe_focuser();
+        focuser_rx.mark_unchanged();
+        asiair.focuser_move(5350).await.unwrap();
+        tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                focuser_rx.changed().await.unwrap();
+                if focuser_rx.borrow_and_update().state == FocuserMoveState::Idle {
+                    break;
+                }
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(asiair.focuser_get_position().await.unwrap(), 5350);
+        assert!(asiair.focuser_move(20000).await.is_err());
+        assert!(asiair.focuser_move(-1).await.is_err());
+
+        // Autofocus needs the main camera
+        assert!(asiair.auto_focus(100, 9).await.is_err());
+        asiair.main_camera_open(0).await.unwrap();
+        asiair.main_camera_set_bin(4).await.unwrap();
+        asiair.main_camera_set_exposure(100000).await.unwrap();
+
+        // Params that would overflow the range of the curve are refused without harming the simulator
+        assert!(asiair.auto_focus(1 << 30, 5).await.is_err());
+        assert!(asiair.auto_focus(i32::MAX, 3).await.is_err());
+        assert!(asiair.auto_focus(100, u32::MAX).await.is_err());
+        assert_eq!(asiair.focuser_get_position().await.unwrap(), 5350);
+
+        let result = asiair.auto_focus(100, 9).await.unwrap();
+        assert_eq!(result.points.len(), 9);
+        assert_eq!(result.points[0].position, 4950);
+        assert_eq!(result.points[8].position, 5750);
+        // The curve is measured with the backlash taken up, which shifts it by at most the backlash
+        assert!((result.best_position as f64 - asiair_sim.focus_position()).abs() < 50.0);
+        assert!(result.best_hfr < 1.0);
+        assert_eq!(asiair.focuser_get_position().await.unwrap(), result.best_position);
+
+        // Stars are sharper, so brighter at their peak, once in focus
+        let focused = brightest_pixel(&mut asiair).await;
+        asiair.focuser_move(result.best_position + 1500).await.unwrap();
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        let defocused = brightest_pixel(&mut asiair).await;
+        assert!(focused > defocused, "focused {focused}, defocused {defocused}");
+
+        // Without stars there is no curve, the focuser goes back to where it was
+        asiair_sim.set_solver_config(SolverConfig {
+            failure: Some(SolveFailure::NoStars),
+            ..asiair_sim.solver_config()
+        });
+        let start = asiair.focuser_get_position().await.unwrap();
+        let error = asiair.auto_focus(100, 5).await.unwrap_err();
+        assert_eq!(error.to_string(), "no stars");
+        assert_eq!(asiair.focuser_get_position().await.unwrap(), start);
+
+        // Final cleanup
+        asiair.disconnect().await;
+        asiair_sim.shutdown();
+    }
+}
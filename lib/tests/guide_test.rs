@@ -0,0 +1,124 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::init_logger;
+
+    use asiair::guide::GuideState;
+    use asiair::ASIAir;
+    use asisim::{ASIAirSim, GuiderConfig};
+    use std::net::Ipv4Addr;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_guide() {
+        init_logger();
+
+        // Create a new ASIAir instance
+        let addr: Ipv4Addr = Ipv4Addr::from([127, 0, 0, 1]);
+        let mut asiair = ASIAir::new(addr);
+
+        // Create a new ASIAir simulator instance
+        let mut asiair_sim = ASIAirSim::new();
+        asiair_sim.start().await.unwrap();
+        tokio::time::sleep(Duration::from_secs(1)).await; // Give the simulator some time to start
+
+        asiair_sim.set_guider_config(GuiderConfig {
+            exposure: Duration::from_millis(100),
+            periodic_error_period: Duration::from_secs(10),
+            settle_time: Duration::from_millis(300),
+            settle_timeout: Duration::from_secs(2),
+            ..Default::default()
+        });
+
+        asiair.connect().await.unwrap();
+
+        // Guiding needs a tracking mount
+        assert!(asiair.guide_start().await.is_err());
+        asiair.mount_set_tracking(true).await.unwrap();
+
+        let mut steps = asiair.guide_steps();
+        asiair.guide_start().await.unwrap();
+        assert!(asiair.guide_start().await.is_err());
+
+        // Every frame is corrected by a pulse against the error
+        let mut frame = 0;
+        for _ in 0..20 {
+            let step = tokio::time::timeout(Duration::from_secs(2), steps.recv()).await.unwrap().unwrap();
+            assert!(frame == 0 || step.frame == frame + 1);
+            frame = step.frame;
+            assert_eq!(step.state, GuideState::Guiding);
+            assert!(step.ra_pulse * step.ra_error <= 0.0);
+            assert!(step.dec_pulse * step.dec_error <= 0.0);
+        }
+        let status = asiair.guide_get_state().await.unwrap();
+        assert_eq!(status.state, GuideState::Guiding);
+        assert!(status.frame >= 20);
+        assert!(status.rms_total > 0.0 && status.rms_total < 5.0);
+        assert!((status.rms_total - status.rms_ra.hypot(status.rms_dec)).abs() < 1e-9);
+
+        // Dithers settle back to guiding, none of the frames taken meanwhile is missed
+        asiair.dither_and_settle(3.0, false).await.unwrap();
+        assert_eq!(asiair.guide_get_state().await.unwrap().state, GuideState::Guiding);
+        let mut settling = 0;
+        loop {
+            let step = tokio::time::timeout(Duration::from_secs(2), steps.recv()).await.unwrap().unwrap();
+            assert_eq!(step.frame, frame + 1);
+            frame = step.frame;
+            match step.state {
+                GuideState::Settling => settling += 1,
+                GuideState::Guiding if settling > 0 => break,
+                _ => {}
+            }
+        }
+        assert!(settling >= 3);
+
+        // Settling can time out
+        asiair_sim.set_guider_config(GuiderConfig {
+            settle_px: 0.0,
+            ..asiair_sim.guider_config()
+        });
+        let error = asiair.dither_and_settle(3.0, true).await.unwrap_err();
+        assert_eq!(error.to_string(), "settle timeout");
+        asiair_sim.set_guider_config(GuiderConfig {
+            settle_px: 1.5,
+            ..asiair_sim.guider_config()
+        });
+
+        // Frames without the star are reported, and guiding resumes once it is back
+        asiair_sim.set_guider_config(GuiderConfig {
+            star_lost: true,
+            ..asiair_sim.guider_config()
+        });
+        let lost = tokio::time::timeout(Duration::from_secs(2), async {
+            loop {
+                let step = steps.recv().await.unwrap();
+                if step.state == GuideState::Lost {
+                    return step;
+                }
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(lost.error.as_deref(), Some("star lost"));
+        asiair_sim.set_guider_config(GuiderConfig {
+            star_lost: false,
+            ..asiair_sim.guider_config()
+        });
+        tokio::time::timeout(Duration::from_secs(2), async {
+            while steps.recv().await.unwrap().state != GuideState::Guiding {}
+        })
+        .await
+        .unwrap();
+
+        // Stopping guiding ends the stream, dithers then have nothing to wait for
+        asiair.guide_stop().await.unwrap();
+        while steps.recv().await.is_some() {}
+        assert_eq!(asiair.guide_get_state().await.unwrap().state, GuideState::Idle);
+        asiair.dither_and_settle(3.0, false).await.unwrap();
+
+        // Final cleanup
+        asiair.disconnect().await;
+        asiair_sim.shutdown();
+    }
+}
@@ -0,0 +1,121 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::init_logger;
+
+    use asiair::camera::{CameraListEvent, CameraState};
+    use asiair::{ASIAir, ExposureEvent};
+    use asisim::ASIAirSim;
+    use std::net::Ipv4Addr;
+    use std::time::Duration;
+    use tokio::sync::watch;
+
+    const MAIN_CAMERA: &str = "ZWO ASI2600MC Pro";
+
+    async fn next_camera_list(camera_list_rx: &mut watch::Receiver<CameraListEvent>) -> CameraListEvent {
+        tokio::time::timeout(Duration::from_secs(5), camera_list_rx.changed())
+            .await
+            .unwrap()
+            .unwrap();
+        camera_list_rx.borrow_and_update().clone()
+    }
+
+    #[tokio::test]
+    async fn test_hotplug() {
+        init_logger();
+
+        // Create a new ASIAir instance
+        let addr: Ipv4Addr = Ipv4Addr::from([127, 0, 0, 1]);
+        let mut asiair = ASIAir::new(addr);
+
+        // Create a new ASIAir simulator instance
+        let mut asiair_sim = ASIAirSim::new();
+        asiair_sim.start().await.unwrap();
+        tokio::time::sleep(Duration::from_secs(1)).await; // Give the simulator some time to start
+
+        asiair.connect().await.unwrap();
+        asiair.main_camera_open(0).await.unwrap();
+        asiair.main_camera_set_bin(4).await.unwrap();
+        asiair.main_camera_set_exposure(5_000_000).await.unwrap();
+
+        // Unplugging the guide camera only changes the list
+        let mut camera_list_rx = asiair.subscribe_camera_list();
+        asiair_sim.unplug_camera("ZWO ASI462MM").unwrap();
+        let event = next_camera_list(&mut camera_list_rx).await;
+        assert_eq!(event.unplugged.as_deref(), Some("ZWO ASI462MM"));
+        assert_eq!(event.plugged, None);
+        assert_eq!(event.cameras.len(), 1);
+        assert!(matches!(asiair.main_camera_get_state().await.unwrap(), CameraState::Idle { .. }));
+
+        // Unplugging the open camera closes it and fails its exposure
+        let mut exposure_rx = asiair.subscribe_exposure();
+        let mut camera_state_change_rx = asiair.subscribe_camera_state_change();
+        camera_state_change_rx.mark_unchanged();
+        asiair.main_camera_start_exposure().await.unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        asiair_sim.unplug_camera(MAIN_CAMERA).unwrap();
+        let event = next_camera_list(&mut camera_list_rx).await;
+        assert_eq!(event.unplugged.as_deref(), Some(MAIN_CAMERA));
+        assert!(event.cameras.is_empty());
+        let error = tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                exposure_rx.changed().await.unwrap();
+                if let ExposureEvent::Fail { error } = &*exposure_rx.borrow_and_update() {
+                    return error.clone();
+                }
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(error, "camera disconnected");
+        tokio::time::timeout(Duration::from_secs(5), camera_state_change_rx.changed())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(asiair.main_camera_get_state().await.unwrap(), CameraState::Close);
+
+        // Without reopening, the camera stays closed when plugged back in
+        asiair_sim.plug_camera(MAIN_CAMERA).unwrap();
+        let event = next_camera_list(&mut camera_list_rx).await;
+        assert_eq!(event.plugged.as_deref(), Some(MAIN_CAMERA));
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        assert_eq!(asiair.main_camera_get_state().await.unwrap(), CameraState::Close);
+
+        // With reopening, the main camera is opened again with its new id
+        asiair.set_reopen_main_camera(true);
+        asiair_sim.unplug_camera(MAIN_CAMERA).unwrap();
+        next_camera_list(&mut camera_list_rx).await;
+        asiair_sim.plug_camera("ZWO ASI462MM").unwrap();
+        next_camera_list(&mut camera_list_rx).await;
+        asiair_sim.plug_camera(MAIN_CAMERA).unwrap();
+        let event = next_camera_list(&mut camera_list_rx).await;
+        let main_camera = event.cameras.iter().find(|camera| camera.name == MAIN_CAMERA).unwrap().clone();
+        tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                if let CameraState::Idle { name, path } = asiair.main_camera_get_state().await.unwrap() {
+                    assert_eq!(name, MAIN_CAMERA);
+                    assert_eq!(path, main_camera.path);
+                    break;
+                }
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+        })
+        .await
+        .unwrap();
+
+        // Other cameras are not opened in its place
+        asiair_sim.unplug_camera("ZWO ASI462MM").unwrap();
+        next_camera_list(&mut camera_list_rx).await;
+        asiair_sim.plug_camera("ZWO ASI462MM").unwrap();
+        next_camera_list(&mut camera_list_rx).await;
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        match asiair.main_camera_get_state().await.unwrap() {
+            CameraState::Idle { name, .. } => assert_eq!(name, MAIN_CAMERA),
+            CameraState::Close => panic!("the main camera was closed"),
+        }
+
+        asiair.disconnect().await;
+        asiair_sim.shutdown();
+    }
+}
@@ -0,0 +1,120 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::init_logger;
+
+    use asiair::ASIAir;
+    use asiair::camera::FrameType;
+    use asiair::guide::GuideState;
+    use asiair::merid_flip::{MeridFlipSettings, MeridFlipState};
+    use asiair::mount::{GotoState, MountEvent, PierSide};
+    use asiair::sequencer::{SequencePlan, SequenceState, SequenceStep, Sequencer};
+    use asisim::{ASIAirSim, SolveFailure, SolverConfig};
+    use chrono::TimeZone;
+    use std::net::Ipv4Addr;
+    use std::time::Duration;
+
+    // Slew to a target crossing the meridian in a few seconds
+    async fn goto_before_meridian(asiair: &mut ASIAir) {
+        let state = asiair.mount_get_state().await.unwrap();
+        let lst = state.position.ra + state.hour_angle;
+        let mut mount_rx = asiair.subscribe_mount();
+        mount_rx.mark_unchanged();
+        asiair.mount_goto((lst + 0.0005).rem_euclid(24.0), 0.0).await.unwrap();
+        // The last goto may already be complete, only look at the events of this one
+        tokio::time::timeout(Duration::from_secs(10), async {
+            loop {
+                mount_rx.changed().await.unwrap();
+                if matches!(*mount_rx.borrow_and_update(), MountEvent::Goto { state: GotoState::Complete, .. }) {
+                    break;
+                }
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(asiair.mount_get_state().await.unwrap().position.pier_side, PierSide::West);
+    }
+
+    #[tokio::test]
+    async fn test_merid_flip() {
+        init_logger();
+
+        // Create a new ASIAir instance
+        let addr: Ipv4Addr = Ipv4Addr::from([127, 0, 0, 1]);
+        let mut asiair = ASIAir::new(addr);
+
+        // Create a new ASIAir simulator instance
+        let mut asiair_sim = ASIAirSim::new();
+        asiair_sim.start().await.unwrap();
+        tokio::time::sleep(Duration::from_secs(1)).await; // Give the simulator some time to start
+
+        asiair_sim.set_solver_config(SolverConfig {
+            latency: Duration::from_millis(100),
+            ..Default::default()
+        });
+
+        asiair.connect().await.unwrap();
+        let now = chrono_tz::UTC.with_ymd_and_hms(2025, 5, 6, 20, 0, 0).unwrap();
+        asiair.set_time(now).await.unwrap();
+        asiair.mount_set_slew_rate(360.0).await.unwrap();
+        asiair.main_camera_open(0).await.unwrap();
+
+        let settings = asiair.merid_flip_get_settings().await.unwrap();
+        assert_eq!(settings, MeridFlipSettings { auto_flip: false, minutes_past_meridian: 5.0 });
+        let settings = MeridFlipSettings { auto_flip: true, minutes_past_meridian: 0.05 };
+        asiair.merid_flip_set_settings(settings).await.unwrap();
+        assert_eq!(asiair.merid_flip_get_settings().await.unwrap(), settings);
+
+        // Only a target past the meridian can be flipped
+        goto_before_meridian(&mut asiair).await;
+        assert!(asiair.start_merid_flip().await.is_err());
+        asiair.guide_start().await.unwrap();
+
+        // The sequence waits for the flip and captures the interrupted frame again
+        let plan = SequencePlan {
+            steps: vec![SequenceStep {
+                frame_type: FrameType::Light,
+                count: 6,
+                exposure_us: 1000000,
+                bin: 4,
+                ..Default::default()
+            }],
+            output_dir: std::env::temp_dir().join("asiair_merid_flip_test"),
+            ..Default::default()
+        };
+        let sequencer = Sequencer::new(asiair.clone(), plan.clone());
+        let mut progress_rx = sequencer.subscribe_progress();
+        let flip_seen = tokio::spawn(async move {
+            progress_rx.wait_for(|progress| progress.state == SequenceState::MeridianFlip).await.is_ok()
+        });
+        let mut merid_flip_rx = asiair.subscribe_merid_flip();
+        let progress = sequencer.run().await.unwrap();
+        assert_eq!(progress.state, SequenceState::Completed);
+        assert_eq!(progress.completed_frames, 6);
+        assert!(flip_seen.await.unwrap());
+        assert_eq!(merid_flip_rx.borrow_and_update().state, MeridFlipState::Complete);
+
+        // The mount is on the other side of the pier and guiding again
+        assert_eq!(asiair.mount_get_state().await.unwrap().position.pier_side, PierSide::East);
+        assert_ne!(asiair.guide_get_state().await.unwrap().state, GuideState::Idle);
+        asiair.guide_stop().await.unwrap();
+
+        // A flip that can't solve the target again fails the sequence
+        asiair_sim.set_solver_config(SolverConfig {
+            failure: Some(SolveFailure::NoStars),
+            ..asiair_sim.solver_config()
+        });
+        goto_before_meridian(&mut asiair).await;
+        let sequencer = Sequencer::new(asiair.clone(), plan);
+        let error = sequencer.run().await.unwrap_err();
+        assert_eq!(error.to_string(), "meridian flip failed: no stars");
+        let event = merid_flip_rx.borrow_and_update().clone();
+        assert_eq!(event.state, MeridFlipState::Fail);
+        assert_eq!(event.error.as_deref(), Some("no stars"));
+
+        // Final cleanup
+        asiair.disconnect().await;
+        asiair_sim.shutdown();
+    }
+}
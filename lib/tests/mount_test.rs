@@ -0,0 +1,126 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::init_logger;
+
+    use asiair::ASIAir;
+    use asiair::mount::{GotoState, MountEvent, ParkState, PierSide, TrackMode};
+    use asisim::ASIAirSim;
+    use chrono::TimeZone;
+    use std::net::Ipv4Addr;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_mount() {
+        init_logger();
+
+        // Create a new ASIAir instance
+        let addr: Ipv4Addr = Ipv4Addr::from([127, 0, 0, 1]);
+        let mut asiair = ASIAir::new(addr);
+
+        // Create a new ASIAir simulator instance
+        let mut asiair_sim = ASIAirSim::new();
+        asiair_sim.start().await.unwrap();
+        tokio::time::sleep(Duration::from_secs(1)).await; // Give the simulator some time to start
+
+        asiair.connect().await.unwrap();
+
+        // M42 is just east of the meridian at that time
+        let now = chrono_tz::UTC.with_ymd_and_hms(2025, 5, 6, 20, 0, 0).unwrap();
+        asiair.set_time(now).await.unwrap();
+        asiair.mount_set_location(9.93, -84.08).await.unwrap();
+        assert!(asiair.mount_set_slew_rate(0.0).await.is_err());
+        assert!(asiair.mount_set_slew_rate(-90.0).await.is_err());
+        asiair.mount_set_slew_rate(90.0).await.unwrap();
+
+        let mut mount_rx = asiair.subscribe_mount();
+        asiair.mount_goto(5.58, -5.39).await.unwrap();
+        tokio::time::timeout(
+            Duration::from_secs(10),
+            mount_rx.wait_for(|event| matches!(event, MountEvent::Goto { state: GotoState::Complete, .. })),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+        let state = asiair.mount_get_state().await.unwrap();
+        assert!((state.position.ra - 5.58).abs() < 0.001);
+        assert!((state.position.dec + 5.39).abs() < 0.001);
+        assert!(state.position.tracking);
+        assert!(!state.position.slewing);
+        assert_eq!(state.position.pier_side, PierSide::West);
+        assert!(state.hour_angle < 0.0);
+        assert!(state.position.alt > 60.0);
+
+        // The goto is the target of the next frames
+        let setting = asiair.get_app_setting().await.unwrap();
+        assert_eq!(setting.goto_target_name, "");
+        assert_eq!((setting.goto_target_ra, setting.goto_target_dec), (5.58, -5.39));
+        asiair.mount_goto_named(5.59, -5.39, "M42").await.unwrap();
+        tokio::time::timeout(
+            Duration::from_secs(10),
+            mount_rx.wait_for(|event| {
+                matches!(event, MountEvent::Goto { state: GotoState::Complete, ra, .. } if (ra - 5.59).abs() < 0.001)
+            }),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        let setting = asiair.get_app_setting().await.unwrap();
+        assert_eq!(setting.goto_target_name, "M42");
+        assert_eq!((setting.goto_target_ra, setting.goto_target_dec), (5.59, -5.39));
+
+        // The sky drifts under the mount once tracking stops
+        asiair.mount_set_tracking(false).await.unwrap();
+        assert!(!asiair.mount_get_tracking().await.unwrap());
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        let (ra, _) = asiair.mount_get_position().await.unwrap();
+        assert!(ra - 5.59 > 0.0004);
+
+        asiair.mount_set_track_mode(TrackMode::Lunar).await.unwrap();
+        asiair.mount_set_tracking(true).await.unwrap();
+        let state = asiair.mount_get_state().await.unwrap();
+        assert_eq!(state.track_mode, TrackMode::Lunar);
+        assert!(state.position.tracking);
+
+        // Targets below the horizon can't be reached
+        assert!(asiair.mount_goto(5.58, -85.0).await.is_err());
+        assert!(asiair.mount_goto(25.0, 0.0).await.is_err());
+
+        // Abort a slew halfway
+        asiair.mount_set_slew_rate(10.0).await.unwrap();
+        asiair.mount_goto(5.0, 40.0).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        assert!(asiair.mount_get_state().await.unwrap().position.slewing);
+        asiair.mount_abort().await.unwrap();
+        let state = asiair.mount_get_state().await.unwrap();
+        assert!(!state.position.slewing);
+        assert!(state.position.dec < 40.0);
+
+        // Park, then only unparking allows moving again
+        asiair.mount_set_slew_rate(90.0).await.unwrap();
+        asiair.mount_park().await.unwrap();
+        tokio::time::timeout(
+            Duration::from_secs(10),
+            mount_rx.wait_for(|event| *event == MountEvent::Park { state: ParkState::Complete }),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+        let state = asiair.mount_get_state().await.unwrap();
+        assert!(state.position.parked);
+        assert!(!state.position.tracking);
+        assert_eq!(state.position.dec, 90.0);
+        assert!(asiair.mount_goto(5.58, -5.39).await.is_err());
+        assert!(asiair.mount_set_tracking(true).await.is_err());
+
+        asiair.mount_unpark().await.unwrap();
+        asiair.mount_goto(5.58, -5.39).await.unwrap();
+
+        // Final cleanup
+        asiair.disconnect().await;
+        asiair_sim.shutdown();
+    }
+}
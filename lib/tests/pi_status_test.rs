@@ -0,0 +1,102 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::init_logger;
+
+    use asiair::{ASIAir, ExposureEvent, PiStatusEvent};
+    use asisim::{ASIAirSim, PiConfig};
+    use std::net::Ipv4Addr;
+    use std::time::Duration;
+    use tokio::sync::watch;
+
+    async fn wait_status(pi_status_rx: &mut watch::Receiver<PiStatusEvent>, until: impl Fn(&PiStatusEvent) -> bool) -> PiStatusEvent {
+        tokio::time::timeout(Duration::from_secs(10), async {
+            loop {
+                pi_status_rx.changed().await.unwrap();
+                let event = pi_status_rx.borrow_and_update().clone();
+                if until(&event) {
+                    return event;
+                }
+            }
+        })
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_pi_status() {
+        init_logger();
+
+        // Create a new ASIAir instance
+        let addr: Ipv4Addr = Ipv4Addr::from([127, 0, 0, 1]);
+        let mut asiair = ASIAir::new(addr);
+
+        // Create a new ASIAir simulator instance, with a board heating up quickly
+        let mut asiair_sim = ASIAirSim::new();
+        asiair_sim.set_pi_config(PiConfig {
+            time_constant: Duration::from_millis(500),
+            status_interval: Duration::from_millis(200),
+            download_rate: 1.0e6,
+            download_rise: 40.0,
+            ..Default::default()
+        });
+        asiair_sim.start().await.unwrap();
+        tokio::time::sleep(Duration::from_secs(1)).await; // Give the simulator some time to start
+
+        asiair.connect().await.unwrap();
+
+        // The board reports its temperature periodically, at rest it settles above the ambient
+        let mut pi_status_rx = asiair.subscribe_pi_status();
+        pi_status_rx.mark_unchanged();
+        let status = wait_status(&mut pi_status_rx, |_| true).await;
+        assert!((status.temp - 45.0).abs() < 1.0, "{:?}", status);
+        assert!(!status.is_overtemp && !status.is_undervolt && !status.is_over_current);
+
+        // The live stack heats the CPU, which cools down once it stops
+        asiair.main_camera_open(0).await.unwrap();
+        asiair.main_camera_set_bin(4).await.unwrap();
+        asiair.main_camera_set_exposure(100000).await.unwrap();
+        asiair.stack_start().await.unwrap();
+        let status = wait_status(&mut pi_status_rx, |status| status.temp > 58.0).await;
+        assert!(!status.is_overtemp);
+        asiair.stack_stop().await.unwrap();
+        wait_status(&mut pi_status_rx, |status| status.temp < 46.0).await;
+
+        // Downloads heat it too, past the overtemp threshold here
+        let mut exposure_rx = asiair.subscribe_exposure();
+        exposure_rx.mark_unchanged();
+        asiair.main_camera_start_exposure().await.unwrap();
+        loop {
+            exposure_rx.changed().await.unwrap();
+            if let ExposureEvent::Complete = *exposure_rx.borrow_and_update() {
+                break;
+            }
+        }
+        asiair.main_camera_get_current_img().await.unwrap();
+        let status = wait_status(&mut pi_status_rx, |status| status.is_overtemp).await;
+        assert!(status.temp >= 80.0, "{:?}", status);
+        assert!(asiair_sim.pi_temperature() >= 79.0);
+        wait_status(&mut pi_status_rx, |status| !status.is_overtemp).await;
+
+        // Power faults are triggered by the test
+        asiair_sim.set_pi_config(PiConfig {
+            undervolt: true,
+            ..asiair_sim.pi_config()
+        });
+        let status = wait_status(&mut pi_status_rx, |status| status.is_undervolt).await;
+        assert!(!status.is_over_current && !status.is_overtemp);
+        asiair_sim.set_pi_config(PiConfig {
+            undervolt: false,
+            over_current: true,
+            force_overtemp: true,
+            ..asiair_sim.pi_config()
+        });
+        let status = wait_status(&mut pi_status_rx, |status| status.is_over_current).await;
+        assert!(!status.is_undervolt && status.is_overtemp && status.temp < 80.0);
+
+        // Final cleanup
+        asiair.disconnect().await;
+        asiair_sim.shutdown();
+    }
+}
@@ -0,0 +1,163 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::init_logger;
+
+    use asiair::plan::{Plan, PlanExposure, PlanState, PlanTarget};
+    use asiair::{ASIAir, ASIAirPage, ExposureEvent};
+    use asisim::ASIAirSim;
+    use chrono::{DateTime, TimeZone};
+    use std::net::Ipv4Addr;
+    use std::time::Duration;
+
+    fn target(name: &str, start: &str, end: &str, count: u32) -> PlanTarget {
+        PlanTarget {
+            name: name.to_string(),
+            ra: 5.58,
+            dec: -5.39,
+            start_time: DateTime::parse_from_rfc3339(start).unwrap(),
+            end_time: DateTime::parse_from_rfc3339(end).unwrap(),
+            exposures: vec![
+                PlanExposure {
+                    filter: "L".to_string(),
+                    exp_us: 100000,
+                    count,
+                    done: 0,
+                },
+                PlanExposure {
+                    filter: "Ha".to_string(),
+                    exp_us: 100000,
+                    count,
+                    done: 0,
+                },
+            ],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_plan() {
+        init_logger();
+
+        // Create a new ASIAir instance
+        let addr: Ipv4Addr = Ipv4Addr::from([127, 0, 0, 1]);
+        let mut asiair = ASIAir::new(addr);
+
+        // Create a new ASIAir simulator instance
+        let mut asiair_sim = ASIAirSim::new();
+        asiair_sim.start().await.unwrap();
+        tokio::time::sleep(Duration::from_secs(1)).await; // Give the simulator some time to start
+
+        asiair.connect().await.unwrap();
+        asiair.set_page(ASIAirPage::Plan).await.unwrap();
+
+        // Plans follow the device clock
+        let now = chrono_tz::UTC.with_ymd_and_hms(2025, 5, 6, 20, 0, 0).unwrap();
+        asiair.set_time(now).await.unwrap();
+        asiair.mount_set_slew_rate(180.0).await.unwrap();
+
+        let plan = Plan {
+            name: "Orion".to_string(),
+            targets: vec![
+                target("M42", "2025-05-06T19:59:00Z", "2025-05-06T23:00:00Z", 2),
+                target("M43", "2025-05-06T18:00:00Z", "2025-05-06T19:00:00Z", 2),
+                target("NGC 2024", "2025-05-06T20:00:02Z", "2025-05-06T23:00:00Z", 1),
+            ],
+        };
+        asiair.create_plan(&plan).await.unwrap();
+
+        let plans = asiair.list_plans().await.unwrap();
+        assert_eq!(plans, vec![plan.clone()]);
+
+        assert!(asiair.start_plan("Unknown").await.is_err());
+
+        let mut plan_rx = asiair.subscribe_plan();
+        asiair.start_plan("Orion").await.unwrap();
+        assert!(asiair.start_plan("Orion").await.is_err());
+
+        let event = tokio::time::timeout(
+            Duration::from_secs(10),
+            plan_rx.wait_for(|event| event.state == PlanState::Complete),
+        )
+        .await
+        .unwrap()
+        .unwrap()
+        .clone();
+        assert_eq!(event.plan, "Orion");
+
+        let plans = asiair.list_plans().await.unwrap();
+        let done: Vec<Vec<u32>> = plans[0]
+            .targets
+            .iter()
+            .map(|t| t.exposures.iter().map(|e| e.done).collect())
+            .collect();
+        assert_eq!(done, vec![vec![2, 2], vec![0, 0], vec![1, 1]]);
+
+        // Stop a plan while it is running
+        let plan = Plan {
+            name: "Long".to_string(),
+            targets: vec![target("M31", "2025-05-06T19:00:00Z", "2025-05-06T23:00:00Z", 100)],
+        };
+        asiair.create_plan(&plan).await.unwrap();
+        asiair.start_plan("Long").await.unwrap();
+        plan_rx
+            .wait_for(|event| event.state == PlanState::Working)
+            .await
+            .unwrap();
+        assert!(asiair.delete_plan("Long").await.is_err());
+        asiair.stop_plan().await.unwrap();
+        plan_rx
+            .wait_for(|event| event.state == PlanState::Stop)
+            .await
+            .unwrap();
+
+        asiair.delete_plan("Long").await.unwrap();
+        let plans = asiair.list_plans().await.unwrap();
+        assert_eq!(plans.len(), 1);
+
+        // Stopping the exposure of a plan cancels it, the plan takes it again
+        let mut plan = Plan {
+            name: "Slow".to_string(),
+            targets: vec![target("M78", "2025-05-06T19:00:00Z", "2025-05-06T23:00:00Z", 1)],
+        };
+        plan.targets[0].exposures.truncate(1);
+        plan.targets[0].exposures[0].exp_us = 1000000;
+        asiair.create_plan(&plan).await.unwrap();
+        let mut exposure_rx = asiair.subscribe_exposure();
+        asiair.start_plan("Slow").await.unwrap();
+        tokio::time::timeout(
+            Duration::from_secs(10),
+            exposure_rx.wait_for(|event| matches!(event, ExposureEvent::Start { .. })),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        exposure_rx.mark_unchanged();
+        asiair.main_camera_stop_exposure().await.unwrap();
+        let next = tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                exposure_rx.changed().await.unwrap();
+                let event = exposure_rx.borrow_and_update().clone();
+                if !matches!(event, ExposureEvent::Cancel) {
+                    return event;
+                }
+            }
+        })
+        .await
+        .unwrap();
+        assert!(matches!(next, ExposureEvent::Start { .. }), "{next:?}");
+        tokio::time::timeout(
+            Duration::from_secs(10),
+            plan_rx.wait_for(|event| event.plan == "Slow" && event.state == PlanState::Complete),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        let plans = asiair.list_plans().await.unwrap();
+        assert_eq!(plans[1].targets[0].exposures[0].done, 1);
+
+        // Final cleanup
+        asiair.disconnect().await;
+        asiair_sim.shutdown();
+    }
+}
@@ -0,0 +1,93 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::init_logger;
+
+    use asiair::{ASIAir, ASIAirPage};
+    use asisim::{ASIAirSim, SolveFailure, SolverConfig};
+    use std::net::Ipv4Addr;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_polar_align() {
+        init_logger();
+
+        // Create a new ASIAir instance
+        let addr: Ipv4Addr = Ipv4Addr::from([127, 0, 0, 1]);
+        let mut asiair = ASIAir::new(addr);
+
+        // Create a new ASIAir simulator instance
+        let mut asiair_sim = ASIAirSim::new();
+        asiair_sim.start().await.unwrap();
+        tokio::time::sleep(Duration::from_secs(1)).await; // Give the simulator some time to start
+
+        asiair_sim.set_solver_config(SolverConfig {
+            latency: Duration::from_millis(100),
+            noise_arcsec: 0.0,
+            ..Default::default()
+        });
+        asiair_sim.set_polar_error(30.0, -45.0);
+
+        asiair.connect().await.unwrap();
+        asiair.set_page(ASIAirPage::PA).await.unwrap();
+
+        // Polar alignment needs the main camera
+        assert!(asiair.polar_align().await.is_err());
+        asiair.main_camera_open(0).await.unwrap();
+        asiair.mount_set_slew_rate(180.0).await.unwrap();
+
+        let mut readings = asiair.polar_align().await.unwrap();
+        let reading = tokio::time::timeout(Duration::from_secs(10), readings.recv())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        assert_eq!(reading.alt_error, 30.0);
+        assert_eq!(reading.az_error, -45.0);
+        assert!((reading.total_error - 54.083).abs() < 0.001);
+
+        // The error shrinks while the knobs are turned, readings taken before may still be queued
+        asiair_sim.adjust_polar_knobs(-20.0, 40.0);
+        let reading = tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                let reading = readings.recv().await.unwrap().unwrap();
+                if reading.alt_error != 30.0 {
+                    return reading;
+                }
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(reading.alt_error, 10.0);
+        assert_eq!(reading.az_error, -5.0);
+
+        asiair_sim.adjust_polar_knobs(-10.0, 5.0);
+        assert_eq!(asiair_sim.polar_error(), (0.0, 0.0));
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while readings.recv().await.unwrap().unwrap().total_error > 0.001 {}
+        })
+        .await
+        .unwrap();
+
+        // Stopping the routine ends the stream
+        asiair.stop_polar_align().await.unwrap();
+        while let Some(reading) = readings.recv().await {
+            assert!(reading.is_ok());
+        }
+
+        // Failures to solve end the stream with an error
+        asiair_sim.set_solver_config(SolverConfig {
+            failure: Some(SolveFailure::NoStars),
+            ..asiair_sim.solver_config()
+        });
+        let mut readings = asiair.polar_align().await.unwrap();
+        let error = readings.recv().await.unwrap().unwrap_err();
+        assert_eq!(error.to_string(), "no stars");
+        assert!(readings.recv().await.is_none());
+
+        // Final cleanup
+        asiair.disconnect().await;
+        asiair_sim.shutdown();
+    }
+}
@@ -0,0 +1,102 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::init_logger;
+
+    use asiair::ASIAir;
+    use asiair::record::{AviRecordState, VideoFormat};
+    use asisim::ASIAirSim;
+    use std::net::Ipv4Addr;
+    use std::time::Duration;
+
+    // Bytes of a frame of the main camera binned 4x4
+    const FRAME_BYTES: f64 = 1562.0 * 1044.0 * 2.0;
+
+    fn assert_close(value: f32, expected: f64) {
+        assert!((value as f64 - expected).abs() < expected * 0.01, "{} != {}", value, expected);
+    }
+
+    #[tokio::test]
+    async fn test_record() {
+        init_logger();
+
+        // Create a new ASIAir instance
+        let addr: Ipv4Addr = Ipv4Addr::from([127, 0, 0, 1]);
+        let mut asiair = ASIAir::new(addr);
+
+        // Create a new ASIAir simulator instance
+        let mut asiair_sim = ASIAirSim::new();
+        asiair_sim.start().await.unwrap();
+        tokio::time::sleep(Duration::from_secs(1)).await; // Give the simulator some time to start
+
+        asiair.connect().await.unwrap();
+
+        // Recording needs the main camera
+        assert!(asiair.record_start(VideoFormat::Ser, None).await.is_err());
+        asiair.main_camera_open(0).await.unwrap();
+        asiair.main_camera_set_bin(4).await.unwrap();
+        asiair.main_camera_set_exposure(1000).await.unwrap();
+        assert!(asiair.main_camera_get_info().await.unwrap().is_usb3_host);
+
+        // On USB3 short exposures are limited by the link, and the file by the storage
+        asiair.record_start(VideoFormat::Ser, None).await.unwrap();
+        assert!(asiair.record_start(VideoFormat::Ser, None).await.is_err());
+        assert!(asiair.main_camera_start_exposure().await.is_err());
+        tokio::time::sleep(Duration::from_millis(1200)).await;
+        let status = asiair.record_get_state().await.unwrap();
+        assert!(status.is_working);
+        assert_eq!(status.lapse_sec, 1);
+        assert_close(status.fps, 320.0e6 / FRAME_BYTES);
+        assert_close(status.write_file_fps, 80.0e6 / FRAME_BYTES);
+        assert!(status.frame_count >= 24);
+
+        let path = asiair.record_stop().await.unwrap();
+        assert!(path.starts_with("Video/Untitled/Untitled_"));
+        assert!(path.ends_with(".ser"));
+        assert!(asiair.record_stop().await.is_err());
+        let status = asiair.record_get_state().await.unwrap();
+        assert!(!status.is_working);
+        let videos = asiair.storage_list_images(Some("Video")).await.unwrap();
+        assert_eq!(videos.len(), 1);
+        assert_eq!(videos[0].path, path);
+        assert_eq!(videos[0].frame_count, status.frame_count);
+        assert!(videos[0].size as f64 > FRAME_BYTES * status.frame_count as f64);
+
+        // USB2 limits the frame rate further, a recording of fixed duration stops by itself
+        asiair_sim.set_usb3_host(false);
+        assert!(!asiair.main_camera_get_info().await.unwrap().is_usb3_host);
+        let mut record_rx = asiair.subscribe_avi_record();
+        record_rx.mark_unchanged();
+        asiair.record_start(VideoFormat::Avi, Some(1.0)).await.unwrap();
+        let event = tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                record_rx.changed().await.unwrap();
+                let event = record_rx.borrow_and_update().clone();
+                if event.state == AviRecordState::Complete {
+                    return event;
+                }
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(event.lapse_sec, 1);
+        assert_close(event.fps, 35.0e6 / FRAME_BYTES);
+        assert_close(event.write_file_fps, 35.0e6 / FRAME_BYTES);
+        assert_eq!(event.frame_count, (35.0e6 / FRAME_BYTES) as u32);
+        assert!(event.path.unwrap().ends_with(".avi"));
+
+        // Long exposures limit the frame rate
+        asiair.main_camera_set_exposure(200000).await.unwrap();
+        asiair.record_start(VideoFormat::Ser, None).await.unwrap();
+        let status = asiair.record_get_state().await.unwrap();
+        assert_close(status.fps, 5.0);
+        assert_close(status.write_file_fps, 5.0);
+        asiair.record_stop().await.unwrap();
+        assert_eq!(asiair.storage_list_images(Some("Video")).await.unwrap().len(), 3);
+
+        // Final cleanup
+        asiair.disconnect().await;
+        asiair_sim.shutdown();
+    }
+}
@@ -0,0 +1,102 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::init_logger;
+
+    use asiair::ASIAir;
+    use asiair::rtmp::{RtmpConfig, RtmpEvent, RtmpState};
+    use asisim::{ASIAirSim, RtmpSink};
+    use std::net::Ipv4Addr;
+    use std::time::Duration;
+    use tokio::sync::watch;
+
+    async fn wait_rtmp(rtmp_rx: &mut watch::Receiver<RtmpEvent>, until: impl Fn(&RtmpEvent) -> bool) -> RtmpEvent {
+        tokio::time::timeout(Duration::from_secs(10), async {
+            loop {
+                rtmp_rx.changed().await.unwrap();
+                let event = rtmp_rx.borrow_and_update().clone();
+                if until(&event) {
+                    return event;
+                }
+            }
+        })
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_rtmp() {
+        init_logger();
+
+        // Create a new ASIAir instance
+        let addr: Ipv4Addr = Ipv4Addr::from([127, 0, 0, 1]);
+        let mut asiair = ASIAir::new(addr);
+
+        // Create a new ASIAir simulator instance
+        let mut asiair_sim = ASIAirSim::new();
+        asiair_sim.start().await.unwrap();
+        tokio::time::sleep(Duration::from_secs(1)).await; // Give the simulator some time to start
+
+        asiair.connect().await.unwrap();
+
+        // Streaming needs a server and the main camera
+        assert!(asiair.rtmp_start().await.is_err());
+        let sink = RtmpSink::bind(0).await.unwrap();
+        let config = RtmpConfig {
+            url: sink.url(),
+            key: "live-key".to_string(),
+            width: 640,
+            height: 360,
+        };
+        asiair.rtmp_set_config(&config).await.unwrap();
+        assert_eq!(asiair.rtmp_get_config().await.unwrap(), config);
+        assert!(asiair.rtmp_set_config(&RtmpConfig { url: "http://localhost/live".to_string(), ..config.clone() }).await.is_err());
+        assert!(asiair.rtmp_set_config(&RtmpConfig { width: 8000, ..config.clone() }).await.is_err());
+        assert!(asiair.rtmp_start().await.is_err());
+
+        asiair.main_camera_open(0).await.unwrap();
+        asiair.main_camera_set_bin(4).await.unwrap();
+        asiair.main_camera_set_exposure(100000).await.unwrap();
+
+        // Frames reach the server at the configured resolution
+        let mut rtmp_rx = asiair.subscribe_rtmp();
+        rtmp_rx.mark_unchanged();
+        asiair.rtmp_start().await.unwrap();
+        assert!(asiair.rtmp_start().await.is_err());
+        let event = wait_rtmp(&mut rtmp_rx, |event| event.frames >= 2).await;
+        assert_eq!(event.state, RtmpState::Working);
+        let status = asiair.rtmp_get_state().await.unwrap();
+        assert!(status.is_working);
+        assert!(status.frames >= 2);
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let stats = sink.stats();
+        assert_eq!(stats.app, "live");
+        assert_eq!(stats.stream_key, "live-key");
+        assert!(stats.publishing);
+        assert!(stats.frames >= 2);
+        assert_eq!((stats.width, stats.height), (640, 360));
+
+        asiair.rtmp_stop().await.unwrap();
+        assert!(asiair.rtmp_stop().await.is_err());
+        wait_rtmp(&mut rtmp_rx, |event| event.state == RtmpState::Stop).await;
+        assert!(!asiair.rtmp_get_state().await.unwrap().is_working);
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert!(!sink.stats().publishing);
+
+        // The stream fails when the server can't be reached
+        let port = sink.port();
+        drop(sink);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        asiair.rtmp_set_config(&RtmpConfig { url: format!("rtmp://127.0.0.1:{}/live", port), ..config }).await.unwrap();
+        asiair.rtmp_start().await.unwrap();
+        let event = wait_rtmp(&mut rtmp_rx, |event| event.state == RtmpState::Fail).await;
+        assert!(event.error.is_some());
+        assert!(!asiair.rtmp_get_state().await.unwrap().is_working);
+
+        // Final cleanup
+        asiair.disconnect().await;
+        asiair_sim.shutdown();
+    }
+}
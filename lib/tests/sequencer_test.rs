@@ -0,0 +1,116 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::init_logger;
+
+    use asiair::ASIAir;
+    use asiair::camera::FrameType;
+    use asiair::sequencer::{SequencePlan, SequenceState, SequenceStep, Sequencer};
+    use asisim::ASIAirSim;
+    use std::net::Ipv4Addr;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_sequencer() {
+        init_logger();
+
+        // Create a new ASIAir instance
+        let addr: Ipv4Addr = Ipv4Addr::from([127, 0, 0, 1]);
+        let mut asiair = ASIAir::new(addr);
+
+        // Create a new ASIAir simulator instance
+        let mut asiair_sim = ASIAirSim::new();
+        asiair_sim.start().await.unwrap();
+        tokio::time::sleep(Duration::from_secs(1)).await; // Give the simulator some time to start
+
+        asiair.connect().await.unwrap();
+        asiair.main_camera_open(0).await.unwrap();
+
+        let output_dir = std::env::temp_dir().join("asiair_sequencer_test");
+        let _ = std::fs::remove_dir_all(&output_dir);
+
+        // Run a short plan to completion
+        let plan = SequencePlan {
+            steps: vec![
+                SequenceStep {
+                    frame_type: FrameType::Light,
+                    count: 2,
+                    exposure_us: 1000,
+                    gain: 100,
+                    bin: 2,
+                    target_temperature: Some(-10.0),
+                    dither_every: Some(1),
+                },
+                SequenceStep {
+                    frame_type: FrameType::Dark,
+                    count: 1,
+                    exposure_us: 1000,
+                    ..Default::default()
+                },
+            ],
+            output_dir: output_dir.clone(),
+            ..Default::default()
+        };
+        let sequencer = Sequencer::new(asiair.clone(), plan);
+        let progress = sequencer.run().await.unwrap();
+        assert_eq!(progress.state, SequenceState::Completed);
+        assert_eq!(progress.completed_frames, 3);
+        assert_eq!(progress.total_frames, 3);
+
+        for name in [
+            "Light_0.001s_Bin2_gain100_0001.fit",
+            "Light_0.001s_Bin2_gain100_0002.fit",
+            "Dark_0.001s_Bin1_gain0_0003.fit",
+        ] {
+            let data = std::fs::read(output_dir.join(name)).unwrap();
+            assert!(data.starts_with(b"SIMPLE  ="));
+            assert_eq!(data.len() % 2880, 0);
+        }
+        assert_eq!(asiair.main_camera_get_gain().await.unwrap(), 0);
+        assert_eq!(asiair.main_camera_get_target_temperature().await.unwrap(), -10.0);
+
+        // Pause, resume and abort a longer plan
+        let plan = SequencePlan {
+            steps: vec![SequenceStep {
+                frame_type: FrameType::Light,
+                count: 10,
+                exposure_us: 500000,
+                ..Default::default()
+            }],
+            output_dir: output_dir.clone(),
+            ..Default::default()
+        };
+        let sequencer = Sequencer::new(asiair.clone(), plan);
+        let mut progress_rx = sequencer.subscribe_progress();
+        let runner = sequencer.clone();
+        let handle = tokio::spawn(async move { runner.run().await });
+
+        sequencer.pause();
+        progress_rx
+            .wait_for(|progress| progress.state == SequenceState::Paused)
+            .await
+            .unwrap();
+        let paused_frames = sequencer.progress().completed_frames;
+        assert!(paused_frames <= 1);
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        assert_eq!(sequencer.progress().completed_frames, paused_frames);
+
+        sequencer.resume();
+        progress_rx
+            .wait_for(|progress| progress.completed_frames > paused_frames)
+            .await
+            .unwrap();
+
+        sequencer.abort();
+        let progress = handle.await.unwrap().unwrap();
+        assert_eq!(progress.state, SequenceState::Aborted);
+        assert!(progress.completed_frames < 10);
+
+        let _ = std::fs::remove_dir_all(&output_dir);
+
+        // Final cleanup
+        asiair.disconnect().await;
+        asiair_sim.shutdown();
+    }
+}
@@ -4,9 +4,10 @@ mod common;
 mod tests {
     use super::common::init_logger;
 
-    use asiair::ASIAir;
+    use asiair::{ASIAir, ASIAirLanguage, Settings, TemperatureUnit, WifiBand};
     use asisim::ASIAirSim;
     use chrono::DateTime;
+    use serde_json::json;
     use std::net::Ipv4Addr;
     use std::time::Duration;
 
@@ -33,6 +34,30 @@ mod tests {
             .set_language(asiair::ASIAirLanguage::English)
             .await
             .unwrap();
+        assert_eq!(asiair.get_settings().await.unwrap(), Settings { beep: true, ..Default::default() });
+
+        // Setting the language leaves the other settings alone
+        asiair.set_language(ASIAirLanguage::Japanese).await.unwrap();
+        let settings = asiair.get_settings().await.unwrap();
+        assert_eq!(settings.lang, ASIAirLanguage::Japanese);
+        assert!(settings.beep);
+
+        // Every setting round-trips
+        let settings = Settings {
+            lang: ASIAirLanguage::German,
+            temp_unit: TemperatureUnit::Fahrenheit,
+            beep: false,
+            auto_shutdown_min: 30,
+            wifi_band: WifiBand::Band2G4,
+        };
+        asiair.set_settings(&settings).await.unwrap();
+        assert_eq!(asiair.get_settings().await.unwrap(), settings);
+
+        // Invalid values and unknown settings are refused without changing anything
+        assert!(asiair.rpc_request_4700("set_setting", Some(json!({ "temp_unit": "K" }))).await.is_err());
+        assert!(asiair.rpc_request_4700("set_setting", Some(json!({ "beep": true, "volume": 3 }))).await.is_err());
+        assert!(asiair.rpc_request_4700("set_setting", Some(json!({ "lang": "xx" }))).await.is_err());
+        assert_eq!(asiair.get_settings().await.unwrap(), settings);
 
         // Final cleanup
         asiair.disconnect().await;
@@ -0,0 +1,94 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::init_logger;
+
+    use asiair::ASIAir;
+    use asiair::mount::{GotoState, MountEvent};
+    use asiair::solve::SolveError;
+    use asisim::{ASIAirSim, SolveFailure, SolverConfig};
+    use chrono::TimeZone;
+    use std::net::Ipv4Addr;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_solve() {
+        init_logger();
+
+        // Create a new ASIAir instance
+        let addr: Ipv4Addr = Ipv4Addr::from([127, 0, 0, 1]);
+        let mut asiair = ASIAir::new(addr);
+
+        // Create a new ASIAir simulator instance
+        let mut asiair_sim = ASIAirSim::new();
+        asiair_sim.start().await.unwrap();
+        tokio::time::sleep(Duration::from_secs(1)).await; // Give the simulator some time to start
+
+        asiair_sim.set_solver_config(SolverConfig {
+            latency: Duration::from_millis(300),
+            timeout: Duration::from_millis(600),
+            ra_offset_arcsec: 0.0,
+            dec_offset_arcsec: 36.0,
+            noise_arcsec: 0.0,
+            camera_angle: 30.0,
+            ..Default::default()
+        });
+
+        asiair.connect().await.unwrap();
+
+        // Solving needs an image from the main camera
+        assert!(asiair.solve_current_image().await.is_err());
+        asiair.main_camera_open(0).await.unwrap();
+
+        let now = chrono_tz::UTC.with_ymd_and_hms(2025, 5, 6, 20, 0, 0).unwrap();
+        asiair.set_time(now).await.unwrap();
+        asiair.mount_set_slew_rate(180.0).await.unwrap();
+        let mut mount_rx = asiair.subscribe_mount();
+        asiair.mount_goto(5.58, -5.39).await.unwrap();
+        mount_rx
+            .wait_for(|event| matches!(event, MountEvent::Goto { state: GotoState::Complete, .. }))
+            .await
+            .unwrap();
+
+        let result = asiair.solve_current_image().await.unwrap();
+        assert!((result.ra - 5.58).abs() < 0.001);
+        assert!((result.dec - (-5.39 + 0.01)).abs() < 0.001);
+        assert_eq!(result.angle, 30.0);
+        // 3.76um pixels at 400mm of focal length
+        assert!((result.scale - 1.939).abs() < 0.001);
+        assert!((result.fov[0] - 6248.0 * result.scale / 3600.0).abs() < 1e-6);
+        assert_eq!(result.lapse_ms, 300);
+        assert_eq!(asiair.get_solve_result().await.unwrap(), result);
+
+        // Failures are reported with their reason
+        asiair_sim.set_solver_config(SolverConfig {
+            failure: Some(SolveFailure::NoStars),
+            ..asiair_sim.solver_config()
+        });
+        let error = asiair.solve_current_image().await.unwrap_err();
+        assert_eq!(error.downcast_ref::<SolveError>(), Some(&SolveError::NoStars));
+
+        asiair_sim.set_solver_config(SolverConfig {
+            failure: Some(SolveFailure::Timeout),
+            ..asiair_sim.solver_config()
+        });
+        let error = asiair.solve_current_image().await.unwrap_err();
+        assert_eq!(error.downcast_ref::<SolveError>(), Some(&SolveError::Timeout));
+
+        // A solve can be stopped while running
+        asiair.start_solve().await.unwrap();
+        assert!(asiair.start_solve().await.is_err());
+        asiair.stop_solve().await.unwrap();
+        let error = asiair.get_solve_result().await.unwrap_err();
+        assert_eq!(error.downcast_ref::<SolveError>(), Some(&SolveError::Cancelled));
+
+        // Failures to reach the device are not solve failures
+        asiair.disconnect().await;
+        let error = asiair.get_solve_result().await.unwrap_err();
+        assert_eq!(error.downcast_ref::<SolveError>(), None);
+
+        // Final cleanup
+        asiair_sim.shutdown();
+    }
+}
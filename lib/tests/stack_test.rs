@@ -0,0 +1,164 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::init_logger;
+
+    use asiair::mount::{GotoState, MountEvent};
+    use asiair::stack::{StackEventState, StackProgress};
+    use asiair::{ASIAir, ExposureEvent};
+    use asisim::ASIAirSim;
+    use chrono::TimeZone;
+    use std::net::Ipv4Addr;
+    use std::time::Duration;
+
+    async fn expose(asiair: &mut ASIAir) {
+        let mut exposure_rx = asiair.subscribe_exposure();
+        exposure_rx.mark_unchanged();
+        asiair.main_camera_start_exposure().await.unwrap();
+        tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                exposure_rx.changed().await.unwrap();
+                if let ExposureEvent::Complete = *exposure_rx.borrow_and_update() {
+                    break;
+                }
+            }
+        })
+        .await
+        .unwrap();
+    }
+
+    async fn goto(asiair: &mut ASIAir, ra: f64, dec: f64) {
+        let mut mount_rx = asiair.subscribe_mount();
+        mount_rx.mark_unchanged();
+        asiair.mount_goto(ra, dec).await.unwrap();
+        tokio::time::timeout(Duration::from_secs(30), async {
+            loop {
+                mount_rx.changed().await.unwrap();
+                if matches!(*mount_rx.borrow_and_update(), MountEvent::Goto { state: GotoState::Complete, .. }) {
+                    break;
+                }
+            }
+        })
+        .await
+        .unwrap();
+    }
+
+    // Robust estimate of the background noise of a raw image
+    fn noise(data: &[u8]) -> f64 {
+        let mut values: Vec<f64> = data
+            .chunks_exact(2)
+            .map(|pixel| (i16::from_be_bytes([pixel[0], pixel[1]]) as i32 + 32768) as f64)
+            .collect();
+        values.sort_by(f64::total_cmp);
+        let median = values[values.len() / 2];
+        let mut deviations: Vec<f64> = values.iter().map(|v| (v - median).abs()).collect();
+        deviations.sort_by(f64::total_cmp);
+        deviations[deviations.len() / 2]
+    }
+
+    #[tokio::test]
+    async fn test_stack() {
+        init_logger();
+
+        // Create a new ASIAir instance
+        let addr: Ipv4Addr = Ipv4Addr::from([127, 0, 0, 1]);
+        let mut asiair = ASIAir::new(addr);
+
+        // Create a new ASIAir simulator instance
+        let mut asiair_sim = ASIAirSim::new();
+        asiair_sim.start().await.unwrap();
+        tokio::time::sleep(Duration::from_secs(1)).await; // Give the simulator some time to start
+
+        asiair.connect().await.unwrap();
+        let now = chrono_tz::UTC.with_ymd_and_hms(2025, 5, 6, 20, 0, 0).unwrap();
+        asiair.set_time(now).await.unwrap();
+        asiair.mount_set_slew_rate(360.0).await.unwrap();
+        goto(&mut asiair, 5.58, -5.39).await;
+        asiair.main_camera_open(0).await.unwrap();
+        asiair.main_camera_set_bin(4).await.unwrap();
+        asiair.main_camera_set_exposure(200000).await.unwrap();
+
+        // Nothing to download before a frame is stacked
+        assert!(asiair.stack_get_image().await.is_err());
+
+        let mut events = asiair.stack_events();
+        asiair.stack_start().await.unwrap();
+        assert!(asiair.stack_start().await.is_err());
+
+        for stacked_frame in 1..=3 {
+            expose(&mut asiair).await;
+            let event = tokio::time::timeout(Duration::from_secs(10), events.recv()).await.unwrap().unwrap();
+            assert_eq!(event.state, StackEventState::Stacked, "{:?}", event.error);
+            assert_eq!(event.stacked_frame, stacked_frame);
+            assert!(event.star_number >= 8);
+        }
+        let (single, _, _) = asiair.main_camera_get_current_img().await.unwrap();
+
+        // A small pointing offset is registered
+        goto(&mut asiair, 5.58, -5.39 + 30.0 / 3600.0).await;
+        expose(&mut asiair).await;
+        let event = tokio::time::timeout(Duration::from_secs(10), events.recv()).await.unwrap().unwrap();
+        assert_eq!(event.state, StackEventState::Stacked, "{:?}", event.error);
+
+        // A frame without stars is dropped
+        asiair.main_camera_set_exposure(1000).await.unwrap();
+        expose(&mut asiair).await;
+        let event = tokio::time::timeout(Duration::from_secs(10), events.recv()).await.unwrap().unwrap();
+        assert_eq!(event.state, StackEventState::Dropped);
+        assert_eq!(event.error.as_deref(), Some("not enough stars"));
+
+        let progress = asiair.stack_get_state().await.unwrap();
+        assert_eq!(progress, StackProgress { is_working: true, stacked_frame: 4, dropped_frame: 1, total_frame: 5 });
+
+        // The stack is less noisy than a single frame
+        let (stacked, width, height) = asiair.stack_get_image().await.unwrap();
+        assert_eq!(stacked.len(), width as usize * height as usize * 2);
+        assert_eq!(stacked.len(), single.len());
+        assert!(noise(&stacked) < noise(&single));
+
+        // Frames taken after a stop are not stacked
+        asiair.stack_stop().await.unwrap();
+        assert!(tokio::time::timeout(Duration::from_secs(5), events.recv()).await.unwrap().is_none());
+        expose(&mut asiair).await;
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        let progress = asiair.stack_get_state().await.unwrap();
+        assert!(!progress.is_working);
+        assert_eq!(progress.total_frame, 5);
+
+        asiair.stack_reset().await.unwrap();
+        let progress = asiair.stack_get_state().await.unwrap();
+        assert_eq!(progress, StackProgress::default());
+        assert!(asiair.stack_get_image().await.is_err());
+
+        // A session stopped with a frame queued does not end the next one
+        asiair.main_camera_set_exposure(200000).await.unwrap();
+        let mut events = asiair.stack_events();
+        asiair.stack_start().await.unwrap();
+        expose(&mut asiair).await;
+        asiair.stack_stop().await.unwrap();
+        tokio::time::timeout(Duration::from_secs(5), async { while events.recv().await.is_some() {} })
+            .await
+            .unwrap();
+        let mut events = asiair.stack_events();
+        asiair.stack_start().await.unwrap();
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        let progress = asiair.stack_get_state().await.unwrap();
+        assert!(progress.is_working);
+        expose(&mut asiair).await;
+        let event = tokio::time::timeout(Duration::from_secs(10), events.recv()).await.unwrap().unwrap();
+        assert_eq!(event.state, StackEventState::Stacked, "{:?}", event.error);
+
+        // Frames queued before a reset are not counted after it
+        expose(&mut asiair).await;
+        asiair.stack_reset().await.unwrap();
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        let progress = asiair.stack_get_state().await.unwrap();
+        assert_eq!(progress, StackProgress { is_working: true, ..Default::default() });
+        asiair.stack_stop().await.unwrap();
+
+        // Final cleanup
+        asiair.disconnect().await;
+        asiair_sim.shutdown();
+    }
+}
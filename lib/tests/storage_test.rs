@@ -0,0 +1,100 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::init_logger;
+
+    use asiair::camera::FrameType;
+    use asiair::{ASIAir, ExposureEvent};
+    use asisim::ASIAirSim;
+    use std::net::Ipv4Addr;
+    use std::time::Duration;
+
+    async fn expose(asiair: &mut ASIAir, frame_type: FrameType) {
+        let mut exposure_rx = asiair.subscribe_exposure();
+        exposure_rx.mark_unchanged();
+        asiair.main_camera_start_frame_exposure(frame_type).await.unwrap();
+        tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                exposure_rx.changed().await.unwrap();
+                if let ExposureEvent::Complete = *exposure_rx.borrow_and_update() {
+                    break;
+                }
+            }
+        })
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_storage() {
+        init_logger();
+
+        // Create a new ASIAir instance
+        let addr: Ipv4Addr = Ipv4Addr::from([127, 0, 0, 1]);
+        let mut asiair = ASIAir::new(addr);
+
+        // Create a new ASIAir simulator instance
+        let mut asiair_sim = ASIAirSim::new();
+        asiair_sim.start().await.unwrap();
+        tokio::time::sleep(Duration::from_secs(1)).await; // Give the simulator some time to start
+
+        asiair.connect().await.unwrap();
+        asiair.main_camera_open(0).await.unwrap();
+        asiair.main_camera_set_bin(4).await.unwrap();
+        asiair.main_camera_set_exposure(100000).await.unwrap();
+        let params = Some(serde_json::json!([ { "goto_target_name": "M 42" } ]));
+        asiair.rpc_request_4700("set_app_setting", params).await.unwrap();
+
+        let info = asiair.storage_get_info().await.unwrap();
+        assert_eq!(info[0].name, "emmc");
+        assert_eq!(info[0].image_count, 0);
+        assert_eq!(info[0].used + info[0].free, info[0].total);
+        let used = info[0].used;
+
+        // Every frame is saved in the folder of its type, lights in a folder per target
+        expose(&mut asiair, FrameType::Light).await;
+        expose(&mut asiair, FrameType::Light).await;
+        expose(&mut asiair, FrameType::Dark).await;
+        let images = asiair.storage_list_images(None).await.unwrap();
+        assert_eq!(images.len(), 3);
+        assert!(images[0].path.starts_with("Autosave/Light/M_42/Light_M_42_0.1s_Bin4_gain"));
+        assert!(images[0].path.ends_with("_0001.fit"));
+        assert!(images[2].path.starts_with("Autosave/Dark/Dark_"));
+        assert_eq!(images[1].frame_type, FrameType::Light);
+        assert_eq!(images[1].target, "M_42");
+        assert_eq!(images[1].exposure_us, 100000);
+        assert_eq!(images[1].bin, 4);
+        assert_eq!(images[2].frame_type, FrameType::Dark);
+        assert!(images[0].date_time <= images[1].date_time);
+        assert_eq!(asiair.storage_list_images(Some("Autosave/Light")).await.unwrap().len(), 2);
+        assert_eq!(asiair.storage_list_images(Some("Autosave/Lig")).await.unwrap().len(), 0);
+
+        let info = asiair.storage_get_info().await.unwrap();
+        assert_eq!(info[0].image_count, 3);
+        assert_eq!(info[0].used, used + images.iter().map(|image| image.size).sum::<u64>());
+
+        // Saved images can be downloaded
+        let (data, width, height) = asiair.storage_download_image(&images[0].path).await.unwrap();
+        assert_eq!((width as u32, height as u32), (images[0].width, images[0].height));
+        assert_eq!(data.len(), width as usize * height as usize * 2);
+        assert!(asiair.storage_download_image("Autosave/Light/M_42/missing.fit").await.is_err());
+
+        // Images are deleted one by one or by folder
+        assert_eq!(asiair.storage_delete(&images[0].path).await.unwrap(), 1);
+        assert!(asiair.storage_delete(&images[0].path).await.is_err());
+        expose(&mut asiair, FrameType::Light).await;
+        assert_eq!(asiair.storage_delete("Autosave/Light/M_42").await.unwrap(), 2);
+        assert_eq!(asiair.storage_list_images(None).await.unwrap().len(), 1);
+
+        // Formatting erases everything
+        asiair.format_drive().await.unwrap();
+        assert!(asiair.storage_list_images(None).await.unwrap().is_empty());
+        let info = asiair.storage_get_info().await.unwrap();
+        assert_eq!((info[0].image_count, info[0].used), (0, used));
+
+        // Final cleanup
+        asiair.disconnect().await;
+        asiair_sim.shutdown();
+    }
+}
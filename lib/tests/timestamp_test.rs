@@ -0,0 +1,82 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::init_logger;
+
+    use asiair::ASIAir;
+    use asisim::ASIAirSim;
+    use chrono::{FixedOffset, TimeZone};
+    use chrono_tz::Europe::Paris;
+    use std::net::Ipv4Addr;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_timestamps() {
+        init_logger();
+
+        // Create a new ASIAir instance
+        let addr: Ipv4Addr = Ipv4Addr::from([127, 0, 0, 1]);
+        let mut asiair = ASIAir::new(addr);
+
+        // Create a new ASIAir simulator instance
+        let mut asiair_sim = ASIAirSim::new();
+        asiair_sim.start().await.unwrap();
+        tokio::time::sleep(Duration::from_secs(1)).await; // Give the simulator some time to start
+
+        asiair.connect().await.unwrap();
+
+        // Responses are stamped with the time set on the device, in its time zone
+        let set_time = Paris.with_ymd_and_hms(2025, 6, 1, 22, 0, 0).unwrap();
+        asiair.set_time(set_time).await.unwrap();
+        let response = asiair.rpc_request_4700_timestamped("test_connection", None).await.unwrap();
+        assert_eq!(response.value, "server connected!");
+        let timestamp = response.timestamp.unwrap();
+        assert_eq!(timestamp.offset(), &FixedOffset::east_opt(2 * 3600).unwrap());
+        let elapsed = timestamp - set_time.fixed_offset();
+        assert!(elapsed >= chrono::Duration::zero() && elapsed < chrono::Duration::seconds(5));
+
+        // Events are stamped too, in the order they happened
+        let mut events_rx = asiair.subscribe_events();
+        let exposure_rx = asiair.subscribe_exposure_timestamped();
+        asiair.main_camera_open(0).await.unwrap();
+        asiair.main_camera_set_bin(4).await.unwrap();
+        asiair.main_camera_set_exposure(200_000).await.unwrap();
+        asiair.main_camera_start_exposure().await.unwrap();
+        let mut exposure_timestamps = Vec::new();
+        tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                let event = events_rx.recv().await.unwrap();
+                if event.value["Event"] == "Exposure" {
+                    exposure_timestamps.push(event.timestamp.unwrap());
+                    if event.value["state"] == "complete" {
+                        break;
+                    }
+                }
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(exposure_timestamps.len(), 3);
+        assert!(exposure_timestamps.is_sorted());
+        let exposure = exposure_timestamps[1] - exposure_timestamps[0];
+        assert!(exposure >= chrono::Duration::milliseconds(200));
+
+        // The timestamped channels and getters carry the same timestamps
+        assert_eq!(exposure_rx.borrow().timestamp, Some(exposure_timestamps[2]));
+        let state = asiair.main_camera_get_state_timestamped().await.unwrap();
+        assert!(state.timestamp.unwrap() >= exposure_timestamps[2]);
+        assert_eq!(state.value, asiair.main_camera_get_state().await.unwrap());
+
+        // Moving the clock of the device moves the timestamps
+        asiair_sim.advance_time(chrono::Duration::days(1));
+        let response = asiair.rpc_request_4700_timestamped("test_connection", None).await.unwrap();
+        let elapsed = response.timestamp.unwrap() - set_time.fixed_offset();
+        assert!(elapsed >= chrono::Duration::days(1) && elapsed < chrono::Duration::days(1) + chrono::Duration::seconds(10));
+        let state = asiair.get_app_state_timestamped().await.unwrap();
+        assert!(state.timestamp.unwrap() >= response.timestamp.unwrap());
+
+        asiair.disconnect().await;
+        asiair_sim.shutdown();
+    }
+}
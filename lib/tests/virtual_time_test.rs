@@ -0,0 +1,77 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::init_logger;
+
+    use asiair::{ASIAir, ExposureEvent};
+    use asisim::{ASIAirSim, Clock};
+    use std::net::Ipv4Addr;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_virtual_time() {
+        init_logger();
+
+        // Create a new ASIAir instance
+        let addr: Ipv4Addr = Ipv4Addr::from([127, 0, 0, 1]);
+        let mut asiair = ASIAir::new(addr);
+
+        // Create a new ASIAir simulator instance
+        let mut asiair_sim = ASIAirSim::with_clock(Clock::manual());
+        asiair_sim.start().await.unwrap();
+        tokio::time::sleep(Duration::from_secs(1)).await; // Give the simulator some time to start
+
+        asiair.connect().await.unwrap();
+        asiair.main_camera_open(0).await.unwrap();
+        asiair.main_camera_set_bin(4).await.unwrap();
+
+        // On the manual clock, a 5 minutes exposure is only over once time is moved forward
+        let wall_start = std::time::Instant::now();
+        let device_start = asiair_sim.now();
+        asiair.main_camera_set_exposure(300_000_000).await.unwrap();
+        let mut exposure_rx = asiair.subscribe_exposure();
+        exposure_rx.mark_unchanged();
+        asiair.main_camera_start_exposure().await.unwrap();
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        assert!(asiair.main_camera_get_state().await.is_ok());
+        assert_eq!(asiair_sim.now(), device_start);
+        asiair_sim.advance(Duration::from_secs(300)).await;
+        tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                exposure_rx.changed().await.unwrap();
+                if let ExposureEvent::Complete = *exposure_rx.borrow_and_update() {
+                    break;
+                }
+            }
+        })
+        .await
+        .unwrap();
+        assert!(wall_start.elapsed() < Duration::from_secs(30));
+        assert_eq!(asiair_sim.now() - device_start, chrono::Duration::seconds(300));
+
+        // The image of the exposure downloads as usual
+        let (data, width, height) = asiair.main_camera_get_current_img().await.unwrap();
+        assert_eq!((width, height), (1562, 1044));
+        assert!(!data.is_empty());
+
+        asiair.disconnect().await;
+
+        // Moving the clock forward moves the RTC and lets the board cool down to its new equilibrium
+        asiair_sim.set_ambient_temperature(-10.0);
+        let device_start = asiair_sim.now();
+        asiair_sim.advance(Duration::from_secs(3600)).await;
+        assert_eq!(asiair_sim.now() - device_start, chrono::Duration::seconds(3600));
+        let idle_rise = asiair_sim.pi_config().idle_rise;
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while (asiair_sim.pi_temperature() - (-10.0 + idle_rise)).abs() >= 0.1 {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+        })
+        .await
+        .unwrap();
+        assert!(wall_start.elapsed() < Duration::from_secs(30));
+
+        asiair_sim.shutdown();
+    }
+}
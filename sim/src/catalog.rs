@@ -0,0 +1,121 @@
+use serde_json::{json, Value};
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ObjectType {
+    Galaxy,
+    Nebula,
+    OpenCluster,
+    GlobularCluster,
+    PlanetaryNebula,
+    SupernovaRemnant,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct CatalogObject {
+    pub name: &'static str,
+    pub r#type: ObjectType,
+    // Right ascension in hours
+    pub ra: f64,
+    // Declination in degrees
+    pub dec: f64,
+    // Apparent diameter in arcminutes
+    pub size: f64,
+}
+
+const fn object(name: &'static str, r#type: ObjectType, ra: f64, dec: f64, size: f64) -> CatalogObject {
+    CatalogObject { name, r#type, ra, dec, size }
+}
+
+/// Small catalog of bright deep-sky objects, J2000 coordinates
+pub const CATALOG: &[CatalogObject] = &[
+    object("M1", ObjectType::SupernovaRemnant, 5.5756, 22.0145, 6.0),
+    object("M8", ObjectType::Nebula, 18.0606, -24.3867, 90.0),
+    object("M13", ObjectType::GlobularCluster, 16.6949, 36.4613, 20.0),
+    object("M16", ObjectType::Nebula, 18.3128, -13.7833, 35.0),
+    object("M17", ObjectType::Nebula, 18.3469, -16.1717, 11.0),
+    object("M20", ObjectType::Nebula, 18.0433, -23.0300, 28.0),
+    object("M27", ObjectType::PlanetaryNebula, 19.9934, 22.7212, 8.0),
+    object("M31", ObjectType::Galaxy, 0.7123, 41.2689, 178.0),
+    object("M32", ObjectType::Galaxy, 0.7115, 40.8652, 8.0),
+    object("M33", ObjectType::Galaxy, 1.5641, 30.6602, 70.0),
+    object("M42", ObjectType::Nebula, 5.5881, -5.3911, 85.0),
+    object("M43", ObjectType::Nebula, 5.5925, -5.2700, 20.0),
+    object("M45", ObjectType::OpenCluster, 3.7833, 24.1167, 110.0),
+    object("M51", ObjectType::Galaxy, 13.4979, 47.1953, 11.0),
+    object("M57", ObjectType::PlanetaryNebula, 18.8931, 33.0292, 1.4),
+    object("M81", ObjectType::Galaxy, 9.9259, 69.0653, 27.0),
+    object("M82", ObjectType::Galaxy, 9.9319, 69.6797, 11.0),
+    object("M101", ObjectType::Galaxy, 14.0535, 54.3488, 29.0),
+    object("M104", ObjectType::Galaxy, 12.6664, -11.6231, 9.0),
+    object("NGC 1977", ObjectType::Nebula, 5.5903, -4.8333, 20.0),
+    object("NGC 1981", ObjectType::OpenCluster, 5.5833, -4.4333, 25.0),
+    object("NGC 2024", ObjectType::Nebula, 5.6975, -1.8500, 30.0),
+    object("NGC 7000", ObjectType::Nebula, 20.9800, 44.3300, 120.0),
+];
+
+/// Position in pixels of a sky position in an image centered on `ra`/`dec` (hours and degrees),
+/// rotated by `angle` degrees east of north, with `scale` arcseconds per pixel.
+/// Positions are from the top left corner, with north up and east left at angle 0,
+/// and may be outside of the image. Returns `None` for positions behind the image plane
+pub fn project(ra: f64, dec: f64, angle: f64, scale: f64, image_size: [u32; 2], position: (f64, f64)) -> Option<(f64, f64)> {
+    let (ra0, dec0) = ((ra * 15.0).to_radians(), dec.to_radians());
+    let (ra1, dec1) = ((position.0 * 15.0).to_radians(), position.1.to_radians());
+    let d_ra = ra1 - ra0;
+
+    // Gnomonic projection, xi toward east and eta toward north
+    let cos_c = dec0.sin() * dec1.sin() + dec0.cos() * dec1.cos() * d_ra.cos();
+    if cos_c <= 0.0 {
+        return None;
+    }
+    let xi = dec1.cos() * d_ra.sin() / cos_c;
+    let eta = (dec0.cos() * dec1.sin() - dec0.sin() * dec1.cos() * d_ra.cos()) / cos_c;
+
+    // Image up is toward `angle`, image right is 90 degrees further, toward west at angle 0
+    let angle = angle.to_radians();
+    let scale = (scale / 3600.0).to_radians();
+    let up = xi * angle.sin() + eta * angle.cos();
+    let right = -xi * angle.cos() + eta * angle.sin();
+    Some((image_size[0] as f64 / 2.0 + right / scale, image_size[1] as f64 / 2.0 - up / scale))
+}
+
+/// Sky position (hours and degrees) of a position in pixels, the inverse of `project`
+pub fn unproject(ra: f64, dec: f64, angle: f64, scale: f64, image_size: [u32; 2], position: (f64, f64)) -> (f64, f64) {
+    let (ra0, dec0) = ((ra * 15.0).to_radians(), dec.to_radians());
+    let angle = angle.to_radians();
+    let scale = (scale / 3600.0).to_radians();
+    let right = (position.0 - image_size[0] as f64 / 2.0) * scale;
+    let up = (image_size[1] as f64 / 2.0 - position.1) * scale;
+    let xi = up * angle.sin() - right * angle.cos();
+    let eta = up * angle.cos() + right * angle.sin();
+
+    let denominator = dec0.cos() - eta * dec0.sin();
+    let ra1 = ra0 + xi.atan2(denominator);
+    let dec1 = (dec0.sin() + eta * dec0.cos()).atan2(xi.hypot(denominator));
+    ((ra1.to_degrees() / 15.0).rem_euclid(24.0), dec1.to_degrees())
+}
+
+/// Catalog objects overlapping an image, see `project` for the image geometry
+pub fn annotate(ra: f64, dec: f64, angle: f64, scale: f64, image_size: [u32; 2]) -> Vec<Value> {
+    CATALOG
+        .iter()
+        .filter_map(|object| {
+            let (x, y) = project(ra, dec, angle, scale, image_size, (object.ra, object.dec))?;
+            let radius = object.size * 60.0 / 2.0 / scale;
+
+            let visible = x + radius >= 0.0
+                && x - radius <= image_size[0] as f64
+                && y + radius >= 0.0
+                && y - radius <= image_size[1] as f64;
+            visible.then(|| {
+                json!({
+                    "name": object.name,
+                    "type": object.r#type,
+                    "x": x,
+                    "y": y,
+                    "radius": radius,
+                })
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,143 @@
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::time::Instant;
+
+/// Time source of the simulator. The RTC, exposures, the thermal model and the periodic events
+/// all wait on it, so a session can be simulated as fast as the tasks run
+#[derive(Debug, Clone, Default)]
+pub struct Clock {
+    // Virtual time moved only by `advance`, tokio's clock when None
+    manual: Option<Arc<ManualClock>>,
+}
+
+#[derive(Debug)]
+struct ManualClock {
+    origin: Instant,
+    elapsed: watch::Sender<Duration>,
+}
+
+impl Clock {
+    /// Tokio's clock, the wall clock unless the runtime is paused. On paused time the clock jumps to
+    /// the next timer whenever every task waits, which suits tests without sockets to the simulator
+    pub fn tokio() -> Self {
+        Clock { manual: None }
+    }
+
+    /// Virtual time standing still until `advance` moves it forward, whatever the runtime does
+    pub fn manual() -> Self {
+        let (elapsed, _) = watch::channel(Duration::ZERO);
+        Clock {
+            manual: Some(Arc::new(ManualClock {
+                origin: Instant::now(),
+                elapsed,
+            })),
+        }
+    }
+
+    pub fn is_manual(&self) -> bool {
+        self.manual.is_some()
+    }
+
+    pub fn now(&self) -> Instant {
+        match &self.manual {
+            Some(manual) => manual.origin + *manual.elapsed.borrow(),
+            None => Instant::now(),
+        }
+    }
+
+    pub async fn sleep(&self, duration: Duration) {
+        self.sleep_until(self.now() + duration).await;
+    }
+
+    pub async fn sleep_until(&self, deadline: Instant) {
+        match &self.manual {
+            Some(manual) => {
+                let mut elapsed_rx = manual.elapsed.subscribe();
+                let _ = elapsed_rx.wait_for(|elapsed| manual.origin + *elapsed >= deadline).await;
+            }
+            None => tokio::time::sleep_until(deadline).await,
+        }
+    }
+
+    /// Ticks every `period`, the first tick completes immediately. Missed ticks are skipped, the
+    /// loops measure the time elapsed between their ticks
+    pub fn interval(&self, period: Duration) -> Interval {
+        Interval {
+            clock: self.clone(),
+            next: self.now(),
+            period,
+        }
+    }
+
+    /// Move time forward and wake the tasks waiting for it. Tokio's clock is moved when paused,
+    /// which takes the `test-util` feature, without it the time is waited for
+    pub async fn advance(&self, duration: Duration) {
+        match &self.manual {
+            Some(manual) => {
+                manual.elapsed.send_modify(|elapsed| *elapsed += duration);
+                tokio::task::yield_now().await;
+            }
+            #[cfg(feature = "test-util")]
+            None => tokio::time::advance(duration).await,
+            #[cfg(not(feature = "test-util"))]
+            None => tokio::time::sleep(duration).await,
+        }
+    }
+}
+
+/// Periodic ticks of a clock
+#[derive(Debug)]
+pub struct Interval {
+    clock: Clock,
+    next: Instant,
+    period: Duration,
+}
+
+impl Interval {
+    /// Wait for the next tick, cancel safe
+    pub async fn tick(&mut self) -> Instant {
+        self.clock.sleep_until(self.next).await;
+        let tick = self.next;
+        self.next += self.period;
+        let now = self.clock.now();
+        if self.next <= now {
+            self.next = now + self.period;
+        }
+        tick
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_manual_clock() {
+        let clock = Clock::manual();
+        let start = clock.now();
+
+        let sleeper = clock.clone();
+        let task = tokio::spawn(async move { sleeper.sleep(Duration::from_secs(3600)).await });
+        tokio::task::yield_now().await;
+        clock.advance(Duration::from_secs(1800)).await;
+        assert!(!task.is_finished());
+        clock.advance(Duration::from_secs(1800)).await;
+        tokio::time::timeout(Duration::from_secs(1), task).await.unwrap().unwrap();
+        assert_eq!(clock.now() - start, Duration::from_secs(3600));
+    }
+
+    #[tokio::test]
+    async fn test_manual_interval_skips_missed_ticks() {
+        let clock = Clock::manual();
+        let mut interval = clock.interval(Duration::from_secs(1));
+        let first = interval.tick().await;
+        clock.advance(Duration::from_millis(3500)).await;
+        let second = interval.tick().await;
+        assert_eq!(second - first, Duration::from_secs(1));
+        let third = tokio::time::timeout(Duration::from_millis(100), interval.tick()).await;
+        assert!(third.is_err());
+        clock.advance(Duration::from_secs(1)).await;
+        assert_eq!(interval.tick().await - first, Duration::from_millis(4500));
+    }
+}
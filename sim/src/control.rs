@@ -0,0 +1,169 @@
+use crate::ASIAirSim;
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::watch;
+
+// Largest request body accepted by the control port
+const MAX_BODY: usize = 64 * 1024;
+
+/// Serves the control API over HTTP until the simulator is shut down
+pub async fn run_control(sim: ASIAirSim, listener: TcpListener, mut shutdown_rx: watch::Receiver<()>) {
+    loop {
+        tokio::select! {
+            _ = shutdown_rx.changed() => {
+                break;
+            }
+            accepted = listener.accept() => {
+                let Ok((stream, _)) = accepted else {
+                    continue;
+                };
+                tokio::spawn(serve(sim.clone(), stream));
+            }
+        }
+    }
+}
+
+// Answers a single request, the connection is closed after the response
+async fn serve(sim: ASIAirSim, stream: TcpStream) {
+    let mut reader = BufReader::new(stream);
+
+    let mut request_line = String::new();
+    if reader.read_line(&mut request_line).await.unwrap_or(0) == 0 {
+        return;
+    }
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or("").to_string();
+    let path = parts.next().unwrap_or("").to_string();
+
+    let mut content_length = 0;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).await.unwrap_or(0) == 0 {
+            return;
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap_or(0);
+            }
+        }
+    }
+
+    let (status, body) = if content_length > MAX_BODY {
+        (413, json!({ "error": "body too large" }))
+    } else {
+        let mut body = vec![0; content_length];
+        if reader.read_exact(&mut body).await.is_err() {
+            return;
+        }
+        let body = if body.is_empty() {
+            Ok(Value::Null)
+        } else {
+            serde_json::from_slice(&body)
+        };
+        match body {
+            Ok(body) => match route(&sim, &method, &path, &body) {
+                Ok(result) => (200, result),
+                Err((status, error)) => (status, json!({ "error": error })),
+            },
+            Err(error) => (400, json!({ "error": error.to_string() })),
+        }
+    };
+
+    let body = body.to_string();
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        _ => "Payload Too Large",
+    };
+    let response = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        reason,
+        body.len(),
+        body
+    );
+    let mut stream = reader.into_inner();
+    let _ = stream.write_all(response.as_bytes()).await;
+    let _ = stream.shutdown().await;
+}
+
+fn field<'a>(body: &'a Value, name: &str) -> Result<&'a Value, (u16, String)> {
+    match &body[name] {
+        Value::Null => Err((400, format!("{} is not provided", name))),
+        value => Ok(value),
+    }
+}
+
+fn str_field<'a>(body: &'a Value, name: &str) -> Result<&'a str, (u16, String)> {
+    field(body, name)?.as_str().ok_or((400, format!("{} is not a string", name)))
+}
+
+fn f64_field(body: &Value, name: &str) -> Result<f64, (u16, String)> {
+    field(body, name)?.as_f64().ok_or((400, format!("{} is not a number", name)))
+}
+
+fn bool_field(body: &Value, name: &str) -> Result<bool, (u16, String)> {
+    field(body, name)?.as_bool().ok_or((400, format!("{} is not a boolean", name)))
+}
+
+fn route(sim: &ASIAirSim, method: &str, path: &str, body: &Value) -> Result<Value, (u16, String)> {
+    match (method, path) {
+        ("GET", "/state") => Ok(sim.snapshot()),
+        ("POST", "/cameras/plug") => {
+            sim.plug_camera(str_field(body, "name")?).map_err(|e| (400, e))?;
+            Ok(sim.snapshot()["connected_cameras"].clone())
+        }
+        ("POST", "/cameras/unplug") => {
+            sim.unplug_camera(str_field(body, "name")?).map_err(|e| (400, e))?;
+            Ok(sim.snapshot()["connected_cameras"].clone())
+        }
+        ("POST", "/ambient_temperature") => {
+            sim.set_ambient_temperature(f64_field(body, "temperature")?);
+            Ok(json!(0))
+        }
+        ("POST", "/time/advance") => {
+            let seconds = f64_field(body, "seconds")?;
+            sim.advance_time(chrono::Duration::milliseconds((seconds * 1000.0).round() as i64));
+            Ok(json!({ "time": sim.now().to_rfc3339() }))
+        }
+        ("POST", "/storage/attach") => {
+            let capacity = field(body, "capacity")?.as_u64().ok_or((400, "capacity is not a number".to_string()))?;
+            sim.attach_storage(str_field(body, "name")?, capacity);
+            Ok(json!(0))
+        }
+        ("POST", "/storage/detach") => {
+            sim.detach_storage(str_field(body, "name")?);
+            Ok(json!(0))
+        }
+        ("POST", "/usb3_host") => {
+            sim.set_usb3_host(bool_field(body, "usb3_host")?);
+            Ok(json!(0))
+        }
+        ("POST", "/polar_error") => {
+            sim.set_polar_error(f64_field(body, "alt")?, f64_field(body, "az")?);
+            Ok(json!(0))
+        }
+        ("POST", "/pi/faults") => {
+            // Only the faults present in the body change
+            let mut config = sim.pi_config();
+            if !body["undervolt"].is_null() {
+                config.undervolt = bool_field(body, "undervolt")?;
+            }
+            if !body["over_current"].is_null() {
+                config.over_current = bool_field(body, "over_current")?;
+            }
+            if !body["force_overtemp"].is_null() {
+                config.force_overtemp = bool_field(body, "force_overtemp")?;
+            }
+            sim.set_pi_config(config);
+            Ok(json!(0))
+        }
+        _ => Err((404, format!("no route for {} {}", method, path))),
+    }
+}
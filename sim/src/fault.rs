@@ -0,0 +1,237 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+// Offset of the id of the request in the header of the binary responses
+const HEADER_ID_OFFSET: usize = 15;
+
+/// Ports of the device faults can be injected on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Port {
+    Udp4720,
+    Tcp4500,
+    Tcp4700,
+    Tcp4800,
+}
+
+/// Trouble affecting a single response
+#[derive(Debug, Clone, PartialEq)]
+pub enum Fault {
+    // Answer late
+    Delay(Duration),
+    // Never answer
+    Drop,
+    // Send only the first bytes of the response. On port 4800 the header still announces the
+    // full payload, on the other ports the cut line is terminated
+    Truncate(usize),
+    // Send a response that can't be parsed, a cut JSON line or a 4800 header not matching the request
+    Malformed,
+    // Close the socket in the middle of the response
+    Close,
+}
+
+/// Fault applied to the responses to a method, or to every method
+#[derive(Debug, Clone)]
+pub struct FaultRule {
+    // Method the rule applies to, every method when None
+    pub method: Option<String>,
+    pub fault: Fault,
+    // Chance of the fault for each matching request
+    pub probability: f64,
+    // Number of faults injected before the rule expires, unlimited when None
+    pub times: Option<u32>,
+}
+
+impl FaultRule {
+    /// Inject `fault` on every response to `method`
+    pub fn method(method: &str, fault: Fault) -> Self {
+        FaultRule {
+            method: Some(method.to_string()),
+            fault,
+            probability: 1.0,
+            times: None,
+        }
+    }
+
+    /// Inject `fault` on any response with a chance of `probability`
+    pub fn random(probability: f64, fault: Fault) -> Self {
+        FaultRule {
+            method: None,
+            fault,
+            probability,
+            times: None,
+        }
+    }
+
+    /// Expire the rule after `times` faults
+    pub fn times(self, times: u32) -> Self {
+        FaultRule {
+            times: Some(times),
+            ..self
+        }
+    }
+}
+
+/// Network trouble of a port. The delays run on the wall clock like the network, whatever the
+/// clock of the simulator
+#[derive(Debug, Clone, Default)]
+pub struct PortFaults {
+    // Delay of every response, plus a random jitter up to `jitter`
+    pub latency: Duration,
+    pub jitter: Duration,
+    // Delay of the events sent on the port
+    pub event_delay: Duration,
+    // The first matching rule decides the fault of a response
+    pub rules: Vec<FaultRule>,
+}
+
+/// Faults of the transport of the simulator, none by default
+#[derive(Debug, Clone, Default)]
+pub struct FaultConfig {
+    // Seed of the random draws, the same requests get the same faults with the same seed
+    pub seed: u64,
+    pub ports: HashMap<Port, PortFaults>,
+}
+
+/// Fate of a response decided by the fault layer
+#[derive(Debug, Clone, PartialEq)]
+pub struct FaultDecision {
+    pub delay: Duration,
+    pub fault: Option<Fault>,
+}
+
+/// Decides the faults of the responses, in the order of the requests
+#[derive(Debug, Clone)]
+pub struct FaultInjector {
+    config: FaultConfig,
+    rng: StdRng,
+    // Faults injected by each rule, by port and rule index
+    injected: HashMap<(Port, usize), u32>,
+}
+
+impl FaultInjector {
+    pub fn new() -> Self {
+        FaultInjector {
+            config: FaultConfig::default(),
+            rng: StdRng::seed_from_u64(0),
+            injected: HashMap::new(),
+        }
+    }
+
+    /// Replace the faults, the random draws restart from the seed
+    pub fn set_config(&mut self, config: FaultConfig) {
+        self.rng = StdRng::seed_from_u64(config.seed);
+        self.injected.clear();
+        self.config = config;
+    }
+
+    pub fn config(&self) -> &FaultConfig {
+        &self.config
+    }
+
+    pub fn event_delay(&self, port: Port) -> Duration {
+        self.config.ports.get(&port).map(|faults| faults.event_delay).unwrap_or_default()
+    }
+
+    /// Decide the delay and the fault of the response to `method` on `port`
+    pub fn decide(&mut self, port: Port, method: &str) -> FaultDecision {
+        let Some(faults) = self.config.ports.get(&port) else {
+            return FaultDecision {
+                delay: Duration::ZERO,
+                fault: None,
+            };
+        };
+
+        let mut delay = faults.latency;
+        if !faults.jitter.is_zero() {
+            delay += faults.jitter.mul_f64(self.rng.random::<f64>());
+        }
+
+        let mut fault = None;
+        for (index, rule) in faults.rules.iter().enumerate() {
+            if rule.method.as_ref().is_some_and(|rule_method| rule_method != method) {
+                continue;
+            }
+            let injected = self.injected.entry((port, index)).or_default();
+            if rule.times.is_some_and(|times| *injected >= times) {
+                continue;
+            }
+            if rule.probability < 1.0 && !self.rng.random_bool(rule.probability.max(0.0)) {
+                continue;
+            }
+            *injected += 1;
+            fault = Some(rule.fault.clone());
+            break;
+        }
+
+        if let Some(Fault::Delay(extra)) = fault {
+            delay += extra;
+            fault = None;
+        }
+        FaultDecision { delay, fault }
+    }
+}
+
+/// Write a response line of the text ports through `fault`, returns false when the socket
+/// has to be closed
+pub async fn write_line<W: AsyncWrite + Unpin>(stream: &mut W, line: &str, fault: Option<&Fault>) -> std::io::Result<bool> {
+    let bytes = line.as_bytes();
+    match fault {
+        None | Some(Fault::Delay(_)) => stream.write_all(bytes).await?,
+        Some(Fault::Drop) => {}
+        Some(Fault::Truncate(len)) => {
+            stream.write_all(&bytes[..(*len).min(bytes.len())]).await?;
+            stream.write_all(b"\r\n").await?;
+        }
+        Some(Fault::Malformed) => {
+            stream.write_all(&bytes[..bytes.len() / 2]).await?;
+            stream.write_all(b"\r\n").await?;
+        }
+        Some(Fault::Close) => {
+            stream.write_all(&bytes[..bytes.len() / 2]).await?;
+            stream.shutdown().await?;
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// Write a binary response of port 4800 through `fault`, returns false when the socket
+/// has to be closed
+pub async fn write_binary<W: AsyncWrite + Unpin>(
+    stream: &mut W,
+    header: &[u8],
+    payload: &[u8],
+    fault: Option<&Fault>,
+) -> std::io::Result<bool> {
+    match fault {
+        None | Some(Fault::Delay(_)) => {
+            stream.write_all(header).await?;
+            stream.write_all(payload).await?;
+        }
+        Some(Fault::Drop) => {}
+        Some(Fault::Truncate(len)) => {
+            stream.write_all(header).await?;
+            stream.write_all(&payload[..(*len).min(payload.len())]).await?;
+        }
+        Some(Fault::Malformed) => {
+            // Both the magic and the id of the request are wrong
+            let mut header = header.to_vec();
+            for byte in &mut header[..6] {
+                *byte = !*byte;
+            }
+            header[HEADER_ID_OFFSET] = !header[HEADER_ID_OFFSET];
+            stream.write_all(&header).await?;
+            stream.write_all(payload).await?;
+        }
+        Some(Fault::Close) => {
+            stream.write_all(header).await?;
+            stream.write_all(&payload[..payload.len() / 2]).await?;
+            stream.shutdown().await?;
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
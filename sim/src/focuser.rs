@@ -0,0 +1,154 @@
+use crate::sim::ASIAirState;
+use serde_json::{json, Value};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::watch;
+
+// Period of the focuser simulation loop
+pub const FOCUSER_TICK: Duration = Duration::from_millis(50);
+
+/// Simulated electronic focuser, and the optics it focuses
+#[derive(Debug, Clone)]
+pub struct Focuser {
+    // Position reported by the focuser, in steps
+    pub position: i32,
+    pub max_step: i32,
+    // Steps lost by the gears when reversing direction
+    pub backlash: i32,
+    // Moving speed in steps per second
+    pub speed: f64,
+    // Ambient temperature in degrees Celsius, and its drift in degrees per hour
+    pub temperature: f64,
+    pub temperature_drift: f64,
+    // Focus position at the reference temperature, and how it moves with the temperature in steps per degree
+    pub best_position: i32,
+    pub reference_temperature: f64,
+    pub temperature_coefficient: f64,
+    // Star half flux radius at best focus in pixels, and its growth in pixels per step away from it
+    pub min_hfr: f64,
+    pub hfr_slope: f64,
+    target: Option<i32>,
+    // Position actually reached by the optics, which differs from the reported one by the backlash
+    optical_position: f64,
+    // Slack left in the gears before the optics move outward, between 0 and the backlash
+    play: f64,
+}
+
+impl Focuser {
+    pub fn new() -> Self {
+        Focuser {
+            position: 5000,
+            max_step: 10000,
+            backlash: 20,
+            speed: 2000.0,
+            temperature: 15.0,
+            temperature_drift: -0.5,
+            best_position: 5300,
+            reference_temperature: 15.0,
+            temperature_coefficient: -20.0,
+            min_hfr: 1.8,
+            hfr_slope: 0.02,
+            target: None,
+            optical_position: 5000.0,
+            play: 0.0,
+        }
+    }
+
+    pub fn is_moving(&self) -> bool {
+        self.target.is_some()
+    }
+
+    pub fn move_to(&mut self, position: i32) -> Result<(), String> {
+        if !(0..=self.max_step).contains(&position) {
+            return Err(format!("position out of range 0..{}", self.max_step));
+        }
+        self.target = Some(position);
+        Ok(())
+    }
+
+    pub fn stop(&mut self) {
+        self.target = None;
+    }
+
+    /// Focus position of the optics at the current temperature
+    pub fn focus_position(&self) -> f64 {
+        self.best_position as f64 + self.temperature_coefficient * (self.temperature - self.reference_temperature)
+    }
+
+    /// Star half flux radius in unbinned pixels for the current optics position, following a V-curve
+    pub fn hfr(&self) -> f64 {
+        let defocus = self.hfr_slope * (self.optical_position - self.focus_position());
+        self.min_hfr.hypot(defocus)
+    }
+
+    pub fn state(&self) -> Value {
+        json!({
+            "position": self.position,
+            "max_step": self.max_step,
+            "backlash": self.backlash,
+            "temperature": self.temperature,
+            "moving": self.is_moving(),
+        })
+    }
+
+    // Move the gears, the optics only follow once the slack is taken up
+    fn move_by(&mut self, steps: f64) {
+        let backlash = self.backlash as f64;
+        let slack = if steps > 0.0 { self.play.min(steps) } else { (backlash - self.play).min(-steps) };
+        if steps > 0.0 {
+            self.play -= slack;
+            self.optical_position += steps - slack;
+        } else {
+            self.play += slack;
+            self.optical_position += steps + slack;
+        }
+    }
+
+    /// Advance the simulation by `dt`, returning the events generated meanwhile
+    pub fn step(&mut self, dt: Duration) -> Vec<Value> {
+        let mut events = Vec::new();
+
+        self.temperature += self.temperature_drift * dt.as_secs_f64() / 3600.0;
+
+        if let Some(target) = self.target {
+            let max_steps = (self.speed * dt.as_secs_f64()).max(1.0) as i32;
+            let steps = (target - self.position).clamp(-max_steps, max_steps);
+            self.position += steps;
+            self.move_by(steps as f64);
+            if self.position == target {
+                self.target = None;
+                events.push(json!({
+                    "Event": "Focuser",
+                    "state": "idle",
+                    "position": self.position,
+                }));
+            }
+        }
+
+        events
+    }
+}
+
+/// Drives the focuser simulation until the simulator is shut down
+pub async fn run_focuser(state: Arc<Mutex<ASIAirState>>, mut shutdown_rx: watch::Receiver<()>) {
+    let clock = state.lock().unwrap().clock.clone();
+    let mut interval = clock.interval(FOCUSER_TICK);
+    let mut last = clock.now();
+    loop {
+        tokio::select! {
+            _ = shutdown_rx.changed() => {
+                break;
+            }
+            _ = interval.tick() => {
+                let now = clock.now();
+                let dt = now - last;
+                last = now;
+
+                let mut state = state.lock().unwrap();
+                for event in state.focuser.step(dt) {
+                    state.emit_event(event);
+                }
+            }
+        }
+    }
+}
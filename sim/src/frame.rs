@@ -0,0 +1,254 @@
+use crate::catalog;
+use crate::mount::PierSide;
+use crate::sim::{ASIAirState, FrameType};
+use crate::solver;
+use std::io::{Cursor, Write};
+
+// Sensor model, in electrons
+pub const BIAS_OFFSET_ADU: f64 = 500.0;
+const READ_NOISE: f64 = 1.5;
+const FULL_WELL: f64 = 50000.0;
+// Signal per second and per pixel
+const DARK_CURRENT: f64 = 0.01;
+const SKY_BACKGROUND: f64 = 2.0;
+const FLAT_PANEL: f64 = 2000.0;
+// Flux of the brightest stars, in electrons per second
+const MAX_STAR_FLUX: f64 = 2.0e6;
+// Stars generated in each square degree of sky
+const STARS_PER_TILE: f64 = 60.0;
+const SKY_SEED: u64 = 0x5eed_0f5c_1e55;
+
+/// Small and fast deterministic random generator
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    // Uniform value in [0, 1)
+    fn uniform(&mut self) -> f64 {
+        (self.next() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    // Approximately normal value with unit variance, from the sum of four uniform values
+    fn normal(&mut self) -> f64 {
+        let v = self.next();
+        let sum = (v & 0xffff) + ((v >> 16) & 0xffff) + ((v >> 32) & 0xffff) + (v >> 48);
+        (sum as f64 / 65536.0 - 2.0) * 3.0f64.sqrt()
+    }
+}
+
+/// Star visible in a frame
+#[derive(Debug, Clone, Copy)]
+pub struct Star {
+    // Position in binned pixels from the top left corner
+    pub x: f64,
+    pub y: f64,
+    // Signal in electrons per second
+    pub flux: f64,
+}
+
+/// Everything needed to render the image of an exposure
+#[derive(Debug, Clone)]
+pub struct FrameParams {
+    pub frame_type: FrameType,
+    // Image size in binned pixels
+    pub width: u32,
+    pub height: u32,
+    pub bin: u32,
+    pub exposure_us: u64,
+    pub gain: i64,
+    // Image center in hours and degrees, rotation in degrees east of north, and scale in arcseconds per binned pixel
+    pub ra: f64,
+    pub dec: f64,
+    pub angle: f64,
+    pub scale: f64,
+    // Star half flux radius in binned pixels
+    pub hfr: f64,
+    // Seed of the noise of this frame
+    pub seed: u64,
+}
+
+impl FrameParams {
+    /// Parameters of a frame exposed now with the main camera
+    pub fn capture(state: &ASIAirState, frame_type: FrameType, exposure_us: u64, seed: u64) -> Result<Self, String> {
+        let (size, scale) = solver::image_geometry(state)?;
+        let bin = state.camera_bin.max(1);
+        let flip = if state.mount.pier_side == PierSide::East { 180.0 } else { 0.0 };
+        Ok(FrameParams {
+            frame_type,
+            width: size[0],
+            height: size[1],
+            bin,
+            exposure_us,
+            gain: state.camera_controls.gain,
+            ra: state.mount.ra,
+            dec: state.mount.dec,
+            angle: (state.solver.camera_angle + flip + 180.0).rem_euclid(360.0) - 180.0,
+            scale,
+            hfr: state.focuser.hfr() / bin as f64,
+            seed,
+        })
+    }
+
+    // Conversion factor from electrons to ADU, unity gain is at 100
+    fn adu_per_electron(&self) -> f64 {
+        10f64.powf((self.gain - 100) as f64 / 200.0)
+    }
+
+    fn exposure_s(&self) -> f64 {
+        self.exposure_us as f64 / 1e6
+    }
+
+    /// Mean signal of the background in ADU, without the stars
+    pub fn background_adu(&self) -> f64 {
+        let pixel_area = (self.bin * self.bin) as f64;
+        let electrons = match self.frame_type {
+            FrameType::Bias => 0.0,
+            FrameType::Dark => DARK_CURRENT * pixel_area * self.exposure_s(),
+            FrameType::Flat => FLAT_PANEL * pixel_area * self.exposure_s(),
+            FrameType::Light => (DARK_CURRENT + SKY_BACKGROUND) * pixel_area * self.exposure_s(),
+        };
+        (BIAS_OFFSET_ADU + electrons.min(FULL_WELL) * self.adu_per_electron()).min(65535.0)
+    }
+
+    /// Highest value a pixel reaches, when the sensor is at full well or the ADC saturates
+    pub fn saturation_adu(&self) -> f64 {
+        (BIAS_OFFSET_ADU + FULL_WELL * self.adu_per_electron()).min(65535.0)
+    }
+
+    /// Stars of the sky in the field of view, their positions are fixed on the sky so they
+    /// follow the pointing from one frame to the next
+    pub fn stars(&self) -> Vec<Star> {
+        if self.frame_type != FrameType::Light {
+            return Vec::new();
+        }
+
+        let size = [self.width, self.height];
+        let radius = (self.width as f64).hypot(self.height as f64) / 2.0 * self.scale / 3600.0;
+        let dec_min = (self.dec - radius).floor().max(-90.0) as i32;
+        let dec_max = (self.dec + radius).ceil().min(90.0) as i32;
+
+        let mut stars = Vec::new();
+        for tile_dec in dec_min..dec_max {
+            // Tiles are 1 degree wide in RA and declination, close to the poles a whole band is visible
+            let cos_dec = (tile_dec as f64 + 0.5).to_radians().cos();
+            let (ra_min, ra_max) = if self.dec.abs() + radius >= 89.0 {
+                (0, 360)
+            } else {
+                let half_width = radius / cos_dec.max(0.01);
+                ((self.ra * 15.0 - half_width).floor() as i32, (self.ra * 15.0 + half_width).ceil() as i32)
+            };
+            for tile_ra in ra_min..ra_max {
+                let tile_ra = tile_ra.rem_euclid(360);
+                let mut rng = SplitMix64(SKY_SEED ^ ((tile_ra as u64) << 32) ^ (tile_dec + 90) as u64);
+                let count = (STARS_PER_TILE * cos_dec).round().max(1.0) as usize;
+                for _ in 0..count {
+                    let ra = (tile_ra as f64 + rng.uniform()) / 15.0;
+                    let dec = tile_dec as f64 + rng.uniform();
+                    let flux = MAX_STAR_FLUX * rng.uniform().powi(16);
+                    if let Some((x, y)) = catalog::project(self.ra, self.dec, self.angle, self.scale, size, (ra, dec)) {
+                        if x >= 0.0 && y >= 0.0 && x < self.width as f64 && y < self.height as f64 {
+                            stars.push(Star { x, y, flux });
+                        }
+                    }
+                }
+            }
+        }
+        stars
+    }
+
+    /// Render the frame as big-endian 16 bit signed pixels, offset by 32768 from the sensor values
+    pub fn render(&self) -> Vec<u8> {
+        let (width, height) = (self.width as usize, self.height as usize);
+        let adu_per_electron = self.adu_per_electron();
+        let background = (self.background_adu() - BIAS_OFFSET_ADU) / adu_per_electron;
+        let background_noise = (background + READ_NOISE * READ_NOISE).sqrt();
+        let mut rng = SplitMix64(self.seed);
+
+        let mut data = Vec::with_capacity(width * height * 2);
+        for _ in 0..width * height {
+            let electrons = (background + background_noise * rng.normal()).clamp(0.0, FULL_WELL);
+            let adu = (BIAS_OFFSET_ADU + electrons * adu_per_electron).round().min(65535.0) as i32;
+            data.extend_from_slice(&((adu - 32768) as i16).to_be_bytes());
+        }
+
+        // Stars have a gaussian profile, with their own shot noise added to the background
+        let sigma = (self.hfr / (2.0 * 2f64.ln()).sqrt()).max(0.3);
+        let window = (4.0 * sigma).ceil() as i64 + 1;
+        for star in self.stars() {
+            let peak = star.flux * self.exposure_s() / (2.0 * std::f64::consts::PI * sigma * sigma);
+            let (cx, cy) = (star.x.floor() as i64, star.y.floor() as i64);
+            for y in (cy - window).max(0)..(cy + window + 1).min(height as i64) {
+                let dy = y as f64 + 0.5 - star.y;
+                for x in (cx - window).max(0)..(cx + window + 1).min(width as i64) {
+                    let dx = x as f64 + 0.5 - star.x;
+                    let electrons = peak * (-(dx * dx + dy * dy) / (2.0 * sigma * sigma)).exp();
+                    let electrons = (electrons + electrons.sqrt() * rng.normal()).max(0.0);
+
+                    let offset = (y as usize * width + x as usize) * 2;
+                    let pixel = i16::from_be_bytes([data[offset], data[offset + 1]]) as i32 + 32768;
+                    let adu = (pixel as f64 + electrons * adu_per_electron).round().min(65535.0) as i32;
+                    data[offset..offset + 2].copy_from_slice(&((adu - 32768) as i16).to_be_bytes());
+                }
+            }
+        }
+        data
+    }
+}
+
+/// Mean value of raw pixels in ADU
+pub fn mean_adu(raw: &[u8]) -> f64 {
+    let sum: f64 = raw.chunks_exact(2).map(|pixel| (i16::from_be_bytes([pixel[0], pixel[1]]) as i32 + 32768) as f64).sum();
+    sum / (raw.len() / 2).max(1) as f64
+}
+
+/// Pack raw pixels the way the device sends images, as the only file of a zip archive
+pub fn zip_frame(raw: &[u8]) -> Vec<u8> {
+    let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Stored)
+        .large_file(raw.len() >= u32::MAX as usize);
+    zip.start_file("raw", options).unwrap();
+    zip.write_all(raw).unwrap();
+    zip.finish().unwrap().into_inner()
+}
+
+/// Scale raw pixels to 8 bits grey pixels of `out_width` x `out_height`, keeping the aspect of
+/// the frame. The background is stretched so faint stars stand out, like the preview of the app
+pub fn preview(raw: &[u8], width: u32, height: u32, out_width: u32, out_height: u32) -> Vec<u8> {
+    let (width, height) = (width as usize, height as usize);
+    let (out_width, out_height) = (out_width as usize, out_height as usize);
+    let scale = (width as f64 / out_width as f64).max(height as f64 / out_height as f64);
+    let (used_width, used_height) = ((width as f64 / scale) as usize, (height as f64 / scale) as usize);
+    let (left, top) = ((out_width - used_width) / 2, (out_height - used_height) / 2);
+
+    let adu = |x: usize, y: usize| {
+        let offset = (y * width + x) * 2;
+        i16::from_be_bytes([raw[offset], raw[offset + 1]]) as i32 + 32768
+    };
+    let mut sampled = Vec::with_capacity(used_width * used_height);
+    for y in 0..used_height {
+        let source_y = ((y as f64 + 0.5) * scale) as usize;
+        for x in 0..used_width {
+            sampled.push(adu(((x as f64 + 0.5) * scale) as usize, source_y.min(height - 1)));
+        }
+    }
+
+    let mut sorted = sampled.clone();
+    sorted.sort_unstable();
+    let black = sorted.get(sorted.len() / 2).copied().unwrap_or(0);
+    let white = sorted.get(sorted.len() * 999 / 1000).copied().unwrap_or(0).max(black + 1);
+
+    let mut pixels = vec![0u8; out_width * out_height];
+    for (index, value) in sampled.into_iter().enumerate() {
+        let level = ((value - black) as f64 / (white - black) as f64).clamp(0.0, 1.0).sqrt();
+        pixels[(top + index / used_width) * out_width + left + index % used_width] = (level * 255.0) as u8;
+    }
+    pixels
+}
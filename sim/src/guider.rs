@@ -0,0 +1,273 @@
+use crate::mount::{Mount, SIDEREAL_RATE};
+use crate::solver::gaussian;
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::f64::consts::PI;
+use std::time::Duration;
+
+// Number of guide frames the RMS statistics are computed on
+const RMS_FRAMES: usize = 50;
+
+/// Behavior of the simulated guide camera and mount errors
+#[derive(Debug, Clone)]
+pub struct GuiderConfig {
+    // Exposure of the guide camera, one correction is sent per frame
+    pub exposure: Duration,
+    // Scale of the guide camera in arcseconds per pixel
+    pub scale_arcsec: f64,
+    // Amplitude in arcseconds and period of the periodic error of the RA worm gear
+    pub periodic_error_arcsec: f64,
+    pub periodic_error_period: Duration,
+    // Random walk of the tracking error on both axes, in arcseconds per square root of second
+    pub drift_arcsec: f64,
+    // Standard deviation of the star position measured on each frame, in arcseconds
+    pub seeing_arcsec: f64,
+    // Part of the measured error corrected on each frame
+    pub aggressiveness: f64,
+    // Errors below this number of pixels are not corrected
+    pub min_move_px: f64,
+    pub max_pulse_ms: u32,
+    // The star is lost when it moves further than this number of pixels from the lock position
+    pub search_region_px: f64,
+    // Guiding is settled once the error stayed below `settle_px` pixels for `settle_time`,
+    // settling fails after `settle_timeout`
+    pub settle_px: f64,
+    pub settle_time: Duration,
+    pub settle_timeout: Duration,
+    // When set, the guide star can't be found in the frames
+    pub star_lost: bool,
+}
+
+impl Default for GuiderConfig {
+    fn default() -> Self {
+        GuiderConfig {
+            exposure: Duration::from_secs(1),
+            scale_arcsec: 4.98,
+            periodic_error_arcsec: 8.0,
+            periodic_error_period: Duration::from_secs(480),
+            drift_arcsec: 0.15,
+            seeing_arcsec: 0.4,
+            aggressiveness: 0.7,
+            min_move_px: 0.1,
+            max_pulse_ms: 2000,
+            search_region_px: 15.0,
+            settle_px: 1.5,
+            settle_time: Duration::from_secs(5),
+            settle_timeout: Duration::from_secs(60),
+            star_lost: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GuideStatus {
+    Idle,
+    Guiding,
+    // Waiting for the star to come back to the lock position after a dither
+    Settling,
+    Lost,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Settle {
+    elapsed: Duration,
+    in_range: Duration,
+}
+
+/// Simulated autoguider, keeping a star on its lock position with guide pulses
+#[derive(Debug, Clone)]
+pub struct Guider {
+    pub config: GuiderConfig,
+    pub status: GuideStatus,
+    // Position of the guide star against where guiding started, in arcseconds toward east and north
+    error: [f64; 2],
+    // Offset of the lock position added by dithers, in arcseconds
+    lock_offset: [f64; 2],
+    // Time since guiding started, the phase of the periodic error
+    elapsed: Duration,
+    settle: Option<Settle>,
+    // Errors measured on the last frames, in arcseconds
+    history: VecDeque<[f64; 2]>,
+    frame: u64,
+}
+
+impl Guider {
+    pub fn new() -> Self {
+        Guider {
+            config: GuiderConfig::default(),
+            status: GuideStatus::Idle,
+            error: [0.0; 2],
+            lock_offset: [0.0; 2],
+            elapsed: Duration::ZERO,
+            settle: None,
+            history: VecDeque::new(),
+            frame: 0,
+        }
+    }
+
+    pub fn is_guiding(&self) -> bool {
+        self.status != GuideStatus::Idle
+    }
+
+    pub fn start(&mut self, mount: &Mount) -> Result<(), String> {
+        if self.is_guiding() {
+            return Err("guiding in progress".to_string());
+        }
+        if mount.parked || !mount.tracking || mount.is_slewing() {
+            return Err("mount is not tracking".to_string());
+        }
+        *self = Guider {
+            config: self.config.clone(),
+            status: GuideStatus::Guiding,
+            ..Guider::new()
+        };
+        Ok(())
+    }
+
+    pub fn stop(&mut self) {
+        self.status = GuideStatus::Idle;
+        self.settle = None;
+    }
+
+    /// Move the lock position by a random offset of up to `pixels` pixels, and wait for the star to settle on it
+    pub fn dither(&mut self, pixels: f64, ra_only: bool) {
+        let scale = self.config.scale_arcsec;
+        self.lock_offset[0] += (rand::random::<f64>() * 2.0 - 1.0) * pixels * scale;
+        if !ra_only {
+            self.lock_offset[1] += (rand::random::<f64>() * 2.0 - 1.0) * pixels * scale;
+        }
+        self.settle = Some(Settle {
+            elapsed: Duration::ZERO,
+            in_range: Duration::ZERO,
+        });
+        if self.status == GuideStatus::Guiding {
+            self.status = GuideStatus::Settling;
+        }
+    }
+
+    /// RMS of the RA, Dec and total errors on the last frames, in arcseconds
+    pub fn rms(&self) -> [f64; 3] {
+        if self.history.is_empty() {
+            return [0.0; 3];
+        }
+        let count = self.history.len() as f64;
+        let ra = (self.history.iter().map(|e| e[0] * e[0]).sum::<f64>() / count).sqrt();
+        let dec = (self.history.iter().map(|e| e[1] * e[1]).sum::<f64>() / count).sqrt();
+        [ra, dec, ra.hypot(dec)]
+    }
+
+    pub fn state(&self) -> Value {
+        let [rms_ra, rms_dec, rms_total] = self.rms();
+        json!({
+            "state": self.status,
+            "frame": self.frame,
+            "rms_ra": rms_ra,
+            "rms_dec": rms_dec,
+            "rms_total": rms_total,
+        })
+    }
+
+    fn periodic_error(&self, elapsed: Duration) -> f64 {
+        let phase = 2.0 * PI * elapsed.as_secs_f64() / self.config.periodic_error_period.as_secs_f64();
+        self.config.periodic_error_arcsec * phase.sin()
+    }
+
+    // Guide pulse in milliseconds correcting an error in arcseconds, and the correction it makes.
+    // Pulses are positive toward east and north
+    fn pulse(&self, error: f64, guide_rate: f64) -> (f64, f64) {
+        if error.abs() / self.config.scale_arcsec < self.config.min_move_px || guide_rate <= 0.0 {
+            return (0.0, 0.0);
+        }
+        let rate = guide_rate * SIDEREAL_RATE;
+        let max_pulse = self.config.max_pulse_ms as f64;
+        let pulse = (-self.config.aggressiveness * error / rate * 1000.0).clamp(-max_pulse, max_pulse).round();
+        (pulse, pulse / 1000.0 * rate)
+    }
+
+    /// Take the next guide frame and correct the error it measures, returning the events generated
+    pub fn step(&mut self, mount: &Mount, guide_rate: f64) -> Vec<Value> {
+        let mut events = Vec::new();
+        if !self.is_guiding() {
+            return events;
+        }
+        let dt = self.config.exposure;
+        self.frame += 1;
+
+        // The star moves with the periodic error and random drift, and runs away when the mount stops tracking
+        let periodic = self.periodic_error(self.elapsed + dt) - self.periodic_error(self.elapsed);
+        self.elapsed += dt;
+        let walk = self.config.drift_arcsec * dt.as_secs_f64().sqrt();
+        self.error[0] += periodic + gaussian(walk);
+        self.error[1] += gaussian(walk);
+        if !mount.tracking || mount.is_slewing() {
+            self.error[0] -= SIDEREAL_RATE * dt.as_secs_f64();
+        }
+
+        let seeing = self.config.seeing_arcsec;
+        let measured = [
+            self.error[0] - self.lock_offset[0] + gaussian(seeing),
+            self.error[1] - self.lock_offset[1] + gaussian(seeing),
+        ];
+        let distance = measured[0].hypot(measured[1]) / self.config.scale_arcsec;
+        if self.config.star_lost || distance > self.config.search_region_px || mount.is_slewing() {
+            self.status = GuideStatus::Lost;
+            events.push(json!({
+                "Event": "Guide",
+                "state": "lost",
+                "frame": self.frame,
+                "error": "star lost",
+            }));
+            return events;
+        }
+        if self.status == GuideStatus::Lost {
+            self.status = if self.settle.is_some() { GuideStatus::Settling } else { GuideStatus::Guiding };
+        }
+
+        let (ra_pulse, ra_correction) = self.pulse(measured[0], guide_rate);
+        let (dec_pulse, dec_correction) = self.pulse(measured[1], guide_rate);
+        self.error[0] += ra_correction;
+        self.error[1] += dec_correction;
+
+        self.history.push_back(measured);
+        if self.history.len() > RMS_FRAMES {
+            self.history.pop_front();
+        }
+        let [rms_ra, rms_dec, rms_total] = self.rms();
+        events.push(json!({
+            "Event": "Guide",
+            "state": self.status,
+            "frame": self.frame,
+            "ra_error": measured[0],
+            "dec_error": measured[1],
+            "ra_pulse": ra_pulse,
+            "dec_pulse": dec_pulse,
+            "rms_ra": rms_ra,
+            "rms_dec": rms_dec,
+            "rms_total": rms_total,
+        }));
+
+        if let Some(mut settle) = self.settle {
+            settle.elapsed += dt;
+            settle.in_range = if distance <= self.config.settle_px { settle.in_range + dt } else { Duration::ZERO };
+            let settled = settle.in_range >= self.config.settle_time;
+            if settled || settle.elapsed >= self.config.settle_timeout {
+                self.settle = None;
+                self.status = GuideStatus::Guiding;
+                let mut event = json!({
+                    "Event": "Guide",
+                    "state": if settled { "settled" } else { "settle_failed" },
+                    "frame": self.frame,
+                });
+                if !settled {
+                    event["error"] = json!("settle timeout");
+                }
+                events.push(event);
+            } else {
+                self.settle = Some(settle);
+            }
+        }
+
+        events
+    }
+}
@@ -1,6 +1,27 @@
+mod catalog;
+mod clock;
+mod control;
+mod fault;
+mod focuser;
+mod frame;
+mod guider;
+mod mount;
+mod pi;
+mod recorder;
 mod rpc;
 mod rtc;
+mod rtmp;
 mod sim;
+mod solver;
+mod stack;
+mod storage;
+
+pub use clock::Clock;
+pub use fault::{Fault, FaultConfig, FaultRule, Port, PortFaults};
+pub use guider::GuiderConfig;
+pub use pi::PiConfig;
+pub use rtmp::{RtmpSink, RtmpSinkStats};
+pub use solver::{SolveFailure, SolverConfig};
 
 use sim::ASIAirState;
 use std::sync::{Arc, Mutex};
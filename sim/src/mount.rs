@@ -0,0 +1,291 @@
+use crate::sim::ASIAirState;
+use chrono::{DateTime, FixedOffset};
+use serde_json::{json, Value};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::watch;
+
+// Period of the mount simulation loop
+pub const MOUNT_TICK: Duration = Duration::from_millis(100);
+// Period of the position events sent while the mount is moving
+const POSITION_EVENT_PERIOD: Duration = Duration::from_millis(500);
+
+// Tracking rates in arcseconds per second
+pub const SIDEREAL_RATE: f64 = 15.041067;
+const LUNAR_RATE: f64 = 14.685;
+const SOLAR_RATE: f64 = 15.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrackMode {
+    Sidereal,
+    Lunar,
+    Solar,
+}
+
+impl TrackMode {
+    pub fn rate(&self) -> f64 {
+        match self {
+            TrackMode::Sidereal => SIDEREAL_RATE,
+            TrackMode::Lunar => LUNAR_RATE,
+            TrackMode::Solar => SOLAR_RATE,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PierSide {
+    // Telescope on the east side of the pier, pointing west of the meridian
+    East,
+    // Telescope on the west side of the pier, pointing east of the meridian
+    West,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MountMotion {
+    Idle,
+    Slewing { ra: f64, dec: f64 },
+    Parking,
+}
+
+/// Simulated German equatorial mount
+#[derive(Debug, Clone)]
+pub struct Mount {
+    // Current pointing, right ascension in hours and declination in degrees
+    pub ra: f64,
+    pub dec: f64,
+    // Site location in degrees, east longitudes are positive
+    pub latitude: f64,
+    pub longitude: f64,
+    pub tracking: bool,
+    pub track_mode: TrackMode,
+    pub parked: bool,
+    pub pier_side: PierSide,
+    // Slew speed in degrees per second on each axis
+    pub slew_rate: f64,
+    // Targets below this altitude can't be reached, and tracking stops when reaching it
+    pub min_altitude: f64,
+    // How far past the meridian the mount can track before hitting the pier, in minutes
+    pub meridian_limit_min: f64,
+    // Misalignment of the polar axis with the celestial pole, in arcminutes.
+    // Positive values point the axis above, and east of the pole
+    pub polar_error_alt: f64,
+    pub polar_error_az: f64,
+    motion: MountMotion,
+    since_position_event: Duration,
+}
+
+impl Mount {
+    pub fn new() -> Self {
+        Mount {
+            ra: 0.0,
+            dec: 90.0,
+            latitude: 9.93,
+            longitude: -84.08,
+            tracking: false,
+            track_mode: TrackMode::Sidereal,
+            parked: false,
+            pier_side: PierSide::West,
+            slew_rate: 4.0,
+            min_altitude: 0.0,
+            meridian_limit_min: 30.0,
+            polar_error_alt: 25.0,
+            polar_error_az: -40.0,
+            motion: MountMotion::Idle,
+            since_position_event: Duration::ZERO,
+        }
+    }
+
+    pub fn is_slewing(&self) -> bool {
+        self.motion != MountMotion::Idle
+    }
+
+    /// Local sidereal time in hours
+    pub fn lst(&self, now: DateTime<FixedOffset>) -> f64 {
+        let j2000 = DateTime::parse_from_rfc3339("2000-01-01T12:00:00Z").unwrap();
+        let days = (now - j2000).num_milliseconds() as f64 / 86400000.0;
+        let gmst = 18.697374558 + 24.06570982441908 * days;
+        (gmst + self.longitude / 15.0).rem_euclid(24.0)
+    }
+
+    /// Hour angle of a right ascension in hours, in the [-12, 12) range
+    pub fn hour_angle(&self, ra: f64, now: DateTime<FixedOffset>) -> f64 {
+        (self.lst(now) - ra + 12.0).rem_euclid(24.0) - 12.0
+    }
+
+    pub fn altitude(&self, ra: f64, dec: f64, now: DateTime<FixedOffset>) -> f64 {
+        let ha = (self.hour_angle(ra, now) * 15.0).to_radians();
+        let lat = self.latitude.to_radians();
+        let dec = dec.to_radians();
+        (lat.sin() * dec.sin() + lat.cos() * dec.cos() * ha.cos()).asin().to_degrees()
+    }
+
+    pub fn goto(&mut self, ra: f64, dec: f64, now: DateTime<FixedOffset>) -> Result<(), String> {
+        if self.parked {
+            return Err("mount is parked".to_string());
+        }
+        if !(0.0..24.0).contains(&ra) || !(-90.0..=90.0).contains(&dec) {
+            return Err("invalid coordinates".to_string());
+        }
+        if self.altitude(ra, dec, now) < self.min_altitude {
+            return Err("target below the horizon limit".to_string());
+        }
+        self.motion = MountMotion::Slewing { ra, dec };
+        self.since_position_event = Duration::ZERO;
+        Ok(())
+    }
+
+    pub fn abort(&mut self) {
+        self.motion = MountMotion::Idle;
+    }
+
+    pub fn park(&mut self) {
+        self.tracking = false;
+        self.motion = MountMotion::Parking;
+        self.since_position_event = Duration::ZERO;
+    }
+
+    pub fn unpark(&mut self) {
+        if self.motion == MountMotion::Parking {
+            self.motion = MountMotion::Idle;
+        }
+        self.parked = false;
+    }
+
+    pub fn set_tracking(&mut self, tracking: bool) -> Result<(), String> {
+        if tracking && self.parked {
+            return Err("mount is parked".to_string());
+        }
+        self.tracking = tracking;
+        Ok(())
+    }
+
+    pub fn position_event(&self, now: DateTime<FixedOffset>) -> Value {
+        json!({
+            "Event": "ScopePosition",
+            "ra": self.ra,
+            "dec": self.dec,
+            "alt": self.altitude(self.ra, self.dec, now),
+            "pier_side": self.pier_side,
+            "tracking": self.tracking,
+            "slewing": self.is_slewing(),
+            "parked": self.parked,
+        })
+    }
+
+    // Move the mount axes toward a position, returns true once it is reached
+    fn move_toward(&mut self, ra: f64, dec: f64, dt: Duration) -> bool {
+        let step = self.slew_rate * dt.as_secs_f64();
+
+        // Shortest way around in right ascension, in degrees
+        let d_ra = ((ra - self.ra + 12.0).rem_euclid(24.0) - 12.0) * 15.0;
+        let d_dec = dec - self.dec;
+
+        self.ra = (self.ra + d_ra.clamp(-step, step) / 15.0).rem_euclid(24.0);
+        self.dec += d_dec.clamp(-step, step);
+
+        d_ra.abs() <= step && d_dec.abs() <= step
+    }
+
+    /// Advance the simulation by `dt`, returning the events generated meanwhile
+    pub fn step(&mut self, dt: Duration, now: DateTime<FixedOffset>) -> Vec<Value> {
+        let mut events = Vec::new();
+
+        // The sky turns at the sidereal rate, tracking compensates it at its own rate
+        let drift = if self.tracking { SIDEREAL_RATE - self.track_mode.rate() } else { SIDEREAL_RATE };
+        if !self.is_slewing() {
+            self.ra = (self.ra + drift * dt.as_secs_f64() / 3600.0 / 15.0).rem_euclid(24.0);
+        }
+
+        match self.motion {
+            MountMotion::Idle => {}
+            MountMotion::Slewing { ra, dec } => {
+                if self.move_toward(ra, dec, dt) {
+                    self.ra = ra;
+                    self.dec = dec;
+                    self.motion = MountMotion::Idle;
+                    self.tracking = true;
+                    self.pier_side = if self.hour_angle(ra, now) < 0.0 { PierSide::West } else { PierSide::East };
+                    events.push(json!({
+                        "Event": "ScopeGoto",
+                        "state": "complete",
+                        "ra": self.ra,
+                        "dec": self.dec,
+                    }));
+                }
+            }
+            MountMotion::Parking => {
+                // Park pointing at the celestial pole
+                let (ra, dec) = (self.lst(now), if self.latitude >= 0.0 { 90.0 } else { -90.0 });
+                if self.move_toward(ra, dec, dt) {
+                    self.ra = ra;
+                    self.dec = dec;
+                    self.motion = MountMotion::Idle;
+                    self.parked = true;
+                    self.pier_side = PierSide::West;
+                    events.push(json!({
+                        "Event": "ScopePark",
+                        "state": "complete",
+                    }));
+                }
+            }
+        }
+
+        if self.is_slewing() {
+            self.since_position_event += dt;
+            if self.since_position_event >= POSITION_EVENT_PERIOD {
+                self.since_position_event = Duration::ZERO;
+                events.push(self.position_event(now));
+            }
+        }
+
+        // Stop tracking before the telescope hits the pier or goes below the horizon
+        if self.tracking {
+            let limit = if self.pier_side == PierSide::West
+                && self.hour_angle(self.ra, now) * 60.0 > self.meridian_limit_min
+            {
+                Some("meridian")
+            } else if self.altitude(self.ra, self.dec, now) < self.min_altitude {
+                Some("horizon")
+            } else {
+                None
+            };
+            if let Some(limit) = limit {
+                self.tracking = false;
+                events.push(json!({
+                    "Event": "ScopeTrack",
+                    "tracking": false,
+                    "reason": limit,
+                }));
+            }
+        }
+
+        events
+    }
+}
+
+/// Drives the mount simulation until the simulator is shut down
+pub async fn run_mount(state: Arc<Mutex<ASIAirState>>, mut shutdown_rx: watch::Receiver<()>) {
+    let clock = state.lock().unwrap().clock.clone();
+    let mut interval = clock.interval(MOUNT_TICK);
+    let mut last = clock.now();
+    loop {
+        tokio::select! {
+            _ = shutdown_rx.changed() => {
+                break;
+            }
+            _ = interval.tick() => {
+                let now = clock.now();
+                let dt = now - last;
+                last = now;
+
+                let mut state = state.lock().unwrap();
+                let now = state.rtc.now();
+                for event in state.mount.step(dt, now) {
+                    state.emit_event(event);
+                }
+            }
+        }
+    }
+}
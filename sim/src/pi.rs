@@ -0,0 +1,149 @@
+use crate::sim::ASIAirState;
+use serde_json::{json, Value};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::watch;
+
+// Interval between two updates of the board temperature
+const PI_TICK: Duration = Duration::from_millis(100);
+
+/// Behavior of the simulated board, its CPU temperature and its power supply
+#[derive(Debug, Clone)]
+pub struct PiConfig {
+    // Ambient temperature in degrees Celsius, and how far above it the CPU settles when idle
+    pub ambient: f64,
+    pub idle_rise: f64,
+    // Extra heating while images are downloaded and while the live stack runs
+    pub download_rise: f64,
+    pub stack_rise: f64,
+    // Time for the temperature to cover 63% of the way to its equilibrium
+    pub time_constant: Duration,
+    // The board reports an overtemp above this temperature
+    pub overtemp: f64,
+    // Bytes per second the CPU packs for downloads, each download keeps it busy for its size
+    pub download_rate: f64,
+    // Interval between two PiStatus events
+    pub status_interval: Duration,
+    // Faults of the power supply, and an overtemp reported whatever the temperature
+    pub undervolt: bool,
+    pub over_current: bool,
+    pub force_overtemp: bool,
+}
+
+impl Default for PiConfig {
+    fn default() -> Self {
+        PiConfig {
+            ambient: 20.0,
+            idle_rise: 25.0,
+            download_rise: 20.0,
+            stack_rise: 15.0,
+            time_constant: Duration::from_secs(60),
+            overtemp: 80.0,
+            download_rate: 40.0e6,
+            status_interval: Duration::from_secs(5),
+            undervolt: false,
+            over_current: false,
+            force_overtemp: false,
+        }
+    }
+}
+
+/// Simulated board of the device
+#[derive(Debug, Clone)]
+pub struct Pi {
+    pub config: PiConfig,
+    // CPU temperature in degrees Celsius
+    pub temperature: f64,
+    // CPU time left to pack the requested downloads
+    download_busy: Duration,
+    since_status: Duration,
+}
+
+impl Pi {
+    pub fn new() -> Self {
+        let config = PiConfig::default();
+        Pi {
+            temperature: config.ambient + config.idle_rise,
+            config,
+            download_busy: Duration::ZERO,
+            since_status: Duration::ZERO,
+        }
+    }
+
+    /// Change the behavior of the board, a PiStatus event reports the change on the next step
+    pub fn set_config(&mut self, config: PiConfig) {
+        self.config = config;
+        self.since_status = self.config.status_interval;
+    }
+
+    /// Keep the CPU busy for a download of `bytes`
+    pub fn download(&mut self, bytes: usize) {
+        self.download_busy += Duration::from_secs_f64(bytes as f64 / self.config.download_rate.max(1.0));
+    }
+
+    pub fn is_overtemp(&self) -> bool {
+        self.config.force_overtemp || self.temperature >= self.config.overtemp
+    }
+
+    pub fn status_event(&self) -> Value {
+        json!({
+            "Event": "PiStatus",
+            "is_overtemp": self.is_overtemp(),
+            "temp": (self.temperature * 10.0).round() / 10.0,
+            "is_undervolt": self.config.undervolt,
+            "is_over_current": self.config.over_current,
+        })
+    }
+
+    /// Heat or cool the CPU for `dt` toward the temperature of its current load,
+    /// returns a PiStatus event when one is due
+    pub fn step(&mut self, dt: Duration, stacking: bool) -> Vec<Value> {
+        let mut target = self.config.ambient + self.config.idle_rise;
+        if stacking {
+            target += self.config.stack_rise;
+        }
+        // Long steps on virtual time are split where the download ends
+        let busy = self.download_busy.min(dt);
+        self.download_busy -= busy;
+        self.relax(target + self.config.download_rise, busy);
+        self.relax(target, dt - busy);
+
+        self.since_status += dt;
+        if self.since_status < self.config.status_interval {
+            return Vec::new();
+        }
+        self.since_status = Duration::ZERO;
+        vec![self.status_event()]
+    }
+
+    // Move the temperature toward `target` for `dt`
+    fn relax(&mut self, target: f64, dt: Duration) {
+        let time_constant = self.config.time_constant.as_secs_f64().max(1e-3);
+        self.temperature += (target - self.temperature) * (1.0 - (-dt.as_secs_f64() / time_constant).exp());
+    }
+}
+
+/// Drives the board simulation until the simulator is shut down
+pub async fn run_pi(state: Arc<Mutex<ASIAirState>>, mut shutdown_rx: watch::Receiver<()>) {
+    let clock = state.lock().unwrap().clock.clone();
+    let mut interval = clock.interval(PI_TICK);
+    let mut last = clock.now();
+    loop {
+        tokio::select! {
+            _ = shutdown_rx.changed() => {
+                break;
+            }
+            _ = interval.tick() => {
+                let now = clock.now();
+                let dt = now - last;
+                last = now;
+
+                let mut state = state.lock().unwrap();
+                let stacking = state.app_state.stack.is_working;
+                for event in state.pi.step(dt, stacking) {
+                    state.emit_event(event);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use crate::frame::FrameParams;
+use chrono::{DateTime, FixedOffset};
+use std::time::Duration;
+
+// Transfer speed of the camera link, in bytes per second
+const USB3_RATE: f64 = 320.0e6;
+const USB2_RATE: f64 = 35.0e6;
+// Write speed of the internal storage, in bytes per second
+const WRITE_RATE: f64 = 80.0e6;
+
+/// Frames per second read from the camera and written to the file. The camera can't go
+/// faster than its exposure or its link, and the file than the storage
+pub fn frame_rates(frame: &FrameParams, usb3_host: bool) -> (f64, f64) {
+    let bytes = frame.width as f64 * frame.height as f64 * 2.0;
+    let link = if usb3_host { USB3_RATE } else { USB2_RATE };
+    let fps = (1e6 / frame.exposure_us.max(1) as f64).min(link / bytes);
+    (fps, fps.min(WRITE_RATE / bytes))
+}
+
+/// Video being recorded from the main camera
+#[derive(Debug, Clone)]
+pub struct Recording {
+    // First frame of the video, the others only differ by their noise
+    pub frame: FrameParams,
+    // File extension, avi or ser
+    pub format: String,
+    pub target: String,
+    pub temperature: i64,
+    pub start: DateTime<FixedOffset>,
+    // Recording stops by itself after this time
+    pub duration: Option<Duration>,
+    pub elapsed: Duration,
+    pub fps: f64,
+    pub write_file_fps: f64,
+    // Frames written to the file, fractional between two frames
+    written: f64,
+}
+
+impl Recording {
+    pub fn new(frame: FrameParams, format: &str, target: &str, temperature: i64, start: DateTime<FixedOffset>, duration: Option<Duration>, usb3_host: bool) -> Self {
+        let (fps, write_file_fps) = frame_rates(&frame, usb3_host);
+        Recording {
+            frame,
+            format: format.to_string(),
+            target: target.to_string(),
+            temperature,
+            start,
+            duration,
+            elapsed: Duration::ZERO,
+            fps,
+            write_file_fps,
+            written: 0.0,
+        }
+    }
+
+    /// Record for `dt` more
+    pub fn step(&mut self, dt: Duration) {
+        self.elapsed += dt;
+        self.written += self.write_file_fps * dt.as_secs_f64();
+    }
+
+    pub fn frame_count(&self) -> u32 {
+        self.written as u32
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.duration.is_some_and(|duration| self.elapsed >= duration)
+    }
+}
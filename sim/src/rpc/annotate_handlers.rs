@@ -0,0 +1,41 @@
+use super::ASIAirState;
+use super::solve_handlers::{get_job_result, start_job, stop_job, SolverJob};
+use crate::catalog;
+use serde_json::{json, Value};
+use std::sync::{Arc, Mutex};
+
+const ANNOTATE: SolverJob = SolverJob {
+    event: "Annotate",
+    tag: "Annotate",
+    in_progress: "annotation in progress",
+    no_result: "no annotate result",
+    status: |state| (&mut state.app_state.annotate.is_working, &mut state.app_state.annotate.lapse_ms),
+    result: |state| &mut state.annotate_result,
+    task: |state| &mut state.annotate_task,
+    complete: add_objects,
+};
+
+// Lists the catalog objects the solved image contains
+fn add_objects(mut solution: Value, chip_size: [u32; 2], scale: f64) -> Value {
+    let (ra, dec, angle) = (
+        solution["ra"].as_f64().unwrap(),
+        solution["dec"].as_f64().unwrap(),
+        solution["angle"].as_f64().unwrap(),
+    );
+    solution["objects"] = json!(catalog::annotate(ra, dec, angle, scale, chip_size));
+    solution
+}
+
+// Solves the last image of the main camera and lists the catalog objects it contains,
+// the result is reported by Annotate events
+pub fn start_annotate(_: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
+    start_job(ANNOTATE, state)
+}
+
+pub fn stop_annotate(_: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
+    stop_job(ANNOTATE, state)
+}
+
+pub fn get_annotate_result(_: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
+    get_job_result(ANNOTATE, state)
+}
@@ -0,0 +1,168 @@
+use super::ASIAirState;
+use crate::frame::{self, FrameParams, BIAS_OFFSET_ADU};
+use crate::sim::FrameType;
+use serde_json::{json, Value};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+// Frames taken before giving up on reaching the target
+const MAX_ITERATIONS: u32 = 10;
+// Range of the exposures tried, in microseconds
+const MIN_EXPOSURE_US: u64 = 32;
+const MAX_EXPOSURE_US: u64 = 60_000_000;
+// Default tolerance, as a fraction of the target
+const DEFAULT_TOLERANCE: f64 = 0.05;
+
+fn emit_auto_exp_event(state: &ASIAirState, auto_exp_state: &str, error: Option<&str>) {
+    let auto_exp = &state.app_state.auto_exp;
+    let mut event = json!({
+        "Event": "AutoExp",
+        "state": auto_exp_state,
+        "exposure_us": auto_exp.exposure_us,
+        "mean_adu": auto_exp.mean_adu,
+        "iteration": auto_exp.iteration,
+    });
+    if let Some(error) = error {
+        event["error"] = json!(error);
+    }
+    state.emit_event(event);
+}
+
+// Ends the auto exposure, with `error` when the target couldn't be reached
+fn finish_auto_exp(state: &mut ASIAirState, error: Option<&str>) {
+    state.auto_exp_task = None;
+    state.app_state.auto_exp.is_working = false;
+    match error {
+        Some(error) => emit_auto_exp_event(state, "fail", Some(error)),
+        None => {
+            // The main camera keeps the exposure found
+            state.camera_controls.exposure = state.app_state.auto_exp.exposure_us as i64;
+            emit_auto_exp_event(state, "complete", None);
+        }
+    }
+}
+
+// Exposure expected to give `target_adu`, from the mean of a frame. The signal is proportional
+// to the exposure until the sensor saturates, then only a shorter exposure tells how far it is
+fn next_exposure(frame: &FrameParams, mean_adu: f64, target_adu: f64) -> u64 {
+    let exposure_us = frame.exposure_us as f64;
+    let signal = mean_adu - BIAS_OFFSET_ADU;
+    let next = if mean_adu >= frame.saturation_adu() * 0.98 {
+        exposure_us / 4.0
+    } else if signal < 1.0 {
+        exposure_us * 10.0
+    } else {
+        exposure_us * (target_adu - BIAS_OFFSET_ADU) / signal
+    };
+    (next.round() as u64).clamp(MIN_EXPOSURE_US, MAX_EXPOSURE_US)
+}
+
+// Finds the exposure of the main camera giving frames of a mean ADU, for flats or previews
+pub fn start_auto_exp(params: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
+    let options = params.as_ref().map(|value| value[0].clone()).unwrap_or(Value::Null);
+    let frame_type = match options["frame_type"].as_str().map(FrameType::from_str) {
+        Some(Ok(frame_type)) => frame_type,
+        Some(Err(_)) => return Err(("unexpected param".to_string(), 1)),
+        None => FrameType::Flat,
+    };
+    let target_adu = options["target_adu"].as_f64().ok_or(("target_adu is not provided".to_string(), 1))?;
+    let tolerance = options["tolerance"].as_f64().unwrap_or(target_adu * DEFAULT_TOLERANCE);
+    if tolerance <= 0.0 {
+        return Err(("invalid tolerance".to_string(), 1));
+    }
+
+    {
+        let mut state = state.lock().unwrap();
+        if state.app_state.auto_exp.is_working {
+            return Err(("auto exposure in progress".to_string(), 1));
+        }
+        if state.app_state.capture.is_working || state.app_state.plan.is_working {
+            return Err(("exposure in progress".to_string(), 1));
+        }
+        if state.app_state.avi_record.is_working {
+            return Err(("recording in progress".to_string(), 1));
+        }
+        let exposure_us = (state.camera_controls.exposure.max(0) as u64).clamp(MIN_EXPOSURE_US, MAX_EXPOSURE_US);
+        let frame = FrameParams::capture(&state, frame_type, exposure_us, 0).map_err(|e| (e, 1))?;
+        if target_adu - tolerance <= BIAS_OFFSET_ADU || target_adu + tolerance >= frame.saturation_adu() {
+            return Err(("target_adu out of range".to_string(), 1));
+        }
+
+        let auto_exp = &mut state.app_state.auto_exp;
+        auto_exp.is_working = true;
+        auto_exp.exposure_us = exposure_us;
+        auto_exp.mean_adu = 0.0;
+        auto_exp.iteration = 0;
+        emit_auto_exp_event(&state, "start", None);
+    }
+
+    let task = tokio::spawn(run_auto_exp(state.clone(), frame_type, target_adu, tolerance));
+    {
+        let mut state = state.lock().unwrap();
+        if state.app_state.auto_exp.is_working {
+            state.auto_exp_task = Some(task.abort_handle());
+        }
+    }
+
+    Ok((json!(0), 0))
+}
+
+pub fn stop_auto_exp(_: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
+    let mut state = state.lock().unwrap();
+
+    if !state.app_state.auto_exp.is_working {
+        return Ok((json!(0), 0));
+    }
+    if let Some(task) = state.auto_exp_task.take() {
+        task.abort();
+    }
+    state.app_state.auto_exp.is_working = false;
+    emit_auto_exp_event(&state, "cancel", None);
+
+    Ok((json!(0), 0))
+}
+
+pub fn get_auto_exp_state(_: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
+    let state = state.lock().unwrap();
+
+    Ok((serde_json::to_value(&state.app_state.auto_exp).unwrap(), 0))
+}
+
+// Exposes frames and measures their mean, scaling the exposure until the mean is within the tolerance
+async fn run_auto_exp(state: Arc<Mutex<ASIAirState>>, frame_type: FrameType, target_adu: f64, tolerance: f64) {
+    let clock = state.lock().unwrap().clock.clone();
+    loop {
+        let frame = {
+            let mut state = state.lock().unwrap();
+            let exposure_us = state.app_state.auto_exp.exposure_us;
+            state.capture_frame(frame_type, exposure_us)
+        };
+        let frame = match frame {
+            Ok(frame) => frame,
+            Err(error) => return finish_auto_exp(&mut state.lock().unwrap(), Some(&error)),
+        };
+        clock.sleep(Duration::from_micros(frame.exposure_us)).await;
+        let measured = frame.clone();
+        let mean_adu = tokio::task::spawn_blocking(move || frame::mean_adu(&measured.render())).await.unwrap();
+
+        let mut state = state.lock().unwrap();
+        let auto_exp = &mut state.app_state.auto_exp;
+        auto_exp.iteration += 1;
+        auto_exp.mean_adu = mean_adu;
+        let iteration = auto_exp.iteration;
+        if (mean_adu - target_adu).abs() <= tolerance {
+            return finish_auto_exp(&mut state, None);
+        }
+        emit_auto_exp_event(&state, "working", None);
+
+        let next = next_exposure(&frame, mean_adu, target_adu);
+        if next == frame.exposure_us {
+            return finish_auto_exp(&mut state, Some("target_adu out of the exposure range"));
+        }
+        if iteration >= MAX_ITERATIONS {
+            return finish_auto_exp(&mut state, Some("auto exposure did not converge"));
+        }
+        state.app_state.auto_exp.exposure_us = next;
+    }
+}
@@ -1,8 +1,11 @@
 use super::ASIAirState;
 use crate::sim::CameraState;
+use crate::sim::CaptureStatus;
+use crate::sim::FrameType;
 use crate::sim::CAMERAS_INFO;
 use crate::sim::CAMERA_CONTROL_TYPES;
 use serde_json::{json, Value};
+use std::str::FromStr;
 use std::sync::{Arc, Mutex};
 
 pub fn get_connected_cameras(_: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
@@ -29,11 +32,11 @@ pub async fn open_camera(params: &Option<Value>, state: Arc<Mutex<ASIAirState>>,
                 if !value.is_array() {
                     return Err(("params is not an array".to_string(), 1));
                 }
-                if let Some(camera_index) = value[0].as_u64() {
-                    let camera_index = camera_index as usize;
+                if let Some(camera_id) = value[0].as_u64() {
+                    // Ids stay attached to the cameras while others are plugged in and out
+                    let camera = state.connected_cameras.iter().find(|camera| camera.id as u64 == camera_id);
 
-                    if camera_index < state.connected_cameras.len() {
-                        let camera = &state.connected_cameras[camera_index];
+                    if let Some(camera) = camera {
                         state.camera_state = CameraState::Idle {
                             name: camera.name.clone(),
                             path: camera.path.clone(),
@@ -56,10 +59,10 @@ pub async fn open_camera(params: &Option<Value>, state: Arc<Mutex<ASIAirState>>,
         return Err(("Camera not found".to_string(), 1));
     }
 
-    let _ = event_tx.send(json!({
+    let event = state.lock().unwrap().stamp_event(json!({
         "Event": "CameraStateChange",
-        "Timestamp": "2025-05-06T00:00:00Z".to_string(),
-    })).await;
+    }));
+    let _ = event_tx.send(event).await;
 
     return Ok((json!(0), 0));
 }
@@ -71,10 +74,10 @@ pub async fn close_camera(_: &Option<Value>, state: Arc<Mutex<ASIAirState>>, eve
         state.camera_state = CameraState::Close;
     }
 
-    let _ = event_tx.send(json!({
+    let event = state.lock().unwrap().stamp_event(json!({
         "Event": "CameraStateChange",
-        "Timestamp": "2025-05-06T00:00:00Z".to_string(),
-    })).await;
+    }));
+    let _ = event_tx.send(event).await;
 
     Ok((json!(0), 0))
 }
@@ -83,6 +86,8 @@ pub fn get_camera_info(_: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Res
     let state = state.lock().unwrap();
 
     if let Some(camera_info) = CAMERAS_INFO.get(state.app_setting.main_camera_name.as_str()) {
+        let mut camera_info = camera_info.clone();
+        camera_info.is_usb3_host &= state.usb3_host;
         return Ok((serde_json::to_value(camera_info).unwrap(), 0));
     }
 
@@ -283,59 +288,123 @@ pub async fn start_exposure(
     state: Arc<Mutex<ASIAirState>>,
     event_tx: tokio::sync::mpsc::Sender<Value>
 ) -> Result<(Value, u8), (String, u8)> {
+    let frame_type = match params {
+        Some(value) => {
+            if !value.is_array() {
+                return Err(("params is not an array".to_string(), 1));
+            }
+            match value[0].as_str().map(FrameType::from_str) {
+                Some(Ok(frame_type)) => frame_type,
+                _ => return Err(("unexpected param".to_string(), 1)),
+            }
+        }
+        None => return Err(("params is not provided".to_string(), 1)),
+    };
+
     let exposure_us: i64;
     let gain: i64;
     let page: String;
+    let frame;
 
     {
-        let state = state.lock().unwrap();
+        let mut state = state.lock().unwrap();
+        if state.app_state.capture.is_working {
+            return Err(("exposure in progress".to_string(), 1));
+        }
+        if state.app_state.merid_flip.is_working {
+            return Err(("meridian flip in progress".to_string(), 1));
+        }
+        if state.app_state.avi_record.is_working {
+            return Err(("recording in progress".to_string(), 1));
+        }
+        if state.app_state.auto_exp.is_working {
+            return Err(("auto exposure in progress".to_string(), 1));
+        }
+        if state.app_state.find_star.is_working {
+            return Err(("find star in progress".to_string(), 1));
+        }
         exposure_us = state.camera_controls.exposure;
         gain = state.camera_controls.gain;
         page = state.app_state.page.as_str().to_string();
+        frame = state.capture_frame(frame_type, exposure_us as u64).ok();
+
+        state.frame_type = frame_type;
+        state.app_state.capture.is_working = true;
+        state.app_state.capture.state = CaptureStatus::Working;
     }
 
-    match params {
-        Some(value) => {
-            if !value.is_array() {
-                return Err(("params is not an array".to_string(), 1));
-            }
-            match value[0].as_str() {
-                Some(exposure_type) => {
-                    match exposure_type {
-                        "light" => {
-                            let _ = event_tx.send(json!({
-                                "Event": "Exposure",
-                                "Timestamp": "2025-05-06T00:00:00Z".to_string(),
-                                "page": page,
-                                "state": "start",
-                                "exp_us": exposure_us,
-                                "gain": gain,
-                            })).await;
-
-                            tokio::spawn(async move {
-                                tokio::time::sleep(std::time::Duration::from_millis((exposure_us / 1000).try_into().unwrap())).await;
-
-                                let _ = event_tx.send(json!({
-                                    "Event": "Exposure",
-                                    "Timestamp": "2025-05-06T00:00:00Z".to_string(),
-                                    "state": "downloading"
-                                })).await;
-
-                                let _ = event_tx.send(json!({
-                                    "Event": "Exposure",
-                                    "Timestamp": "2025-05-06T00:00:00Z".to_string(),
-                                    "state": "complete"
-                                })).await;
-                            });
-                        }
-                        _ => return Err(("unexpected param".to_string(), 1)),
-                    }
-                }
-                None => return Err(("unexpected param".to_string(), 1)),
+    let event = state.lock().unwrap().stamp_event(json!({
+        "Event": "Exposure",
+        "page": page,
+        "state": "start",
+        "exp_us": exposure_us,
+        "gain": gain,
+    }));
+    let _ = event_tx.send(event).await;
+
+    let task_state = state.clone();
+    let clock = state.lock().unwrap().clock.clone();
+    let task = tokio::spawn(async move {
+        clock.sleep(std::time::Duration::from_millis((exposure_us / 1000).try_into().unwrap())).await;
+
+        let event = task_state.lock().unwrap().stamp_event(json!({
+            "Event": "Exposure",
+            "state": "downloading"
+        }));
+        let _ = event_tx.send(event).await;
+
+        {
+            let mut state = task_state.lock().unwrap();
+            state.app_state.capture.is_working = false;
+            state.app_state.capture.state = CaptureStatus::Idle;
+            state.exposure_task = None;
+            if let Some(frame) = frame {
+                state.complete_frame(frame);
             }
         }
-        None => return Err(("params is not provided".to_string(), 1)),
+
+        let event = task_state.lock().unwrap().stamp_event(json!({
+            "Event": "Exposure",
+            "state": "complete"
+        }));
+        let _ = event_tx.send(event).await;
+    });
+
+    // The exposure may already be over for very short exposures, only keep
+    // the handle while the capture is still in progress
+    {
+        let mut state = state.lock().unwrap();
+        if state.app_state.capture.is_working {
+            state.exposure_task = Some(task.abort_handle());
+        }
+    }
+
+    Ok((json!(0), 0))
+}
+
+pub async fn stop_exposure(
+    _: &Option<Value>,
+    state: Arc<Mutex<ASIAirState>>,
+    event_tx: tokio::sync::mpsc::Sender<Value>
+) -> Result<(Value, u8), (String, u8)> {
+    // Need this pattern to avoid sending the MutexGuard across the async call
+    {
+        let mut state = state.lock().unwrap();
+        if !state.app_state.capture.is_working {
+            return Ok((json!(0), 0));
+        }
+        if let Some(task) = state.exposure_task.take() {
+            task.abort();
+        }
+        state.app_state.capture.is_working = false;
+        state.app_state.capture.state = CaptureStatus::Idle;
     }
 
+    let event = state.lock().unwrap().stamp_event(json!({
+        "Event": "Exposure",
+        "state": "cancel"
+    }));
+    let _ = event_tx.send(event).await;
+
     Ok((json!(0), 0))
-}
\ No newline at end of file
+}
@@ -0,0 +1,166 @@
+use super::ASIAirState;
+use crate::catalog;
+use crate::mount::MOUNT_TICK;
+use crate::sim::FrameType;
+use crate::stack;
+use serde_json::{json, Value};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::Instant;
+
+// Time the device takes to find the stars of a frame
+const FIND_STAR_LATENCY: Duration = Duration::from_millis(200);
+// Radius in pixels around the star within which its flux is measured
+const HFR_RADIUS: f64 = 16.0;
+
+fn emit_find_star_event(state: &ASIAirState, find_star_state: &str, error: Option<&str>) {
+    let mut event = json!({
+        "Event": "FindStar",
+        "state": find_star_state,
+        "lapse_ms": state.app_state.find_star.lapse_ms,
+    });
+    if let Some(Ok(star)) = &state.find_star_result {
+        event["star"] = star.clone();
+    }
+    if let Some(error) = error {
+        event["error"] = json!(error);
+    }
+    state.emit_event(event);
+}
+
+fn finish_find_star(state: &mut ASIAirState, result: Result<Value, String>, start: Instant) {
+    state.find_star_task = None;
+    state.app_state.find_star.is_working = false;
+    state.app_state.find_star.lapse_ms = (state.clock.now() - start).as_millis() as u32;
+    let error = result.as_ref().err().cloned();
+    state.find_star_result = Some(result.map(|mut star| {
+        star["lapse_ms"] = json!(state.app_state.find_star.lapse_ms);
+        star
+    }));
+    match error {
+        Some(error) => emit_find_star_event(state, "fail", Some(&error)),
+        None => emit_find_star_event(state, "complete", None),
+    }
+}
+
+// Exposes a frame with the main camera and finds its brightest star. With `center`, the mount
+// is then moved so the star ends up at the center of the frame
+pub fn start_find_star(params: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
+    let center = params.as_ref().and_then(|p| p[0]["center"].as_bool()).unwrap_or(false);
+
+    {
+        let mut state = state.lock().unwrap();
+        if state.app_state.find_star.is_working {
+            return Err(("find star in progress".to_string(), 1));
+        }
+        if state.app_state.capture.is_working || state.app_state.plan.is_working {
+            return Err(("exposure in progress".to_string(), 1));
+        }
+        if state.app_state.avi_record.is_working {
+            return Err(("recording in progress".to_string(), 1));
+        }
+        crate::solver::image_geometry(&state).map_err(|e| (e, 1))?;
+
+        state.app_state.find_star.is_working = true;
+        state.app_state.find_star.lapse_ms = 0;
+        state.find_star_result = None;
+        emit_find_star_event(&state, "start", None);
+    }
+
+    let task = tokio::spawn(run_find_star(state.clone(), center));
+    {
+        let mut state = state.lock().unwrap();
+        if state.app_state.find_star.is_working {
+            state.find_star_task = Some(task.abort_handle());
+        }
+    }
+
+    Ok((json!(0), 0))
+}
+
+pub fn stop_find_star(_: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
+    let mut state = state.lock().unwrap();
+
+    if !state.app_state.find_star.is_working {
+        return Ok((json!(0), 0));
+    }
+    if let Some(task) = state.find_star_task.take() {
+        task.abort();
+    }
+    state.mount.abort();
+    state.app_state.find_star.is_working = false;
+    state.find_star_result = Some(Err("cancel".to_string()));
+    emit_find_star_event(&state, "cancel", None);
+
+    Ok((json!(0), 0))
+}
+
+pub fn get_find_star_result(_: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
+    let state = state.lock().unwrap();
+
+    match &state.find_star_result {
+        Some(Ok(star)) => Ok((star.clone(), 0)),
+        Some(Err(error)) => Err((error.clone(), 1)),
+        None => Err(("no find star result".to_string(), 1)),
+    }
+}
+
+async fn run_find_star(state: Arc<Mutex<ASIAirState>>, center: bool) {
+    let clock = state.lock().unwrap().clock.clone();
+    let start = clock.now();
+    let frame = {
+        let mut state = state.lock().unwrap();
+        let exposure_us = state.camera_controls.exposure.max(1) as u64;
+        state.capture_frame(FrameType::Light, exposure_us)
+    };
+    let frame = match frame {
+        Ok(frame) => frame,
+        Err(error) => return finish_find_star(&mut state.lock().unwrap(), Err(error), start),
+    };
+    clock.sleep(Duration::from_micros(frame.exposure_us) + FIND_STAR_LATENCY).await;
+
+    let measured = frame.clone();
+    let brightest = tokio::task::spawn_blocking(move || {
+        // The peaks of bright stars saturate, so they are ranked by their flux
+        let raw = measured.render();
+        stack::detect_stars(&raw, measured.width, measured.height)
+            .into_iter()
+            .map(|star| (star, stack::measure_star(&raw, measured.width, measured.height, &star, HFR_RADIUS)))
+            .max_by(|(_, (a, _)), (_, (b, _))| a.total_cmp(b))
+            .map(|(star, (_, hfr))| (star, hfr))
+    })
+    .await
+    .unwrap();
+    let Some((star, hfr)) = brightest else {
+        return finish_find_star(&mut state.lock().unwrap(), Err("no star found".to_string()), start);
+    };
+
+    let (ra, dec) = catalog::unproject(frame.ra, frame.dec, frame.angle, frame.scale, [frame.width, frame.height], (star.x, star.y));
+    let result = json!({
+        "x": star.x,
+        "y": star.y,
+        "hfr": hfr,
+        "peak": star.peak,
+        "ra": ra,
+        "dec": dec,
+        "centered": center,
+    });
+
+    {
+        let mut state = state.lock().unwrap();
+        state.last_frame = Some(frame);
+        if !center {
+            return finish_find_star(&mut state, Ok(result), start);
+        }
+        let now = state.rtc.now();
+        if let Err(error) = state.mount.goto(ra, dec, now) {
+            return finish_find_star(&mut state, Err(error), start);
+        }
+        emit_find_star_event(&state, "center", None);
+    }
+
+    while state.lock().unwrap().mount.is_slewing() {
+        clock.sleep(MOUNT_TICK).await;
+    }
+    finish_find_star(&mut state.lock().unwrap(), Ok(result), start);
+}
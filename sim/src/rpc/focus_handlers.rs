@@ -0,0 +1,324 @@
+use super::ASIAirState;
+use crate::focuser::FOCUSER_TICK;
+use crate::sim::{AutoFocusPoint, AutoFocusResult, AutoFocuserReason, CameraState, FrameType};
+use crate::solver::{gaussian, SolveFailure};
+use serde_json::{json, Value};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+// Default distance between the points of the V-curve, in steps, and number of points
+const AUTO_FOCUS_STEP: i32 = 100;
+const AUTO_FOCUS_POINTS: u32 = 9;
+// Most points a V-curve can be run with
+const MAX_AUTO_FOCUS_POINTS: u32 = 99;
+// Random error of the HFR measured on each frame, in pixels
+const HFR_NOISE: f64 = 0.02;
+
+fn emit_auto_focus_event(state: &ASIAirState, mut event: Value) {
+    event["Event"] = json!("AutoFocus");
+    state.emit_event(event);
+}
+
+fn opened_focuser(state: &ASIAirState) -> Result<(), (String, u8)> {
+    if !state.app_state.auto_focus.focuser_opened {
+        return Err(("focuser is not open".to_string(), 1));
+    }
+    Ok(())
+}
+
+pub fn open_focuser(_: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
+    let mut state = state.lock().unwrap();
+
+    state.app_state.auto_focus.focuser_opened = true;
+
+    Ok((json!(0), 0))
+}
+
+pub fn close_focuser(_: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
+    let mut state = state.lock().unwrap();
+
+    if state.app_state.auto_focus.is_working {
+        return Err(("auto focus in progress".to_string(), 1));
+    }
+    state.focuser.stop();
+    state.app_state.auto_focus.focuser_opened = false;
+
+    Ok((json!(0), 0))
+}
+
+pub fn get_focuser_state(_: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
+    let state = state.lock().unwrap();
+
+    opened_focuser(&state)?;
+
+    Ok((state.focuser.state(), 0))
+}
+
+pub fn move_focuser(params: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
+    let mut state = state.lock().unwrap();
+
+    opened_focuser(&state)?;
+    if state.app_state.auto_focus.is_working {
+        return Err(("auto focus in progress".to_string(), 1));
+    }
+    let position = match params {
+        Some(value) => value[0].as_i64().ok_or(("invalid position".to_string(), 1))?,
+        None => return Err(("params is not provided".to_string(), 1)),
+    };
+    state.focuser.move_to(position as i32).map_err(|e| (e, 1))?;
+    state.emit_event(json!({
+        "Event": "Focuser",
+        "state": "moving",
+        "position": state.focuser.position,
+    }));
+
+    Ok((json!(0), 0))
+}
+
+pub fn stop_focuser(_: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
+    let mut state = state.lock().unwrap();
+
+    opened_focuser(&state)?;
+    if state.focuser.is_moving() {
+        state.focuser.stop();
+        state.emit_event(json!({
+            "Event": "Focuser",
+            "state": "idle",
+            "position": state.focuser.position,
+        }));
+    }
+
+    Ok((json!(0), 0))
+}
+
+// Runs a V-curve around the current focuser position, optional params are the step between
+// points and the number of points
+pub fn start_auto_focus(params: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
+    let invalid = || ("invalid auto focus params".to_string(), 1);
+    let step = match params.as_ref().and_then(|p| p[0].as_i64()) {
+        Some(s) => i32::try_from(s).map_err(|_| invalid())?,
+        None => AUTO_FOCUS_STEP,
+    };
+    let points = match params.as_ref().and_then(|p| p[1].as_u64()) {
+        Some(p) => u32::try_from(p).map_err(|_| invalid())?,
+        None => AUTO_FOCUS_POINTS,
+    };
+    if step <= 0 || !(3..=MAX_AUTO_FOCUS_POINTS).contains(&points) {
+        return Err(invalid());
+    }
+    // Distance from the first to the last point, and from the start position to the first point
+    let span = (points as i32 - 1).checked_mul(step).ok_or_else(invalid)?;
+    let below = ((points as i32 - 1) / 2).checked_mul(step).ok_or_else(invalid)?;
+
+    {
+        let mut state = state.lock().unwrap();
+        opened_focuser(&state)?;
+        if state.app_state.auto_focus.is_working {
+            return Err(("auto focus in progress".to_string(), 1));
+        }
+        if let CameraState::Close = state.camera_state {
+            return Err(("camera is not open".to_string(), 1));
+        }
+
+        // The curve is run outward only, starting one step below the first point to take up the backlash
+        let start = state.focuser.position;
+        let out_of_range = || ("auto focus range out of the focuser range".to_string(), 1);
+        let first = start.checked_sub(below).ok_or_else(out_of_range)?;
+        let last = first.checked_add(span).ok_or_else(out_of_range)?;
+        if first < step || last > state.focuser.max_step {
+            return Err(out_of_range());
+        }
+
+        state.app_state.auto_focus.is_working = true;
+        state.app_state.auto_focus.result = AutoFocusResult::default();
+        emit_auto_focus_event(&state, json!({ "state": "start", "position": start }));
+    }
+
+    let task = tokio::spawn(run_auto_focus(state.clone(), step, points));
+    {
+        let mut state = state.lock().unwrap();
+        if state.app_state.auto_focus.is_working {
+            state.auto_focus_task = Some(task.abort_handle());
+        }
+    }
+
+    Ok((json!(0), 0))
+}
+
+pub fn stop_auto_focus(_: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
+    let mut state = state.lock().unwrap();
+
+    if !state.app_state.auto_focus.is_working {
+        return Ok((json!(0), 0));
+    }
+    if let Some(task) = state.auto_focus_task.take() {
+        task.abort();
+    }
+    state.focuser.stop();
+    state.app_state.auto_focus.is_working = false;
+    state.app_state.auto_focus.reason = AutoFocuserReason {
+        comment: "cancel".to_string(),
+        code: 2,
+    };
+    let position = state.focuser.position;
+    emit_auto_focus_event(&state, json!({ "state": "cancel", "position": position }));
+
+    Ok((json!(0), 0))
+}
+
+async fn move_focuser_to(state: &Arc<Mutex<ASIAirState>>, position: i32) {
+    let clock = state.lock().unwrap().clock.clone();
+    let _ = state.lock().unwrap().focuser.move_to(position);
+    while state.lock().unwrap().focuser.is_moving() {
+        clock.sleep(FOCUSER_TICK).await;
+    }
+}
+
+// Moves to a position from below, so the backlash is taken up the same way as during the curve
+async fn approach(state: &Arc<Mutex<ASIAirState>>, position: i32, step: i32) {
+    move_focuser_to(state, (position - step).max(0)).await;
+    move_focuser_to(state, position).await;
+}
+
+// Least squares fit of hfr² = a.x² + b.x + c, returns the position and HFR at the bottom of the curve
+fn fit_v_curve(points: &[AutoFocusPoint]) -> Option<(f64, f64)> {
+    // Positions are centered on the curve to keep the normal equations well conditioned
+    let center = points.iter().map(|p| p.position as f64).sum::<f64>() / points.len() as f64;
+    let mut sums = [0.0f64; 7];
+    for point in points {
+        let x = point.position as f64 - center;
+        let y = point.hfr * point.hfr;
+        for (power, sum) in sums.iter_mut().take(5).enumerate() {
+            *sum += x.powi(power as i32);
+        }
+        sums[5] += y;
+        sums[6] += x * y;
+    }
+    let x2y: f64 = points.iter().map(|p| (p.position as f64 - center).powi(2) * p.hfr * p.hfr).sum();
+
+    // Solve the 3x3 normal equations with Cramer's rule
+    let m = [[sums[4], sums[3], sums[2]], [sums[3], sums[2], sums[1]], [sums[2], sums[1], sums[0]]];
+    let v = [x2y, sums[6], sums[5]];
+    let det = |m: [[f64; 3]; 3]| {
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1]) - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    };
+    let d = det(m);
+    if d.abs() < f64::EPSILON {
+        return None;
+    }
+    let mut coefficients = [0.0; 3];
+    for (i, coefficient) in coefficients.iter_mut().enumerate() {
+        let mut mi = m;
+        for row in 0..3 {
+            mi[row][i] = v[row];
+        }
+        *coefficient = det(mi) / d;
+    }
+    let [a, b, c] = coefficients;
+    if a <= 0.0 {
+        return None;
+    }
+
+    let best = -b / (2.0 * a);
+    let hfr = (c - b * b / (4.0 * a)).max(0.0).sqrt();
+    Some((center + best, hfr))
+}
+
+async fn run_auto_focus(state: Arc<Mutex<ASIAirState>>, step: i32, points: u32) {
+    let clock = state.lock().unwrap().clock.clone();
+    let start = state.lock().unwrap().focuser.position;
+    let first = start - (points as i32 - 1) / 2 * step;
+
+    let mut curve = Vec::new();
+    let mut failure = None;
+    move_focuser_to(&state, first - step).await;
+    for index in 0..points {
+        let position = first + index as i32 * step;
+        move_focuser_to(&state, position).await;
+
+        let (exposure, frame) = {
+            let mut state = state.lock().unwrap();
+            let exposure = state.camera_controls.exposure.max(0) as u64;
+            (exposure, state.capture_frame(FrameType::Light, exposure).ok())
+        };
+        clock.sleep(Duration::from_micros(exposure)).await;
+
+        let mut state = state.lock().unwrap();
+        if state.solver.failure == Some(SolveFailure::NoStars) {
+            failure = Some("no stars");
+            break;
+        }
+        let bin = state.camera_bin.max(1) as f64;
+        let hfr = (state.focuser.hfr() / bin + gaussian(HFR_NOISE)).max(0.1);
+        if frame.is_some() {
+            state.last_frame = frame;
+        }
+        emit_auto_focus_event(&state, json!({
+            "state": "working",
+            "position": position,
+            "hfr": hfr,
+            "index": index,
+            "points": points,
+        }));
+        curve.push(AutoFocusPoint { position, hfr });
+    }
+
+    let last = first + (points as i32 - 1) * step;
+    let best = match failure {
+        Some(_) => None,
+        None => fit_v_curve(&curve).filter(|(best, _)| (first as f64..=last as f64).contains(best)),
+    };
+    if best.is_none() && failure.is_none() {
+        failure = Some("no V-curve");
+    }
+
+    match best {
+        Some((best_position, best_hfr)) => {
+            let best_position = best_position.round() as i32;
+            approach(&state, best_position, step).await;
+
+            let mut state = state.lock().unwrap();
+            state.app_state.auto_focus.is_working = false;
+            state.app_state.auto_focus.result = AutoFocusResult {
+                points: curve,
+                best_position,
+                best_hfr,
+            };
+            state.app_state.auto_focus.reason = AutoFocuserReason {
+                comment: "success".to_string(),
+                code: 0,
+            };
+            state.auto_focus_task = None;
+            let result = serde_json::to_value(&state.app_state.auto_focus.result).unwrap();
+            emit_auto_focus_event(&state, json!({
+                "state": "complete",
+                "position": best_position,
+                "hfr": best_hfr,
+                "result": result,
+            }));
+        }
+        None => {
+            // Go back to where the focuser was before the curve
+            approach(&state, start, step).await;
+
+            let error = failure.unwrap_or_default();
+            let mut state = state.lock().unwrap();
+            state.app_state.auto_focus.is_working = false;
+            state.app_state.auto_focus.result = AutoFocusResult {
+                points: curve,
+                ..Default::default()
+            };
+            state.app_state.auto_focus.reason = AutoFocuserReason {
+                comment: error.to_string(),
+                code: 1,
+            };
+            state.auto_focus_task = None;
+            emit_auto_focus_event(&state, json!({
+                "state": "fail",
+                "position": start,
+                "error": error,
+            }));
+        }
+    }
+}
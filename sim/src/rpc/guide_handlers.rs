@@ -0,0 +1,91 @@
+use super::ASIAirState;
+use serde_json::{json, Value};
+use std::sync::{Arc, Mutex};
+
+pub fn start_guide(_: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
+    {
+        let mut state = state.lock().unwrap();
+        let ASIAirState { guider, mount, .. } = &mut *state;
+        guider.start(mount).map_err(|e| (e, 1))?;
+        state.emit_event(json!({
+            "Event": "Guide",
+            "state": "start",
+            "frame": 0,
+        }));
+    }
+
+    let task = tokio::spawn(run_guide(state.clone()));
+    {
+        let mut state = state.lock().unwrap();
+        if state.guider.is_guiding() {
+            state.guide_task = Some(task.abort_handle());
+        }
+    }
+
+    Ok((json!(0), 0))
+}
+
+pub fn stop_guide(_: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
+    let mut state = state.lock().unwrap();
+
+    if !state.guider.is_guiding() {
+        return Ok((json!(0), 0));
+    }
+    if let Some(task) = state.guide_task.take() {
+        task.abort();
+    }
+    state.guider.stop();
+    state.emit_event(json!({
+        "Event": "Guide",
+        "state": "stop",
+        "frame": 0,
+    }));
+
+    Ok((json!(0), 0))
+}
+
+pub fn get_guide_state(_: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
+    let state = state.lock().unwrap();
+
+    Ok((state.guider.state(), 0))
+}
+
+// Moves the guiding lock position, guiding reports when the star settled on it.
+// Nothing has to settle when not guiding, so the request is accepted without effect
+pub fn dither(params: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
+    let (pixels, ra_only) = match params {
+        Some(value) => {
+            if !value.is_array() {
+                return Err(("params is not an array".to_string(), 1));
+            }
+            let Some(pixels) = value[0].as_f64() else {
+                return Err(("invalid dither amount".to_string(), 1));
+            };
+            (pixels, value[1].as_bool().unwrap_or(false))
+        }
+        None => return Err(("params is not provided".to_string(), 1)),
+    };
+
+    let mut state = state.lock().unwrap();
+    if state.guider.is_guiding() {
+        state.guider.dither(pixels, ra_only);
+    }
+
+    Ok((json!(0), 0))
+}
+
+// Takes guide frames and sends the corrections, until stopped
+async fn run_guide(state: Arc<Mutex<ASIAirState>>) {
+    let clock = state.lock().unwrap().clock.clone();
+    loop {
+        let exposure = state.lock().unwrap().guider.config.exposure;
+        clock.sleep(exposure).await;
+
+        let mut state = state.lock().unwrap();
+        let guide_rate = state.app_setting.guide_rate as f64;
+        let ASIAirState { guider, mount, .. } = &mut *state;
+        for event in guider.step(mount, guide_rate) {
+            state.emit_event(event);
+        }
+    }
+}
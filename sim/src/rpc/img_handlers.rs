@@ -1,13 +1,48 @@
 use super::sample_raw::RAW_IMAGE_ZIP;
 use super::ASIAirState;
+use crate::frame::zip_frame;
 use crate::sim::BinaryResult;
 use serde_json::Value;
 use std::sync::{Arc, Mutex};
 
-pub fn get_current_img(_params: &Option<Value>, _state: Arc<Mutex<ASIAirState>>) -> BinaryResult {
+// Renders the last image of the main camera, or sends the sample image when nothing was exposed yet
+pub fn get_current_img(_params: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> BinaryResult {
+    let frame = state.lock().unwrap().last_frame.clone();
+    if let Some(frame) = frame {
+        return BinaryResult {
+            data: zip_frame(&frame.render()),
+            width: frame.width as u16,
+            height: frame.height as u16,
+        };
+    }
+
     BinaryResult {
         data: RAW_IMAGE_ZIP.zip_data.to_vec(),
         width: RAW_IMAGE_ZIP.width,
         height: RAW_IMAGE_ZIP.height,
     }
 }
+
+// Sends the average of the live stack
+pub fn get_stacked_img(_params: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<BinaryResult, Box<dyn std::error::Error + Send + Sync>> {
+    let stacker = state.lock().unwrap().stacker.clone();
+    let (raw, width, height) = stacker.lock().unwrap().image().ok_or("nothing stacked")?;
+
+    Ok(BinaryResult {
+        data: zip_frame(&raw),
+        width: width as u16,
+        height: height as u16,
+    })
+}
+
+// Renders an image saved on the storage
+pub fn get_img_file(params: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<BinaryResult, Box<dyn std::error::Error + Send + Sync>> {
+    let path = params.as_ref().and_then(|value| value[0].as_str()).ok_or("invalid path")?;
+    let frame = state.lock().unwrap().storage.internal().get(path).ok_or("file not found")?.frame.clone();
+
+    Ok(BinaryResult {
+        data: zip_frame(&frame.render()),
+        width: frame.width as u16,
+        height: frame.height as u16,
+    })
+}
@@ -0,0 +1,178 @@
+use super::{guide_handlers, ASIAirState};
+use crate::mount::{PierSide, MOUNT_TICK};
+use crate::sim::CaptureStatus;
+use serde_json::{json, Value};
+use std::sync::{Arc, Mutex};
+use tokio::sync::watch;
+
+fn emit_merid_flip_event(state: &ASIAirState, flip_state: &str, error: Option<&str>) {
+    let mut event = json!({
+        "Event": "MeridianFlip",
+        "state": flip_state,
+    });
+    if let Some(error) = error {
+        event["error"] = json!(error);
+    }
+    state.emit_event(event);
+}
+
+// Ends the flip on a failure, the mount is left tracking where it is
+fn fail(state: &Arc<Mutex<ASIAirState>>, error: &str) {
+    let mut state = state.lock().unwrap();
+    state.app_state.merid_flip.is_working = false;
+    state.merid_flip_task = None;
+    emit_merid_flip_event(&state, "fail", Some(error));
+}
+
+// True once the mount tracks a target far enough past the meridian to need a flip
+fn flip_due(state: &ASIAirState) -> bool {
+    let mount = &state.mount;
+    if !mount.tracking || mount.is_slewing() || mount.pier_side != PierSide::West {
+        return false;
+    }
+    let hour_angle = mount.hour_angle(mount.ra, state.rtc.now());
+    hour_angle >= 0.0 && hour_angle * 60.0 >= state.app_setting.merid_flip_minutes
+}
+
+// Starts the flip routine, the state lock must not be held by the caller
+fn spawn_merid_flip(state: &Arc<Mutex<ASIAirState>>) {
+    let task = tokio::spawn(run_merid_flip(state.clone()));
+    let mut state = state.lock().unwrap();
+    if state.app_state.merid_flip.is_working {
+        state.merid_flip_task = Some(task.abort_handle());
+    }
+}
+
+/// Watches the mount and flips it automatically once it crossed the meridian, when enabled
+pub async fn watch_meridian(state: Arc<Mutex<ASIAirState>>, mut shutdown_rx: watch::Receiver<()>) {
+    let clock = state.lock().unwrap().clock.clone();
+    let mut interval = clock.interval(MOUNT_TICK);
+    loop {
+        tokio::select! {
+            _ = shutdown_rx.changed() => {
+                break;
+            }
+            _ = interval.tick() => {
+                let start = {
+                    let mut state = state.lock().unwrap();
+                    let start = state.app_setting.auto_merid_flip
+                        && !state.app_state.merid_flip.is_working
+                        && flip_due(&state);
+                    if start {
+                        state.app_state.merid_flip.is_working = true;
+                        emit_merid_flip_event(&state, "start", None);
+                    }
+                    start
+                };
+                if start {
+                    spawn_merid_flip(&state);
+                }
+            }
+        }
+    }
+}
+
+// Flips the mount now, the target has to be past the meridian
+pub fn start_merid_flip(_: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
+    {
+        let mut state = state.lock().unwrap();
+        if state.app_state.merid_flip.is_working {
+            return Err(("meridian flip in progress".to_string(), 1));
+        }
+        let mount = &state.mount;
+        if !mount.tracking || mount.pier_side != PierSide::West || mount.hour_angle(mount.ra, state.rtc.now()) < 0.0 {
+            return Err(("mount is not past the meridian".to_string(), 1));
+        }
+        state.app_state.merid_flip.is_working = true;
+        emit_merid_flip_event(&state, "start", None);
+    }
+
+    spawn_merid_flip(&state);
+
+    Ok((json!(0), 0))
+}
+
+pub fn stop_merid_flip(_: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
+    let mut state = state.lock().unwrap();
+
+    if !state.app_state.merid_flip.is_working {
+        return Ok((json!(0), 0));
+    }
+    if let Some(task) = state.merid_flip_task.take() {
+        task.abort();
+    }
+    state.mount.abort();
+    state.app_state.merid_flip.is_working = false;
+    emit_merid_flip_event(&state, "cancel", None);
+
+    Ok((json!(0), 0))
+}
+
+// Stops the capture, slews the mount to the other side of the pier, solves and centers the
+// target again, then restarts guiding so the capture can resume
+async fn run_merid_flip(state: Arc<Mutex<ASIAirState>>) {
+    let clock = state.lock().unwrap().clock.clone();
+    let was_guiding = {
+        let mut state = state.lock().unwrap();
+        if state.app_state.capture.is_working {
+            if let Some(task) = state.exposure_task.take() {
+                task.abort();
+                state.app_state.capture.is_working = false;
+                state.app_state.capture.state = CaptureStatus::Idle;
+                state.emit_event(json!({
+                    "Event": "Exposure",
+                    "state": "cancel"
+                }));
+            }
+        }
+        state.guider.is_guiding()
+    };
+    if was_guiding {
+        let _ = guide_handlers::stop_guide(&None, state.clone());
+    }
+
+    let flip = {
+        let mut state = state.lock().unwrap();
+        let now = state.rtc.now();
+        let (ra, dec) = (state.mount.ra, state.mount.dec);
+        let flip = state.mount.goto(ra, dec, now);
+        if flip.is_ok() {
+            emit_merid_flip_event(&state, "flip", None);
+        }
+        flip
+    };
+    if let Err(error) = flip {
+        fail(&state, &error);
+        return;
+    }
+    while state.lock().unwrap().mount.is_slewing() {
+        clock.sleep(MOUNT_TICK).await;
+    }
+
+    // The pointing after the flip is checked with a plate solve, then corrected
+    for step in ["solve", "center"] {
+        let (latency, failure) = {
+            let state = state.lock().unwrap();
+            emit_merid_flip_event(&state, step, None);
+            (state.solver.latency, state.solver.failure)
+        };
+        clock.sleep(latency).await;
+        if let Some(failure) = failure {
+            fail(&state, failure.as_str());
+            return;
+        }
+    }
+
+    if was_guiding {
+        emit_merid_flip_event(&state.lock().unwrap(), "guide", None);
+        if let Err((error, _)) = guide_handlers::start_guide(&None, state.clone()) {
+            fail(&state, &error);
+            return;
+        }
+    }
+
+    let mut state = state.lock().unwrap();
+    state.app_state.merid_flip.is_working = false;
+    state.merid_flip_task = None;
+    emit_merid_flip_event(&state, "complete", None);
+}
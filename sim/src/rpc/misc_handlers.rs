@@ -1,4 +1,5 @@
 use super::ASIAirState;
+use chrono::{Datelike, Timelike};
 use serde_json::{json, Value};
 use std::sync::{Arc, Mutex};
 
@@ -68,29 +69,42 @@ pub fn pi_set_time(params: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Re
     }
 }
 
+// Reads the clock of the device, in the fields of pi_set_time plus the milliseconds and the
+// offset from UTC in seconds
+pub fn pi_get_time(_params: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
+    let now = state.lock().unwrap().rtc.now();
+    Ok((
+        json!({
+            "year": now.year(),
+            "mon": now.month(),
+            "day": now.day(),
+            "hour": now.hour(),
+            "min": now.minute(),
+            "sec": now.second(),
+            "msec": now.timestamp_subsec_millis(),
+            "utc_offset": now.offset().local_minus_utc(),
+        }),
+        0,
+    ))
+}
+
 pub fn set_setting(params: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
     let mut state = state.lock().unwrap();
 
-    // Check if params is None or not
-    if params.is_none() {
-        return Err(("params is None".to_string(), 1));
-    }
-
-    let params = serde_json::from_str::<Value>(params.as_ref().unwrap().as_str().unwrap_or("{}"))
-        .unwrap_or(json!({}));
-    let lang = params["lang"].as_str().unwrap_or("en");
+    // The settings come as an object, older clients send the object as a JSON string
+    let params = match params {
+        Some(Value::String(text)) => serde_json::from_str::<Value>(text).map_err(|e| (e.to_string(), 1))?,
+        Some(params) => params.clone(),
+        None => return Err(("params is None".to_string(), 1)),
+    };
+    let changes = params.as_object().ok_or(("params is not an object".to_string(), 1))?;
 
-    state.language = lang.to_string();
+    state.settings.update(changes).map_err(|e| (e, 1))?;
 
     Ok((json!(0), 0))
 }
 
 pub fn get_setting(_params: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
     let state = state.lock().unwrap();
-    Ok((
-        json!({
-            "lang": state.language,
-        }),
-        0,
-    ))
+    Ok((json!(state.settings), 0))
 }
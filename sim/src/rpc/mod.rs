@@ -1,16 +1,32 @@
 use serde_json::{json, Value};
 use std::sync::{Arc, Mutex};
 
+mod annotate_handlers;
 mod app_handlers;
+mod auto_exp_handlers;
+mod focus_handlers;
 mod img_handlers;
 mod misc_handlers;
+mod plan_handlers;
 mod camera_handlers;
+mod find_star_handlers;
+mod guide_handlers;
+mod merid_handlers;
+mod mount_handlers;
+mod pa_handlers;
+mod record_handlers;
+mod rtmp_handlers;
+mod solve_handlers;
+mod stack_handlers;
+mod storage_handlers;
 pub mod protocol;
 mod sample_raw;
 
 use super::ASIAirState;
 use crate::sim::BinaryResult;
 
+pub use merid_handlers::watch_meridian;
+
 pub fn asiair_udp_handler(
     method: &str,
     params: &Option<Value>,
@@ -31,8 +47,10 @@ pub async fn asiair_tcp_handler(
     match method {
         "test_connection" => misc_handlers::test_connection(params, state),
         "pi_set_time" => misc_handlers::pi_set_time(params, state),
+        "pi_get_time" => misc_handlers::pi_get_time(params, state),
         "set_setting" => misc_handlers::set_setting(params, state),
         "get_setting" => misc_handlers::get_setting(params, state),
+        "set_page" => app_handlers::set_page(params, state),
         "get_app_state" => app_handlers::get_app_state(params, state),
         "get_app_setting" => app_handlers::get_app_setting(params, state),
         "set_app_setting" => app_handlers::set_app_setting(params, state),
@@ -46,6 +64,70 @@ pub async fn asiair_tcp_handler(
         "get_camera_bin" => camera_handlers::get_camera_bin(params, state),
         "set_camera_bin" => camera_handlers::set_camera_bin(params, state),
         "start_exposure" => camera_handlers::start_exposure(params, state, event_tx).await,
+        "stop_exposure" => camera_handlers::stop_exposure(params, state, event_tx).await,
+        "dither" => guide_handlers::dither(params, state),
+        "start_guide" => guide_handlers::start_guide(params, state),
+        "stop_guide" => guide_handlers::stop_guide(params, state),
+        "get_guide_state" => guide_handlers::get_guide_state(params, state),
+        "start_merid_flip" => merid_handlers::start_merid_flip(params, state),
+        "stop_merid_flip" => merid_handlers::stop_merid_flip(params, state),
+        "start_stack" => stack_handlers::start_stack(params, state),
+        "stop_stack" => stack_handlers::stop_stack(params, state),
+        "reset_stack" => stack_handlers::reset_stack(params, state),
+        "get_stack_state" => stack_handlers::get_stack_state(params, state),
+        "get_img_file_list" => storage_handlers::get_img_file_list(params, state),
+        "delete_img_file" => storage_handlers::delete_img_file(params, state),
+        "get_storage_info" => storage_handlers::get_storage_info(params, state),
+        "format_drive" => storage_handlers::format_drive(params, state),
+        "start_export_image" => storage_handlers::start_export_image(params, state),
+        "stop_export_image" => storage_handlers::stop_export_image(params, state),
+        "get_export_image_state" => storage_handlers::get_export_image_state(params, state),
+        "start_find_star" => find_star_handlers::start_find_star(params, state),
+        "stop_find_star" => find_star_handlers::stop_find_star(params, state),
+        "get_find_star_result" => find_star_handlers::get_find_star_result(params, state),
+        "start_auto_exp" => auto_exp_handlers::start_auto_exp(params, state),
+        "stop_auto_exp" => auto_exp_handlers::stop_auto_exp(params, state),
+        "get_auto_exp_state" => auto_exp_handlers::get_auto_exp_state(params, state),
+        "start_avi_record" => record_handlers::start_avi_record(params, state),
+        "stop_avi_record" => record_handlers::stop_avi_record(params, state),
+        "get_avi_record_state" => record_handlers::get_avi_record_state(params, state),
+        "set_rtmp_config" => rtmp_handlers::set_rtmp_config(params, state),
+        "get_rtmp_config" => rtmp_handlers::get_rtmp_config(params, state),
+        "start_rtmp" => rtmp_handlers::start_rtmp(params, state),
+        "stop_rtmp" => rtmp_handlers::stop_rtmp(params, state),
+        "get_rtmp_state" => rtmp_handlers::get_rtmp_state(params, state),
+        "set_plan" => plan_handlers::set_plan(params, state),
+        "get_plan_list" => plan_handlers::get_plan_list(params, state),
+        "delete_plan" => plan_handlers::delete_plan(params, state),
+        "start_plan" => plan_handlers::start_plan(params, state),
+        "stop_plan" => plan_handlers::stop_plan(params, state),
+        "scope_goto" => mount_handlers::scope_goto(params, state),
+        "scope_abort_slew" => mount_handlers::scope_abort_slew(params, state),
+        "scope_park" => mount_handlers::scope_park(params, state),
+        "scope_unpark" => mount_handlers::scope_unpark(params, state),
+        "scope_get_equ_coord" => mount_handlers::scope_get_equ_coord(params, state),
+        "scope_get_state" => mount_handlers::scope_get_state(params, state),
+        "scope_get_track_state" => mount_handlers::scope_get_track_state(params, state),
+        "scope_set_track_state" => mount_handlers::scope_set_track_state(params, state),
+        "scope_set_track_mode" => mount_handlers::scope_set_track_mode(params, state),
+        "scope_set_slew_rate" => mount_handlers::scope_set_slew_rate(params, state),
+        "scope_set_location" => mount_handlers::scope_set_location(params, state),
+        "scope_set_limits" => mount_handlers::scope_set_limits(params, state),
+        "start_solve" => solve_handlers::start_solve(params, state),
+        "stop_solve" => solve_handlers::stop_solve(params, state),
+        "get_solve_result" => solve_handlers::get_solve_result(params, state),
+        "start_annotate" => annotate_handlers::start_annotate(params, state),
+        "stop_annotate" => annotate_handlers::stop_annotate(params, state),
+        "get_annotate_result" => annotate_handlers::get_annotate_result(params, state),
+        "start_polar_align" => pa_handlers::start_polar_align(params, state),
+        "stop_polar_align" => pa_handlers::stop_polar_align(params, state),
+        "open_focuser" => focus_handlers::open_focuser(params, state),
+        "close_focuser" => focus_handlers::close_focuser(params, state),
+        "get_focuser_state" => focus_handlers::get_focuser_state(params, state),
+        "move_focuser" => focus_handlers::move_focuser(params, state),
+        "stop_focuser" => focus_handlers::stop_focuser(params, state),
+        "start_auto_focus" => focus_handlers::start_auto_focus(params, state),
+        "stop_auto_focus" => focus_handlers::stop_auto_focus(params, state),
         _ => Err(("Unknown method".to_string(), 1)),
     }
 }
@@ -75,7 +157,16 @@ pub fn asiair_tcp_4800_handler(
                 height: 0,
             })
         }
-        "get_current_img" => Ok(img_handlers::get_current_img(params, state)),
+        "get_current_img" | "get_stacked_img" | "get_img_file" => {
+            let image = match method {
+                "get_current_img" => Ok(img_handlers::get_current_img(params, state.clone())),
+                "get_stacked_img" => img_handlers::get_stacked_img(params, state.clone()),
+                _ => img_handlers::get_img_file(params, state.clone()),
+            }?;
+            // Packing the image keeps the CPU busy
+            state.lock().unwrap().pi.download(image.data.len());
+            Ok(image)
+        }
         _ => {
             return Err(format!("Unknown method: {}", method).into());
         }
@@ -0,0 +1,182 @@
+use super::ASIAirState;
+use crate::mount::TrackMode;
+use serde_json::{json, Value};
+use std::sync::{Arc, Mutex};
+
+fn array_params(params: &Option<Value>) -> Result<&Value, (String, u8)> {
+    match params {
+        Some(value) if value.is_array() => Ok(value),
+        Some(_) => Err(("params is not an array".to_string(), 1)),
+        None => Err(("params is not provided".to_string(), 1)),
+    }
+}
+
+pub fn scope_goto(params: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
+    let mut state = state.lock().unwrap();
+
+    let value = array_params(params)?;
+    let (Some(ra), Some(dec)) = (value[0].as_f64(), value[1].as_f64()) else {
+        return Err(("invalid coordinates".to_string(), 1));
+    };
+    // The target can be named by a third parameter, the frames taken after the goto are saved under it
+    let name = value[2].as_str().unwrap_or_default().to_string();
+
+    let now = state.rtc.now();
+    state.mount.goto(ra, dec, now).map_err(|e| (e, 1))?;
+    state.app_setting.goto_target_name = name;
+    state.app_setting.goto_target_ra = ra;
+    state.app_setting.goto_target_dec = dec;
+    state.emit_event(json!({
+        "Event": "ScopeGoto",
+        "state": "start",
+        "ra": ra,
+        "dec": dec,
+    }));
+
+    Ok((json!(0), 0))
+}
+
+pub fn scope_abort_slew(_: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
+    let mut state = state.lock().unwrap();
+
+    if state.mount.is_slewing() {
+        state.mount.abort();
+        state.emit_event(json!({
+            "Event": "ScopeGoto",
+            "state": "abort",
+            "ra": state.mount.ra,
+            "dec": state.mount.dec,
+        }));
+    }
+
+    Ok((json!(0), 0))
+}
+
+pub fn scope_park(_: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
+    let mut state = state.lock().unwrap();
+
+    if !state.mount.parked {
+        state.mount.park();
+        state.emit_event(json!({
+            "Event": "ScopePark",
+            "state": "start",
+        }));
+    }
+
+    Ok((json!(0), 0))
+}
+
+pub fn scope_unpark(_: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
+    let mut state = state.lock().unwrap();
+
+    state.mount.unpark();
+
+    Ok((json!(0), 0))
+}
+
+pub fn scope_get_equ_coord(_: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
+    let state = state.lock().unwrap();
+
+    Ok((json!({ "ra": state.mount.ra, "dec": state.mount.dec }), 0))
+}
+
+pub fn scope_get_state(_: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
+    let state = state.lock().unwrap();
+    let mount = &state.mount;
+    let now = state.rtc.now();
+
+    Ok((
+        json!({
+            "ra": mount.ra,
+            "dec": mount.dec,
+            "alt": mount.altitude(mount.ra, mount.dec, now),
+            "hour_angle": mount.hour_angle(mount.ra, now),
+            "pier_side": mount.pier_side,
+            "tracking": mount.tracking,
+            "track_mode": mount.track_mode,
+            "slewing": mount.is_slewing(),
+            "parked": mount.parked,
+            "slew_rate": mount.slew_rate,
+            "latitude": mount.latitude,
+            "longitude": mount.longitude,
+            "min_altitude": mount.min_altitude,
+            "meridian_limit_min": mount.meridian_limit_min,
+        }),
+        0,
+    ))
+}
+
+pub fn scope_get_track_state(_: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
+    let state = state.lock().unwrap();
+
+    Ok((json!(state.mount.tracking), 0))
+}
+
+pub fn scope_set_track_state(params: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
+    let mut state = state.lock().unwrap();
+
+    let value = array_params(params)?;
+    let Some(tracking) = value[0].as_bool() else {
+        return Err(("invalid tracking state".to_string(), 1));
+    };
+    state.mount.set_tracking(tracking).map_err(|e| (e, 1))?;
+    state.emit_event(json!({
+        "Event": "ScopeTrack",
+        "tracking": tracking,
+        "reason": "user",
+    }));
+
+    Ok((json!(0), 0))
+}
+
+pub fn scope_set_track_mode(params: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
+    let mut state = state.lock().unwrap();
+
+    let value = array_params(params)?;
+    let mode: TrackMode = serde_json::from_value(value[0].clone()).map_err(|_| ("invalid track mode".to_string(), 1))?;
+    state.mount.track_mode = mode;
+
+    Ok((json!(0), 0))
+}
+
+pub fn scope_set_slew_rate(params: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
+    let mut state = state.lock().unwrap();
+
+    let value = array_params(params)?;
+    match value[0].as_f64() {
+        Some(rate) if rate.is_finite() && rate > 0.0 => state.mount.slew_rate = rate,
+        _ => return Err(("invalid slew rate".to_string(), 1)),
+    }
+
+    Ok((json!(0), 0))
+}
+
+pub fn scope_set_location(params: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
+    let mut state = state.lock().unwrap();
+
+    let value = array_params(params)?;
+    match (value[0].as_f64(), value[1].as_f64()) {
+        (Some(latitude), Some(longitude)) if latitude.abs() <= 90.0 && longitude.abs() <= 180.0 => {
+            state.mount.latitude = latitude;
+            state.mount.longitude = longitude;
+        }
+        _ => return Err(("invalid location".to_string(), 1)),
+    }
+
+    Ok((json!(0), 0))
+}
+
+pub fn scope_set_limits(params: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
+    let mut state = state.lock().unwrap();
+
+    let value = array_params(params)?;
+    match (value[0].as_f64(), value[1].as_f64()) {
+        (Some(min_altitude), Some(meridian_limit_min)) if min_altitude.abs() <= 90.0 && meridian_limit_min >= 0.0 => {
+            state.mount.min_altitude = min_altitude;
+            state.mount.meridian_limit_min = meridian_limit_min;
+        }
+        _ => return Err(("invalid limits".to_string(), 1)),
+    }
+
+    Ok((json!(0), 0))
+}
@@ -0,0 +1,134 @@
+use super::ASIAirState;
+use crate::mount::MOUNT_TICK;
+use crate::sim::CameraState;
+use crate::solver::gaussian;
+use serde_json::{json, Value};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+// Rotation of the RA axis between the frames used to find the polar axis, in hours
+const PA_ROTATION: f64 = 4.0;
+// Number of frames solved before the error is known
+const PA_STEPS: u32 = 3;
+// Shortest time between two error updates, whatever the solver latency
+const PA_UPDATE_INTERVAL: Duration = Duration::from_millis(100);
+
+fn emit_pa_event(state: &ASIAirState, pa_state: &str, step: u32, error: Option<&str>) {
+    let mut event = json!({
+        "Event": "PolarAlign",
+        "state": pa_state,
+        "step": step,
+    });
+    if let Some(error) = error {
+        event["error"] = json!(error);
+    }
+    state.emit_event(event);
+}
+
+// Ends the routine on a failure
+fn fail(state: &Arc<Mutex<ASIAirState>>, step: u32, error: &str) {
+    let mut state = state.lock().unwrap();
+    state.app_state.pa.is_working = false;
+    state.pa_task = None;
+    emit_pa_event(&state, "fail", step, Some(error));
+}
+
+pub fn start_polar_align(_: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
+    {
+        let mut state = state.lock().unwrap();
+        if state.app_state.pa.is_working {
+            return Err(("polar alignment in progress".to_string(), 1));
+        }
+        if let CameraState::Close = state.camera_state {
+            return Err(("camera is not open".to_string(), 1));
+        }
+        if state.mount.parked {
+            return Err(("mount is parked".to_string(), 1));
+        }
+        state.app_state.pa.is_working = true;
+        emit_pa_event(&state, "start", 0, None);
+    }
+
+    let task = tokio::spawn(run_polar_align(state.clone()));
+    {
+        let mut state = state.lock().unwrap();
+        if state.app_state.pa.is_working {
+            state.pa_task = Some(task.abort_handle());
+        }
+    }
+
+    Ok((json!(0), 0))
+}
+
+pub fn stop_polar_align(_: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
+    let mut state = state.lock().unwrap();
+
+    if !state.app_state.pa.is_working {
+        return Ok((json!(0), 0));
+    }
+    if let Some(task) = state.pa_task.take() {
+        task.abort();
+    }
+    state.mount.abort();
+    state.app_state.pa.is_working = false;
+    emit_pa_event(&state, "stop", 0, None);
+
+    Ok((json!(0), 0))
+}
+
+// Captures and solves frames at three RA positions to find the polar axis, then keeps
+// measuring the error while the knobs are adjusted, until stopped
+async fn run_polar_align(state: Arc<Mutex<ASIAirState>>) {
+    let clock = state.lock().unwrap().clock.clone();
+    for step in 1..=PA_STEPS {
+        let (latency, failure) = {
+            let state = state.lock().unwrap();
+            emit_pa_event(&state, "capture", step, None);
+            (state.solver.latency, state.solver.failure)
+        };
+        clock.sleep(latency).await;
+        if let Some(failure) = failure {
+            fail(&state, step, failure.as_str());
+            return;
+        }
+
+        if step < PA_STEPS {
+            let rotate = {
+                let mut state = state.lock().unwrap();
+                let now = state.rtc.now();
+                let (ra, dec) = (state.mount.ra, state.mount.dec);
+                let rotate = state.mount.goto((ra - PA_ROTATION).rem_euclid(24.0), dec, now);
+                if rotate.is_ok() {
+                    emit_pa_event(&state, "rotate", step, None);
+                }
+                rotate
+            };
+            if let Err(error) = rotate {
+                fail(&state, step, &error);
+                return;
+            }
+            while state.lock().unwrap().mount.is_slewing() {
+                clock.sleep(MOUNT_TICK).await;
+            }
+        }
+    }
+
+    loop {
+        let latency = {
+            let state = state.lock().unwrap();
+            let noise = state.solver.noise_arcsec / 60.0;
+            let alt = state.mount.polar_error_alt + gaussian(noise);
+            let az = state.mount.polar_error_az + gaussian(noise);
+            state.emit_event(json!({
+                "Event": "PolarAlign",
+                "state": "update",
+                "step": PA_STEPS,
+                "alt_error": alt,
+                "az_error": az,
+                "total_error": alt.hypot(az),
+            }));
+            state.solver.latency
+        };
+        clock.sleep(latency.max(PA_UPDATE_INTERVAL)).await;
+    }
+}
@@ -0,0 +1,294 @@
+use super::ASIAirState;
+use crate::mount::MOUNT_TICK;
+use crate::sim::{CaptureStatus, FrameType, Plan, PlanTarget};
+use chrono::{DateTime, FixedOffset};
+use serde_json::{json, Value};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+// Longest wait between two checks of the clock while waiting for a target window
+const PLAN_WAIT_POLL: Duration = Duration::from_secs(1);
+
+fn parse_window(target: &PlanTarget) -> Result<(DateTime<FixedOffset>, DateTime<FixedOffset>), String> {
+    let start = DateTime::parse_from_rfc3339(&target.start_time)
+        .map_err(|_| format!("invalid start_time for target {}", target.name))?;
+    let end = DateTime::parse_from_rfc3339(&target.end_time)
+        .map_err(|_| format!("invalid end_time for target {}", target.name))?;
+    if end <= start {
+        return Err(format!("end_time before start_time for target {}", target.name));
+    }
+    Ok((start, end))
+}
+
+fn emit_plan_event(state: &Arc<Mutex<ASIAirState>>, plan_state: &str, plan: &str, target: &str, filter: &str, done: u32, count: u32) {
+    let state = state.lock().unwrap();
+    state.emit_event(json!({
+        "Event": "Plan",
+        "state": plan_state,
+        "plan": plan,
+        "target": target,
+        "filter": filter,
+        "done": done,
+        "count": count,
+    }));
+}
+
+// Creates a new plan, or replaces the plan with the same name
+pub fn set_plan(params: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
+    let mut state = state.lock().unwrap();
+
+    let plan: Plan = match params {
+        Some(value) => {
+            if !value.is_array() {
+                return Err(("params is not an array".to_string(), 1));
+            }
+            serde_json::from_value(value[0].clone()).map_err(|e| (format!("invalid plan: {}", e), 1))?
+        }
+        None => return Err(("params is not provided".to_string(), 1)),
+    };
+
+    if plan.name.is_empty() {
+        return Err(("plan name is empty".to_string(), 1));
+    }
+    for target in &plan.targets {
+        parse_window(target).map_err(|e| (e, 1))?;
+    }
+    if state.app_state.plan.is_working && state.app_state.plan.plan_name == plan.name {
+        return Err(("plan is running".to_string(), 1));
+    }
+
+    match state.plans.iter_mut().find(|p| p.name == plan.name) {
+        Some(existing) => *existing = plan,
+        None => state.plans.push(plan),
+    }
+
+    Ok((json!(0), 0))
+}
+
+pub fn get_plan_list(_: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
+    let state = state.lock().unwrap();
+
+    Ok((serde_json::to_value(&state.plans).unwrap(), 0))
+}
+
+pub fn delete_plan(params: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
+    let mut state = state.lock().unwrap();
+
+    let name = match params {
+        Some(value) => value[0].as_str().ok_or(("invalid plan name".to_string(), 1))?.to_string(),
+        None => return Err(("params is not provided".to_string(), 1)),
+    };
+
+    if state.app_state.plan.is_working && state.app_state.plan.plan_name == name {
+        return Err(("plan is running".to_string(), 1));
+    }
+    let count = state.plans.len();
+    state.plans.retain(|p| p.name != name);
+    if state.plans.len() == count {
+        return Err(("plan not found".to_string(), 1));
+    }
+
+    Ok((json!(0), 0))
+}
+
+pub fn start_plan(params: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
+    let name = match params {
+        Some(value) => value[0].as_str().ok_or(("invalid plan name".to_string(), 1))?.to_string(),
+        None => return Err(("params is not provided".to_string(), 1)),
+    };
+
+    {
+        let mut state = state.lock().unwrap();
+        if !state.plans.iter().any(|p| p.name == name) {
+            return Err(("plan not found".to_string(), 1));
+        }
+        if state.app_state.plan.is_working {
+            return Err(("a plan is already running".to_string(), 1));
+        }
+        if state.app_state.capture.is_working {
+            return Err(("exposure in progress".to_string(), 1));
+        }
+        state.app_state.plan.is_working = true;
+        state.app_state.plan.plan_name = name.clone();
+        state.app_state.plan.target_name = "".to_string();
+    }
+
+    let task = tokio::spawn(run_plan(state.clone(), name));
+    {
+        let mut state = state.lock().unwrap();
+        if state.app_state.plan.is_working {
+            state.plan_task = Some(task.abort_handle());
+        }
+    }
+
+    Ok((json!(0), 0))
+}
+
+pub fn stop_plan(_: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
+    let plan_name;
+    {
+        let mut state = state.lock().unwrap();
+        if !state.app_state.plan.is_working {
+            return Ok((json!(0), 0));
+        }
+        if let Some(task) = state.plan_task.take() {
+            task.abort();
+        }
+        if let Some(task) = state.exposure_task.take() {
+            task.abort();
+        }
+        plan_name = state.app_state.plan.plan_name.clone();
+        if state.app_state.auto_goto.is_working {
+            state.mount.abort();
+        }
+        state.app_state.plan.is_working = false;
+        state.app_state.plan.target_name = "".to_string();
+        state.app_state.auto_goto.is_working = false;
+        state.app_state.capture.is_working = false;
+        state.app_state.capture.state = CaptureStatus::Idle;
+    }
+
+    emit_plan_event(&state, "stop", &plan_name, "", "", 0, 0);
+
+    Ok((json!(0), 0))
+}
+
+// Runs the targets of a plan in order, following the simulated clock
+async fn run_plan(state: Arc<Mutex<ASIAirState>>, plan_name: String) {
+    let clock = state.lock().unwrap().clock.clone();
+    let plan = {
+        let state = state.lock().unwrap();
+        state.plans.iter().find(|p| p.name == plan_name).cloned()
+    };
+    let Some(plan) = plan else {
+        return;
+    };
+
+    for (target_index, target) in plan.targets.iter().enumerate() {
+        let Ok((start, end)) = parse_window(target) else {
+            continue;
+        };
+
+        // Wait for the start of the target window
+        let mut waiting = false;
+        loop {
+            let now = state.lock().unwrap().rtc.now();
+            if now >= start {
+                break;
+            }
+            if !waiting {
+                emit_plan_event(&state, "waiting", &plan.name, &target.name, "", 0, 0);
+                waiting = true;
+            }
+            let remaining = (start - now).to_std().unwrap_or_default();
+            clock.sleep(remaining.min(PLAN_WAIT_POLL)).await;
+        }
+
+        if state.lock().unwrap().rtc.now() >= end {
+            emit_plan_event(&state, "skip", &plan.name, &target.name, "", 0, 0);
+            continue;
+        }
+
+        let goto = {
+            let mut state = state.lock().unwrap();
+            let now = state.rtc.now();
+            let goto = state.mount.goto(target.ra, target.dec, now);
+            if goto.is_ok() {
+                state.app_state.plan.target_name = target.name.clone();
+                state.app_state.auto_goto.is_working = true;
+            }
+            goto
+        };
+        if goto.is_err() {
+            emit_plan_event(&state, "skip", &plan.name, &target.name, "", 0, 0);
+            continue;
+        }
+        emit_plan_event(&state, "goto", &plan.name, &target.name, "", 0, 0);
+        while state.lock().unwrap().mount.is_slewing() {
+            clock.sleep(MOUNT_TICK).await;
+        }
+        {
+            let mut state = state.lock().unwrap();
+            state.app_state.auto_goto.is_working = false;
+            state.app_setting.goto_target_name = target.name.clone();
+            state.app_setting.goto_target_ra = target.ra;
+            state.app_setting.goto_target_dec = target.dec;
+        }
+
+        'exposures: for (exposure_index, exposure) in target.exposures.iter().enumerate() {
+            let mut done = exposure.done;
+            while done < exposure.count {
+                // Capture is suspended while the mount flips
+                while state.lock().unwrap().app_state.merid_flip.is_working {
+                    clock.sleep(MOUNT_TICK).await;
+                }
+                if state.lock().unwrap().rtc.now() >= end {
+                    emit_plan_event(&state, "skip", &plan.name, &target.name, &exposure.filter, done, exposure.count);
+                    break 'exposures;
+                }
+
+                let frame = {
+                    let mut state = state.lock().unwrap();
+                    state.app_state.capture.is_working = true;
+                    state.app_state.capture.state = CaptureStatus::Working;
+                    state.emit_event(json!({
+                        "Event": "Exposure",
+                        "page": "plan",
+                        "state": "start",
+                        "exp_us": exposure.exp_us,
+                        "gain": state.camera_controls.gain,
+                    }));
+                    state.capture_frame(FrameType::Light, exposure.exp_us).ok()
+                };
+
+                // The exposure is a task of its own, so stopping the exposure or a meridian flip can abort it
+                let exposure_clock = clock.clone();
+                let exp_us = exposure.exp_us;
+                let task = tokio::spawn(async move { exposure_clock.sleep(Duration::from_micros(exp_us)).await });
+                state.lock().unwrap().exposure_task = Some(task.abort_handle());
+                let exposed = task.await.is_ok();
+
+                {
+                    let mut state = state.lock().unwrap();
+                    // An aborted exposure has already been reported as canceled, take it again
+                    if !exposed || !state.app_state.capture.is_working {
+                        continue;
+                    }
+                    state.exposure_task = None;
+                    state.app_state.capture.is_working = false;
+                    state.app_state.capture.state = CaptureStatus::Idle;
+                    // A frame exposed while the mount started flipping is trailed, take it again
+                    if state.app_state.merid_flip.is_working {
+                        state.emit_event(json!({
+                            "Event": "Exposure",
+                            "state": "cancel"
+                        }));
+                        continue;
+                    }
+                    done += 1;
+                    if let Some(frame) = frame {
+                        state.complete_frame(frame);
+                    }
+                    if let Some(p) = state.plans.iter_mut().find(|p| p.name == plan.name) {
+                        if let Some(e) = p.targets.get_mut(target_index).and_then(|t| t.exposures.get_mut(exposure_index)) {
+                            e.done = done;
+                        }
+                    }
+                    state.emit_event(json!({
+                        "Event": "Exposure",
+                        "state": "complete"
+                    }));
+                }
+                emit_plan_event(&state, "working", &plan.name, &target.name, &exposure.filter, done, exposure.count);
+            }
+        }
+    }
+
+    {
+        let mut state = state.lock().unwrap();
+        state.app_state.plan.is_working = false;
+        state.app_state.plan.target_name = "".to_string();
+        state.plan_task = None;
+    }
+    emit_plan_event(&state, "complete", &plan.name, "", "", 0, 0);
+}
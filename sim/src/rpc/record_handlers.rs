@@ -0,0 +1,143 @@
+use super::ASIAirState;
+use crate::recorder::Recording;
+use crate::sim::FrameType;
+use serde_json::{json, Value};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+// Interval between two updates of the recording state
+const RECORD_TICK: Duration = Duration::from_millis(250);
+
+fn emit_record_event(state: &ASIAirState, record_state: &str, path: Option<&str>, error: Option<&str>) {
+    let record = &state.app_state.avi_record;
+    let mut event = json!({
+        "Event": "AviRecord",
+        "state": record_state,
+        "lapse_sec": record.lapse_sec,
+        "fps": record.fps,
+        "write_file_fps": record.write_file_fps,
+        "frame_count": record.frame_count,
+    });
+    if let Some(path) = path {
+        event["path"] = json!(path);
+    }
+    if let Some(error) = error {
+        event["error"] = json!(error);
+    }
+    state.emit_event(event);
+}
+
+// Saves the video being recorded and ends the recording, returns the path of the file.
+// The recording fails with `error` when it couldn't go on
+fn finish_record(state: &mut ASIAirState, error: Option<&str>) -> Result<String, String> {
+    let recording = state.recording.take().ok_or("not recording")?;
+    if let Some(task) = state.record_task.take() {
+        task.abort();
+    }
+    state.app_state.avi_record.is_working = false;
+
+    // Frames that don't fit are lost
+    let frame_count = (recording.frame_count() as u64).min(state.storage.video_capacity(&recording.frame)) as u32;
+    state.app_state.avi_record.frame_count = frame_count;
+    let saved = state
+        .storage
+        .save_video(recording.frame, frame_count, &recording.format, &recording.target, recording.temperature, recording.start)
+        .map(|video| video.path.clone());
+    match (&saved, error) {
+        (Ok(path), None) => emit_record_event(state, "complete", Some(path), None),
+        (Ok(path), Some(error)) => emit_record_event(state, "fail", Some(path), Some(error)),
+        (Err(error), _) => emit_record_event(state, "fail", None, Some(error)),
+    }
+    saved
+}
+
+// Records a video from the main camera, at the rate allowed by the exposure and the camera link
+pub fn start_avi_record(params: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
+    let options = params.as_ref().map(|value| value[0].clone()).unwrap_or(Value::Null);
+    let format = options["format"].as_str().unwrap_or("ser").to_string();
+    if format != "ser" && format != "avi" {
+        return Err(("invalid format".to_string(), 1));
+    }
+    let duration = options["duration_sec"].as_f64().filter(|d| *d > 0.0).map(Duration::from_secs_f64);
+
+    {
+        let mut state = state.lock().unwrap();
+        if state.app_state.avi_record.is_working {
+            return Err(("recording in progress".to_string(), 1));
+        }
+        if state.app_state.capture.is_working || state.app_state.plan.is_working {
+            return Err(("exposure in progress".to_string(), 1));
+        }
+        let exposure_us = state.camera_controls.exposure.max(1) as u64;
+        let frame = state.capture_frame(FrameType::Light, exposure_us).map_err(|e| (e, 1))?;
+        let target = state.app_setting.goto_target_name.clone();
+        let temperature = state.camera_controls.temperature;
+        let start = state.rtc.now();
+        let recording = Recording::new(frame, &format, &target, temperature, start, duration, state.usb3_host);
+
+        let record = &mut state.app_state.avi_record;
+        record.is_working = true;
+        record.lapse_sec = 0;
+        record.fps = recording.fps as f32;
+        record.write_file_fps = recording.write_file_fps as f32;
+        record.frame_count = 0;
+        state.recording = Some(recording);
+        emit_record_event(&state, "start", None, None);
+    }
+
+    let task = tokio::spawn(run_record(state.clone()));
+    {
+        let mut state = state.lock().unwrap();
+        if state.app_state.avi_record.is_working {
+            state.record_task = Some(task.abort_handle());
+        }
+    }
+
+    Ok((json!(0), 0))
+}
+
+pub fn stop_avi_record(_: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
+    let mut state = state.lock().unwrap();
+
+    if !state.app_state.avi_record.is_working {
+        return Err(("not recording".to_string(), 1));
+    }
+    let path = finish_record(&mut state, None).map_err(|e| (e, 1))?;
+
+    Ok((json!({ "path": path }), 0))
+}
+
+pub fn get_avi_record_state(_: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
+    let state = state.lock().unwrap();
+
+    Ok((serde_json::to_value(&state.app_state.avi_record).unwrap(), 0))
+}
+
+// Counts the frames written until the recording is stopped, reaches its duration or fills the storage
+async fn run_record(state: Arc<Mutex<ASIAirState>>) {
+    let clock = state.lock().unwrap().clock.clone();
+    loop {
+        clock.sleep(RECORD_TICK).await;
+
+        let mut state = state.lock().unwrap();
+        let capacity = match &state.recording {
+            Some(recording) => state.storage.video_capacity(&recording.frame),
+            None => return,
+        };
+        let Some(recording) = state.recording.as_mut() else {
+            return;
+        };
+        recording.step(RECORD_TICK);
+        let (lapse, frame_count, over) = (recording.elapsed, recording.frame_count(), recording.is_over());
+
+        state.app_state.avi_record.lapse_sec = lapse.as_secs() as u32;
+        state.app_state.avi_record.frame_count = frame_count;
+        if frame_count as u64 > capacity || over {
+            state.record_task = None;
+            let error = (frame_count as u64 > capacity).then_some("storage full");
+            let _ = finish_record(&mut state, error);
+            return;
+        }
+        emit_record_event(&state, "working", None, None);
+    }
+}
@@ -0,0 +1,164 @@
+use super::ASIAirState;
+use crate::frame;
+use crate::rtmp::{self, Publisher, RtmpConfig};
+use crate::sim::FrameType;
+use serde_json::{json, Value};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+// Interval between two streamed frames, the stream is a low rate preview of the camera
+const RTMP_FRAME_INTERVAL: Duration = Duration::from_millis(500);
+// Largest frame size the Screen Video codec can describe
+const MAX_RESOLUTION: u32 = 4095;
+
+fn emit_rtmp_event(state: &ASIAirState, rtmp_state: &str, error: Option<&str>) {
+    let mut event = json!({
+        "Event": "Rtmp",
+        "state": rtmp_state,
+        "frames": state.app_state.rtmp.frames,
+    });
+    if let Some(error) = error {
+        event["error"] = json!(error);
+    }
+    state.emit_event(event);
+}
+
+// Ends the stream, with `error` when it failed
+fn finish_rtmp(state: &mut ASIAirState, error: Option<&str>) {
+    if let Some(task) = state.rtmp_task.take() {
+        task.abort();
+    }
+    state.app_state.rtmp.is_working = false;
+    match error {
+        Some(error) => emit_rtmp_event(state, "fail", Some(error)),
+        None => emit_rtmp_event(state, "stop", None),
+    }
+}
+
+pub fn set_rtmp_config(params: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
+    let options = params.as_ref().map(|value| value[0].clone()).unwrap_or(Value::Null);
+    let mut state = state.lock().unwrap();
+
+    if state.app_state.rtmp.is_working {
+        return Err(("rtmp in progress".to_string(), 1));
+    }
+    let mut config = state.rtmp_config.clone();
+    if let Some(url) = options["url"].as_str() {
+        rtmp::parse_url(url).map_err(|e| (e, 1))?;
+        config.url = url.to_string();
+    }
+    if let Some(key) = options["key"].as_str() {
+        config.key = key.to_string();
+    }
+    if let Some(width) = options["width"].as_u64() {
+        config.width = width as u32;
+    }
+    if let Some(height) = options["height"].as_u64() {
+        config.height = height as u32;
+    }
+    if !(16..=MAX_RESOLUTION).contains(&config.width) || !(16..=MAX_RESOLUTION).contains(&config.height) {
+        return Err(("invalid resolution".to_string(), 1));
+    }
+    state.rtmp_config = config;
+
+    Ok((json!(0), 0))
+}
+
+pub fn get_rtmp_config(_: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
+    let state = state.lock().unwrap();
+
+    Ok((serde_json::to_value(&state.rtmp_config).unwrap(), 0))
+}
+
+// Streams the main camera to the configured RTMP server until stopped
+pub fn start_rtmp(_: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
+    let config = {
+        let mut state = state.lock().unwrap();
+        if state.app_state.rtmp.is_working {
+            return Err(("rtmp in progress".to_string(), 1));
+        }
+        if state.rtmp_config.url.is_empty() {
+            return Err(("rtmp url is not set".to_string(), 1));
+        }
+        crate::solver::image_geometry(&state).map_err(|e| (e, 1))?;
+
+        state.app_state.rtmp.is_working = true;
+        state.app_state.rtmp.frames = 0;
+        emit_rtmp_event(&state, "connect", None);
+        state.rtmp_config.clone()
+    };
+
+    let task = tokio::spawn(run_rtmp(state.clone(), config));
+    {
+        let mut state = state.lock().unwrap();
+        if state.app_state.rtmp.is_working {
+            state.rtmp_task = Some(task.abort_handle());
+        }
+    }
+
+    Ok((json!(0), 0))
+}
+
+pub fn stop_rtmp(_: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
+    let mut state = state.lock().unwrap();
+
+    if !state.app_state.rtmp.is_working {
+        return Err(("rtmp is not working".to_string(), 1));
+    }
+    finish_rtmp(&mut state, None);
+
+    Ok((json!(0), 0))
+}
+
+pub fn get_rtmp_state(_: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
+    let state = state.lock().unwrap();
+
+    Ok((serde_json::to_value(&state.app_state.rtmp).unwrap(), 0))
+}
+
+// Publishes the stream, then sends a preview of the camera at a low rate until the
+// connection fails or the camera is closed
+async fn run_rtmp(state: Arc<Mutex<ASIAirState>>, config: RtmpConfig) {
+    let fail = |error: String| {
+        let mut state = state.lock().unwrap();
+        state.rtmp_task = None;
+        finish_rtmp(&mut state, Some(&error));
+    };
+
+    let clock = state.lock().unwrap().clock.clone();
+    let mut publisher = match Publisher::connect(&config.url, &config.key, clock.clone()).await {
+        Ok(publisher) => publisher,
+        Err(error) => return fail(error),
+    };
+    let fps = 1.0 / RTMP_FRAME_INTERVAL.as_secs_f64();
+    if let Err(error) = publisher.send_metadata(config.width, config.height, fps).await {
+        return fail(error.to_string());
+    }
+    emit_rtmp_event(&state.lock().unwrap(), "start", None);
+
+    let mut interval = clock.interval(RTMP_FRAME_INTERVAL);
+    loop {
+        interval.tick().await;
+
+        let frame = {
+            let mut state = state.lock().unwrap();
+            let exposure_us = state.camera_controls.exposure.max(1) as u64;
+            state.capture_frame(FrameType::Light, exposure_us)
+        };
+        let frame = match frame {
+            Ok(frame) => frame,
+            Err(error) => return fail(error),
+        };
+        let (width, height) = (config.width, config.height);
+        let pixels = tokio::task::spawn_blocking(move || frame::preview(&frame.render(), frame.width, frame.height, width, height))
+            .await
+            .unwrap();
+        if let Err(error) = publisher.send_frame(&pixels, width, height).await {
+            return fail(error.to_string());
+        }
+
+        let mut state = state.lock().unwrap();
+        state.app_state.rtmp.frames += 1;
+        emit_rtmp_event(&state, "working", None);
+    }
+}
@@ -0,0 +1,141 @@
+use super::ASIAirState;
+use crate::solver::{self, SolveFailure};
+use serde_json::{json, Value};
+use std::sync::{Arc, Mutex};
+use tokio::task::AbortHandle;
+
+// A plain solve and an annotation run the same way on the solver, they only differ by the events
+// they send, where they keep their state and what they add to the solution
+#[derive(Clone, Copy)]
+pub(super) struct SolverJob {
+    pub event: &'static str,
+    pub tag: &'static str,
+    pub in_progress: &'static str,
+    pub no_result: &'static str,
+    // Whether the job is running, and the time it took
+    pub status: fn(&mut ASIAirState) -> (&mut bool, &mut u32),
+    pub result: fn(&mut ASIAirState) -> &mut Option<Result<Value, String>>,
+    pub task: fn(&mut ASIAirState) -> &mut Option<AbortHandle>,
+    // Completes the solution of an image of `chip_size` pixels of `scale` arcseconds
+    pub complete: fn(Value, [u32; 2], f64) -> Value,
+}
+
+const SOLVE: SolverJob = SolverJob {
+    event: "PlateSolve",
+    tag: "Solve",
+    in_progress: "solve in progress",
+    no_result: "no solve result",
+    status: |state| (&mut state.app_state.solve.is_working, &mut state.app_state.solve.lapse_ms),
+    result: |state| &mut state.solve_result,
+    task: |state| &mut state.solve_task,
+    complete: |solution, _, _| solution,
+};
+
+fn emit_job_event(job: SolverJob, state: &ASIAirState, job_state: &str, error: Option<&str>) {
+    let mut event = json!({
+        "Event": job.event,
+        "page": state.app_state.page.as_str(),
+        "tag": job.tag,
+        "state": job_state,
+    });
+    if let Some(error) = error {
+        event["error"] = json!(error);
+    }
+    state.emit_event(event);
+}
+
+// Solves the last image of the main camera, the result is reported by the events of the job
+pub(super) fn start_job(job: SolverJob, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
+    let (outcome, delay) = {
+        let mut state = state.lock().unwrap();
+
+        if *(job.status)(&mut state).0 {
+            return Err((job.in_progress.to_string(), 1));
+        }
+        let (chip_size, scale) = solver::image_geometry(&state).map_err(|e| (e, 1))?;
+
+        let now = state.rtc.now();
+        let outcome = solver::solve(&state.solver, &state.mount, chip_size, scale, now)
+            .map(|solution| (job.complete)(solution, chip_size, scale));
+        let delay = match outcome {
+            Err(SolveFailure::Timeout) => state.solver.timeout,
+            _ => state.solver.latency,
+        };
+
+        let (is_working, lapse_ms) = (job.status)(&mut state);
+        *is_working = true;
+        *lapse_ms = 0;
+        emit_job_event(job, &state, "start", None);
+        (outcome, delay)
+    };
+
+    let task_state = state.clone();
+    let clock = state.lock().unwrap().clock.clone();
+    let task = tokio::spawn(async move {
+        clock.sleep(delay).await;
+
+        let mut state = task_state.lock().unwrap();
+        let (is_working, lapse_ms) = (job.status)(&mut state);
+        *is_working = false;
+        *lapse_ms = delay.as_millis() as u32;
+        *(job.task)(&mut state) = None;
+        match outcome {
+            Ok(solution) => {
+                *(job.result)(&mut state) = Some(Ok(solution));
+                emit_job_event(job, &state, "complete", None);
+            }
+            Err(failure) => {
+                *(job.result)(&mut state) = Some(Err(failure.as_str().to_string()));
+                emit_job_event(job, &state, "fail", Some(failure.as_str()));
+            }
+        }
+    });
+
+    {
+        let mut state = state.lock().unwrap();
+        if *(job.status)(&mut state).0 {
+            *(job.task)(&mut state) = Some(task.abort_handle());
+        }
+    }
+
+    Ok((json!(0), 0))
+}
+
+pub(super) fn stop_job(job: SolverJob, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
+    let mut state = state.lock().unwrap();
+
+    if !*(job.status)(&mut state).0 {
+        return Ok((json!(0), 0));
+    }
+    if let Some(task) = (job.task)(&mut state).take() {
+        task.abort();
+    }
+    *(job.status)(&mut state).0 = false;
+    *(job.result)(&mut state) = Some(Err("cancel".to_string()));
+    emit_job_event(job, &state, "cancel", None);
+
+    Ok((json!(0), 0))
+}
+
+pub(super) fn get_job_result(job: SolverJob, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
+    let mut state = state.lock().unwrap();
+
+    match (job.result)(&mut state) {
+        Some(Ok(solution)) => Ok((solution.clone(), 0)),
+        Some(Err(error)) => Err((error.clone(), 1)),
+        None => Err((job.no_result.to_string(), 1)),
+    }
+}
+
+// Solves the last image of the main camera, the result is reported by PlateSolve events
+pub fn start_solve(_: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
+    start_job(SOLVE, state)
+}
+
+pub fn stop_solve(_: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
+    stop_job(SOLVE, state)
+}
+
+pub fn get_solve_result(_: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
+    get_job_result(SOLVE, state)
+}
@@ -0,0 +1,137 @@
+use super::ASIAirState;
+use crate::frame::FrameParams;
+use crate::sim::FrameType;
+use crate::stack::Stacker;
+use serde_json::{json, Value};
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc;
+
+fn emit_stack_event(state: &ASIAirState, stack_state: &str, star_number: usize, error: Option<&str>) {
+    let stack = &state.app_state.stack;
+    let mut event = json!({
+        "Event": "Stack",
+        "state": stack_state,
+        "stacked_frame": stack.stacked_frame,
+        "dropped_frame": stack.dropped_frame,
+        "total_frame": stack.total_frame,
+        "star_number": star_number,
+    });
+    if let Some(error) = error {
+        event["error"] = json!(error);
+    }
+    state.emit_event(event);
+}
+
+// Starts a new stacking session, its task only touches the state while the session is current
+fn spawn_stack(state: &Arc<Mutex<ASIAirState>>) {
+    let mut guard = state.lock().unwrap();
+    let (stack_tx, stack_rx) = mpsc::unbounded_channel();
+    guard.stack_tx = Some(stack_tx);
+    guard.stack_generation += 1;
+    let task = tokio::spawn(run_stack(state.clone(), guard.stacker.clone(), stack_rx, guard.stack_generation));
+    guard.stack_task = Some(task.abort_handle());
+}
+
+// Ends the current stacking session, frames still queued are not stacked
+fn end_stack(state: &mut ASIAirState) {
+    state.stack_tx = None;
+    state.stack_generation += 1;
+    if let Some(task) = state.stack_task.take() {
+        task.abort();
+    }
+}
+
+// Stacks the light frames completed from now on, on top of the current stack
+pub fn start_stack(_: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
+    {
+        let mut state = state.lock().unwrap();
+        if state.app_state.stack.is_working {
+            return Err(("stacking in progress".to_string(), 1));
+        }
+        state.app_state.stack.is_working = true;
+        state.app_state.stack.frame_type = FrameType::Light;
+        emit_stack_event(&state, "start", 0, None);
+    }
+
+    spawn_stack(&state);
+
+    Ok((json!(0), 0))
+}
+
+pub fn stop_stack(_: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
+    let mut state = state.lock().unwrap();
+
+    if !state.app_state.stack.is_working {
+        return Ok((json!(0), 0));
+    }
+    end_stack(&mut state);
+    state.app_state.stack.is_working = false;
+    emit_stack_event(&state, "stop", 0, None);
+
+    Ok((json!(0), 0))
+}
+
+// Drops the stacked image and the counters, stacking goes on with the next frame as reference
+pub fn reset_stack(_: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
+    let is_working = {
+        let mut state = state.lock().unwrap();
+        end_stack(&mut state);
+        // A frame being added by the old session goes to the old stacker, not the new one
+        state.stacker = Arc::new(Mutex::new(Stacker::new()));
+        state.app_state.stack.stacked_frame = 0;
+        state.app_state.stack.dropped_frame = 0;
+        state.app_state.stack.total_frame = 0;
+        emit_stack_event(&state, "reset", 0, None);
+        state.app_state.stack.is_working
+    };
+
+    if is_working {
+        spawn_stack(&state);
+    }
+
+    Ok((json!(0), 0))
+}
+
+pub fn get_stack_state(_: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
+    let state = state.lock().unwrap();
+
+    Ok((serde_json::to_value(&state.app_state.stack).unwrap(), 0))
+}
+
+// Registers and adds the frames sent by `complete_frame`, until the session ends
+async fn run_stack(
+    state: Arc<Mutex<ASIAirState>>,
+    stacker: Arc<Mutex<Stacker>>,
+    mut stack_rx: mpsc::UnboundedReceiver<FrameParams>,
+    generation: u64,
+) {
+    while let Some(frame) = stack_rx.recv().await {
+        // Rendering and registration take a while on full frames, keep them off the async workers
+        let task_stacker = stacker.clone();
+        let added = tokio::task::spawn_blocking(move || {
+            let raw = frame.render();
+            task_stacker.lock().unwrap().add(&raw, frame.width, frame.height)
+        })
+        .await;
+        let Ok(added) = added else {
+            continue;
+        };
+
+        let mut state = state.lock().unwrap();
+        // The session ended while the frame was being added
+        if state.stack_generation != generation {
+            return;
+        }
+        state.app_state.stack.total_frame += 1;
+        match added {
+            Ok(star_number) => {
+                state.app_state.stack.stacked_frame += 1;
+                emit_stack_event(&state, "stacked", star_number, None);
+            }
+            Err((error, star_number)) => {
+                state.app_state.stack.dropped_frame += 1;
+                emit_stack_event(&state, "dropped", star_number, Some(&error));
+            }
+        }
+    }
+}
@@ -0,0 +1,223 @@
+use super::ASIAirState;
+use crate::storage::{AUTOSAVE_DIR, INTERNAL_STORAGE};
+use serde_json::{json, Value};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+// Time taken to format the internal storage
+const FORMAT_TIME: Duration = Duration::from_millis(1500);
+// Write speed of the removable storages, in bytes per second
+const EXPORT_RATE: f64 = 40.0 * 1024.0 * 1024.0;
+
+fn path_param(params: &Option<Value>) -> Result<String, (String, u8)> {
+    match params {
+        Some(value) => Ok(value[0].as_str().ok_or(("invalid path".to_string(), 1))?.to_string()),
+        None => Err(("params is not provided".to_string(), 1)),
+    }
+}
+
+fn emit_format_event(state: &ASIAirState, format_state: &str) {
+    state.emit_event(json!({
+        "Event": "FormatDrive",
+        "state": format_state,
+    }));
+}
+
+fn emit_export_event(state: &ASIAirState, export_state: &str, error: Option<&str>) {
+    let export = &state.app_state.export_image;
+    let mut event = json!({
+        "Event": "ExportImage",
+        "state": export_state,
+        "success_frame": export.success_frame,
+        "total_frame": export.total_frame,
+        "dst_storage": export.dst_storage,
+    });
+    if let Some(error) = error {
+        event["error"] = json!(error);
+    }
+    state.emit_event(event);
+}
+
+// Lists the images saved in a folder and its subfolders, the whole Autosave folder by default,
+// on the internal storage unless another storage is named
+pub fn get_img_file_list(params: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
+    let state = state.lock().unwrap();
+
+    let (dir, volume) = match params {
+        Some(value) => (
+            value[0].as_str().unwrap_or(AUTOSAVE_DIR),
+            value[1].as_str().unwrap_or(INTERNAL_STORAGE),
+        ),
+        None => (AUTOSAVE_DIR, INTERNAL_STORAGE),
+    };
+    let volume = state.storage.volume(volume).ok_or(("storage not found".to_string(), 1))?;
+    let images: Vec<Value> = volume.list(dir).iter().map(|image| image.to_json()).collect();
+
+    Ok((json!(images), 0))
+}
+
+// Deletes an image, or a folder with all its images
+pub fn delete_img_file(params: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
+    let mut state = state.lock().unwrap();
+
+    let path = path_param(params)?;
+    let deleted = state.storage.internal_mut().delete(&path);
+    if deleted == 0 {
+        return Err(("file not found".to_string(), 1));
+    }
+
+    Ok((json!({ "deleted": deleted }), 0))
+}
+
+pub fn get_storage_info(_: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
+    let state = state.lock().unwrap();
+
+    let volumes: Vec<Value> = state
+        .storage
+        .volumes
+        .iter()
+        .map(|volume| {
+            json!({
+                "name": volume.name,
+                "total": volume.capacity,
+                "used": volume.used(),
+                "free": volume.free(),
+                "image_count": volume.list("").len(),
+            })
+        })
+        .collect();
+
+    Ok((json!(volumes), 0))
+}
+
+// Erases every saved image, the end is reported by a FormatDrive event
+pub fn format_drive(_: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
+    {
+        let mut state = state.lock().unwrap();
+        if state.app_state.format_drive.is_working {
+            return Err(("format in progress".to_string(), 1));
+        }
+        if state.app_state.capture.is_working || state.app_state.plan.is_working {
+            return Err(("exposure in progress".to_string(), 1));
+        }
+        state.app_state.format_drive.is_working = true;
+        emit_format_event(&state, "start");
+    }
+
+    let task_state = state.clone();
+    let clock = state.lock().unwrap().clock.clone();
+    tokio::spawn(async move {
+        clock.sleep(FORMAT_TIME).await;
+
+        let mut state = task_state.lock().unwrap();
+        state.storage.format();
+        state.app_state.format_drive.is_working = false;
+        emit_format_event(&state, "complete");
+    });
+
+    Ok((json!(0), 0))
+}
+
+// Copies an image or a folder of the internal storage to a removable storage,
+// the progress is reported by ExportImage events
+pub fn start_export_image(params: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
+    let params = match params {
+        Some(value) => value[0].clone(),
+        None => return Err(("params is not provided".to_string(), 1)),
+    };
+    let src = params["src"].as_str().ok_or(("invalid src".to_string(), 1))?.to_string();
+    let dst_storage = params["dst_storage"].as_str().ok_or(("invalid dst_storage".to_string(), 1))?.to_string();
+    let keep = params["keep"].as_bool().unwrap_or(true);
+
+    let paths: Vec<String> = {
+        let mut state = state.lock().unwrap();
+        if state.app_state.export_image.is_working {
+            return Err(("export in progress".to_string(), 1));
+        }
+        if dst_storage == INTERNAL_STORAGE || state.storage.volume(&dst_storage).is_none() {
+            return Err(("storage not found".to_string(), 1));
+        }
+        let paths: Vec<String> = state.storage.internal().list(&src).iter().map(|image| image.path.clone()).collect();
+        if paths.is_empty() {
+            return Err(("file not found".to_string(), 1));
+        }
+
+        let export = &mut state.app_state.export_image;
+        export.is_working = true;
+        export.success_frame = 0;
+        export.total_frame = paths.len() as u32;
+        export.keep = keep;
+        export.dst_storage = dst_storage.clone();
+        emit_export_event(&state, "start", None);
+        paths
+    };
+
+    let task = tokio::spawn(run_export(state.clone(), paths, dst_storage, keep));
+    {
+        let mut state = state.lock().unwrap();
+        if state.app_state.export_image.is_working {
+            state.export_task = Some(task.abort_handle());
+        }
+    }
+
+    Ok((json!(0), 0))
+}
+
+pub fn stop_export_image(_: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
+    let mut state = state.lock().unwrap();
+
+    if !state.app_state.export_image.is_working {
+        return Ok((json!(0), 0));
+    }
+    if let Some(task) = state.export_task.take() {
+        task.abort();
+    }
+    state.app_state.export_image.is_working = false;
+    emit_export_event(&state, "cancel", None);
+
+    Ok((json!(0), 0))
+}
+
+pub fn get_export_image_state(_: &Option<Value>, state: Arc<Mutex<ASIAirState>>) -> Result<(Value, u8), (String, u8)> {
+    let state = state.lock().unwrap();
+
+    Ok((serde_json::to_value(&state.app_state.export_image).unwrap(), 0))
+}
+
+// Copies the images one by one at the write speed of the storage
+async fn run_export(state: Arc<Mutex<ASIAirState>>, paths: Vec<String>, dst_storage: String, keep: bool) {
+    let clock = state.lock().unwrap().clock.clone();
+    for path in paths {
+        let size = state.lock().unwrap().storage.internal().get(&path).map(|image| image.size);
+        // Images deleted since the export started are skipped
+        let Some(size) = size else {
+            continue;
+        };
+        clock.sleep(Duration::from_secs_f64(size as f64 / EXPORT_RATE)).await;
+
+        let mut state = state.lock().unwrap();
+        let Some(image) = state.storage.internal().get(&path).cloned() else {
+            continue;
+        };
+        let copied = match state.storage.volume_mut(&dst_storage) {
+            Some(volume) => volume.copy(&image),
+            None => Err("storage removed".to_string()),
+        };
+        if let Err(error) = copied {
+            state.app_state.export_image.is_working = false;
+            state.export_task = None;
+            emit_export_event(&state, "fail", Some(&error));
+            return;
+        }
+        if !keep {
+            state.storage.internal_mut().delete(&path);
+        }
+        state.app_state.export_image.success_frame += 1;
+        emit_export_event(&state, "working", None);
+    }
+
+    let mut state = state.lock().unwrap();
+    state.app_state.export_image.is_working = false;
+    state.export_task = None;
+    emit_export_event(&state, "complete", None);
+}
@@ -1,19 +1,28 @@
+use crate::clock::Clock;
 use chrono::{DateTime, FixedOffset, NaiveDate, NaiveTime, Offset, TimeZone};
-use std::time::Instant;
+use tokio::time::Instant;
 
 #[derive(Debug, Clone)]
 pub struct RTC {
     base_datetime: DateTime<FixedOffset>,
     base_instant: Instant,
+    clock: Clock,
 }
 
 impl RTC {
     /// Creates a new RTC instance with the current system time.
+    #[allow(dead_code)]
     pub fn new() -> Self {
+        Self::with_clock(Clock::tokio())
+    }
+
+    /// Creates a new RTC instance with the current system time, running on `clock`
+    pub fn with_clock(clock: Clock) -> Self {
         let now = chrono::Utc::now().with_timezone(&FixedOffset::east_opt(0).unwrap());
         Self {
             base_datetime: now,
-            base_instant: Instant::now(),
+            base_instant: clock.now(),
+            clock,
         }
     }
 
@@ -43,14 +52,18 @@ impl RTC {
             .ok_or("Ambiguous or nonexistent local time")?;
 
         self.base_datetime = datetime.with_timezone(&datetime.offset().fix());
-        self.base_instant = Instant::now();
+        self.base_instant = self.clock.now();
         Ok(())
     }
 
+    /// Jump the simulated clock forward, or backward for negative durations
+    pub fn advance(&mut self, duration: chrono::Duration) {
+        self.base_datetime += duration;
+    }
+
     /// Get the current simulated time
-    #[allow(dead_code)]
     pub fn now(&self) -> DateTime<FixedOffset> {
-        let elapsed = self.base_instant.elapsed();
+        let elapsed = self.clock.now() - self.base_instant;
         self.base_datetime + chrono::Duration::from_std(elapsed).unwrap()
     }
 }
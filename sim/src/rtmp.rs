@@ -0,0 +1,622 @@
+//! Just enough of RTMP to publish a stream to a server, and to receive one in tests.
+//! Frames are sent with the Screen Video codec, made of zlib blocks stored without compression
+use crate::clock::Clock;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::io;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::time::Instant;
+
+pub const DEFAULT_PORT: u16 = 1935;
+const HANDSHAKE_SIZE: usize = 1536;
+// Chunk size announced to the peer for the messages we send
+const CHUNK_SIZE: usize = 4096;
+// Side of the square blocks of a Screen Video frame, a multiple of 16
+const BLOCK_SIZE: usize = 64;
+
+// Message types
+const MSG_SET_CHUNK_SIZE: u8 = 1;
+const MSG_VIDEO: u8 = 9;
+const MSG_DATA: u8 = 18;
+const MSG_COMMAND: u8 = 20;
+
+// Chunk streams used for each kind of message
+const CSID_CONTROL: u8 = 2;
+const CSID_COMMAND: u8 = 3;
+const CSID_VIDEO: u8 = 6;
+
+// First byte of a video message: key frame, Screen Video codec
+const SCREEN_VIDEO_KEY_FRAME: u8 = 0x13;
+
+/// Where and how the frames of the main camera are streamed
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct RtmpConfig {
+    // rtmp://host[:port]/app
+    pub url: String,
+    pub key: String,
+    // Size of the streamed frames
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Default for RtmpConfig {
+    fn default() -> Self {
+        RtmpConfig {
+            url: "".to_string(),
+            key: "".to_string(),
+            width: 1280,
+            height: 720,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Message {
+    pub msg_type: u8,
+    pub stream_id: u32,
+    pub timestamp: u32,
+    pub payload: Vec<u8>,
+}
+
+fn write_amf_string(buf: &mut Vec<u8>, s: &str) {
+    buf.extend_from_slice(&(s.len() as u16).to_be_bytes());
+    buf.extend_from_slice(s.as_bytes());
+}
+
+fn write_amf(buf: &mut Vec<u8>, value: &Value) {
+    match value {
+        Value::Null => buf.push(0x05),
+        Value::Bool(b) => buf.extend_from_slice(&[0x01, *b as u8]),
+        Value::Number(n) => {
+            buf.push(0x00);
+            buf.extend_from_slice(&n.as_f64().unwrap_or(0.0).to_be_bytes());
+        }
+        Value::String(s) => {
+            buf.push(0x02);
+            write_amf_string(buf, s);
+        }
+        Value::Object(map) => {
+            buf.push(0x03);
+            for (key, value) in map {
+                write_amf_string(buf, key);
+                write_amf(buf, value);
+            }
+            buf.extend_from_slice(&[0, 0, 0x09]);
+        }
+        Value::Array(items) => {
+            buf.push(0x0a);
+            buf.extend_from_slice(&(items.len() as u32).to_be_bytes());
+            for item in items {
+                write_amf(buf, item);
+            }
+        }
+    }
+}
+
+/// Encode values in AMF0, one after the other like the arguments of a command
+pub fn encode_amf(values: &[Value]) -> Vec<u8> {
+    let mut buf = Vec::new();
+    for value in values {
+        write_amf(&mut buf, value);
+    }
+    buf
+}
+
+fn read_bytes<'a>(data: &'a [u8], pos: &mut usize, len: usize) -> Option<&'a [u8]> {
+    let bytes = data.get(*pos..*pos + len)?;
+    *pos += len;
+    Some(bytes)
+}
+
+fn read_amf_string(data: &[u8], pos: &mut usize, long: bool) -> Option<String> {
+    let len = if long {
+        u32::from_be_bytes(read_bytes(data, pos, 4)?.try_into().ok()?) as usize
+    } else {
+        u16::from_be_bytes(read_bytes(data, pos, 2)?.try_into().ok()?) as usize
+    };
+    Some(String::from_utf8_lossy(read_bytes(data, pos, len)?).into_owned())
+}
+
+fn read_amf_properties(data: &[u8], pos: &mut usize) -> Option<Value> {
+    let mut map = Map::new();
+    loop {
+        let key = read_amf_string(data, pos, false)?;
+        if key.is_empty() && data.get(*pos) == Some(&0x09) {
+            *pos += 1;
+            return Some(Value::Object(map));
+        }
+        let value = read_amf(data, pos)?;
+        map.insert(key, value);
+    }
+}
+
+fn read_amf(data: &[u8], pos: &mut usize) -> Option<Value> {
+    let marker = *read_bytes(data, pos, 1)?.first()?;
+    match marker {
+        0x00 => Some(json!(f64::from_be_bytes(read_bytes(data, pos, 8)?.try_into().ok()?))),
+        0x01 => Some(json!(read_bytes(data, pos, 1)?[0] != 0)),
+        0x02 => Some(json!(read_amf_string(data, pos, false)?)),
+        0x03 => read_amf_properties(data, pos),
+        0x05 | 0x06 => Some(Value::Null),
+        0x08 => {
+            read_bytes(data, pos, 4)?;
+            read_amf_properties(data, pos)
+        }
+        0x0a => {
+            let count = u32::from_be_bytes(read_bytes(data, pos, 4)?.try_into().ok()?);
+            (0..count).map(|_| read_amf(data, pos)).collect::<Option<Vec<_>>>().map(Value::Array)
+        }
+        0x0c => Some(json!(read_amf_string(data, pos, true)?)),
+        _ => None,
+    }
+}
+
+/// Decode AMF0 values until the end of the data or the first unsupported type
+pub fn decode_amf(data: &[u8]) -> Vec<Value> {
+    let mut pos = 0;
+    let mut values = Vec::new();
+    while pos < data.len() {
+        match read_amf(data, &mut pos) {
+            Some(value) => values.push(value),
+            None => break,
+        }
+    }
+    values
+}
+
+/// Send a message as chunks of `CHUNK_SIZE` bytes
+pub async fn write_message<W: AsyncWrite + Unpin>(writer: &mut W, csid: u8, message: &Message) -> io::Result<()> {
+    let extended = message.timestamp >= 0xffffff;
+    let timestamp = if extended { 0xffffff } else { message.timestamp };
+
+    let mut buf = Vec::with_capacity(message.payload.len() + 16 + message.payload.len() / CHUNK_SIZE * 5);
+    buf.push(csid & 0x3f);
+    buf.extend_from_slice(&timestamp.to_be_bytes()[1..]);
+    buf.extend_from_slice(&(message.payload.len() as u32).to_be_bytes()[1..]);
+    buf.push(message.msg_type);
+    buf.extend_from_slice(&message.stream_id.to_le_bytes());
+    if extended {
+        buf.extend_from_slice(&message.timestamp.to_be_bytes());
+    }
+    for (index, chunk) in message.payload.chunks(CHUNK_SIZE).enumerate() {
+        if index > 0 {
+            buf.push(0xc0 | (csid & 0x3f));
+            if extended {
+                buf.extend_from_slice(&message.timestamp.to_be_bytes());
+            }
+        }
+        buf.extend_from_slice(chunk);
+    }
+    writer.write_all(&buf).await
+}
+
+async fn read_u24<R: AsyncRead + Unpin>(reader: &mut R) -> io::Result<u32> {
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes[1..]).await?;
+    Ok(u32::from_be_bytes(bytes))
+}
+
+#[derive(Debug, Default)]
+struct ChunkHeader {
+    timestamp: u32,
+    delta: u32,
+    length: usize,
+    msg_type: u8,
+    stream_id: u32,
+    extended: bool,
+}
+
+/// Reassemble the messages sent by the peer from their chunks
+#[derive(Debug)]
+pub struct ChunkReader {
+    chunk_size: usize,
+    headers: HashMap<u32, ChunkHeader>,
+    partial: HashMap<u32, Vec<u8>>,
+}
+
+impl ChunkReader {
+    pub fn new() -> Self {
+        ChunkReader {
+            chunk_size: 128,
+            headers: HashMap::new(),
+            partial: HashMap::new(),
+        }
+    }
+
+    pub async fn read_message<R: AsyncRead + Unpin>(&mut self, reader: &mut R) -> io::Result<Message> {
+        loop {
+            let basic = reader.read_u8().await?;
+            let fmt = basic >> 6;
+            let csid = match basic & 0x3f {
+                0 => 64 + reader.read_u8().await? as u32,
+                1 => 64 + reader.read_u16_le().await? as u32,
+                csid => csid as u32,
+            };
+
+            let header = self.headers.entry(csid).or_default();
+            let partial = self.partial.entry(csid).or_default();
+            if fmt < 3 {
+                let timestamp = read_u24(reader).await?;
+                if fmt < 2 {
+                    header.length = read_u24(reader).await? as usize;
+                    header.msg_type = reader.read_u8().await?;
+                }
+                if fmt == 0 {
+                    header.stream_id = reader.read_u32_le().await?;
+                }
+                header.extended = timestamp == 0xffffff;
+                let timestamp = if header.extended { reader.read_u32().await? } else { timestamp };
+                if fmt == 0 {
+                    header.timestamp = timestamp;
+                    header.delta = 0;
+                } else {
+                    header.timestamp = header.timestamp.wrapping_add(timestamp);
+                    header.delta = timestamp;
+                }
+            } else {
+                if header.extended {
+                    reader.read_u32().await?;
+                }
+                // A new message with the header of the last one
+                if partial.is_empty() {
+                    header.timestamp = header.timestamp.wrapping_add(header.delta);
+                }
+            }
+
+            let start = partial.len();
+            let len = (header.length - start).min(self.chunk_size);
+            partial.resize(start + len, 0);
+            reader.read_exact(&mut partial[start..]).await?;
+            if partial.len() < header.length {
+                continue;
+            }
+
+            let message = Message {
+                msg_type: header.msg_type,
+                stream_id: header.stream_id,
+                timestamp: header.timestamp,
+                payload: std::mem::take(partial),
+            };
+            if message.msg_type == MSG_SET_CHUNK_SIZE && message.payload.len() >= 4 {
+                let size = u32::from_be_bytes(message.payload[..4].try_into().unwrap()) & 0x7fffffff;
+                self.chunk_size = (size as usize).max(1);
+            }
+            return Ok(message);
+        }
+    }
+}
+
+fn random_block() -> Vec<u8> {
+    let mut block = vec![0u8; HANDSHAKE_SIZE];
+    block[8..].iter_mut().for_each(|byte| *byte = rand::random());
+    block
+}
+
+async fn client_handshake(stream: &mut TcpStream) -> io::Result<()> {
+    let mut c0c1 = vec![3u8];
+    c0c1.extend(random_block());
+    stream.write_all(&c0c1).await?;
+
+    let mut s0s1 = vec![0u8; 1 + HANDSHAKE_SIZE];
+    stream.read_exact(&mut s0s1).await?;
+    if s0s1[0] != 3 {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "unsupported RTMP version"));
+    }
+    let mut s2 = vec![0u8; HANDSHAKE_SIZE];
+    stream.read_exact(&mut s2).await?;
+    stream.write_all(&s0s1[1..]).await
+}
+
+async fn server_handshake(stream: &mut TcpStream) -> io::Result<()> {
+    let mut c0c1 = vec![0u8; 1 + HANDSHAKE_SIZE];
+    stream.read_exact(&mut c0c1).await?;
+    if c0c1[0] != 3 {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "unsupported RTMP version"));
+    }
+    let mut s0s1s2 = vec![3u8];
+    s0s1s2.extend(random_block());
+    s0s1s2.extend_from_slice(&c0c1[1..]);
+    stream.write_all(&s0s1s2).await?;
+
+    let mut c2 = vec![0u8; HANDSHAKE_SIZE];
+    stream.read_exact(&mut c2).await?;
+    Ok(())
+}
+
+// Tell the peer the messages we send are split in chunks of `CHUNK_SIZE` bytes
+async fn send_chunk_size(stream: &mut TcpStream) -> io::Result<()> {
+    let message = Message {
+        msg_type: MSG_SET_CHUNK_SIZE,
+        stream_id: 0,
+        timestamp: 0,
+        payload: (CHUNK_SIZE as u32).to_be_bytes().to_vec(),
+    };
+    write_message(stream, CSID_CONTROL, &message).await
+}
+
+async fn send_command(stream: &mut TcpStream, stream_id: u32, values: &[Value]) -> io::Result<()> {
+    let message = Message {
+        msg_type: MSG_COMMAND,
+        stream_id,
+        timestamp: 0,
+        payload: encode_amf(values),
+    };
+    write_message(stream, CSID_COMMAND, &message).await
+}
+
+/// Split `rtmp://host[:port]/app` in its address and application
+pub fn parse_url(url: &str) -> Result<(String, u16, String), String> {
+    let rest = url.strip_prefix("rtmp://").ok_or("invalid RTMP URL")?;
+    let (address, app) = rest.split_once('/').ok_or("invalid RTMP URL")?;
+    let (host, port) = match address.rsplit_once(':') {
+        Some((host, port)) => (host, port.parse().map_err(|_| "invalid RTMP port")?),
+        None => (address, DEFAULT_PORT),
+    };
+    if host.is_empty() || app.is_empty() {
+        return Err("invalid RTMP URL".to_string());
+    }
+    Ok((host.to_string(), port, app.trim_end_matches('/').to_string()))
+}
+
+/// Stream published to an RTMP server
+pub struct Publisher {
+    stream: TcpStream,
+    stream_id: u32,
+    // Frames are timed on the clock of the simulator, from the start of the stream
+    clock: Clock,
+    start: Instant,
+}
+
+impl Publisher {
+    /// Connect to the application of `url` and publish the stream `key` live
+    pub async fn connect(url: &str, key: &str, clock: Clock) -> Result<Self, String> {
+        let (host, port, app) = parse_url(url)?;
+        let mut stream = TcpStream::connect((host.as_str(), port)).await.map_err(|e| e.to_string())?;
+        client_handshake(&mut stream).await.map_err(|e| e.to_string())?;
+        let mut reader = ChunkReader::new();
+
+        send_chunk_size(&mut stream).await.map_err(|e| e.to_string())?;
+
+        let connect = json!({ "app": app, "type": "nonprivate", "flashVer": "FMLE/3.0", "tcUrl": url });
+        send_command(&mut stream, 0, &[json!("connect"), json!(1.0), connect]).await.map_err(|e| e.to_string())?;
+        Self::wait_result(&mut stream, &mut reader, 1.0).await?;
+
+        send_command(&mut stream, 0, &[json!("createStream"), json!(2.0), Value::Null]).await.map_err(|e| e.to_string())?;
+        let result = Self::wait_result(&mut stream, &mut reader, 2.0).await?;
+        let stream_id = result.get(3).and_then(Value::as_f64).ok_or("invalid createStream result")? as u32;
+
+        let publish = [json!("publish"), json!(0.0), Value::Null, json!(key), json!("live")];
+        send_command(&mut stream, stream_id, &publish).await.map_err(|e| e.to_string())?;
+        loop {
+            let message = reader.read_message(&mut stream).await.map_err(|e| e.to_string())?;
+            if message.msg_type != MSG_COMMAND {
+                continue;
+            }
+            let values = decode_amf(&message.payload);
+            if values.first().and_then(Value::as_str) != Some("onStatus") {
+                continue;
+            }
+            let info = values.get(3).cloned().unwrap_or_default();
+            match info["code"].as_str() {
+                Some("NetStream.Publish.Start") => break,
+                code if info["level"] == "error" => return Err(code.unwrap_or("publish failed").to_string()),
+                _ => {}
+            }
+        }
+
+        Ok(Publisher {
+            stream,
+            stream_id,
+            start: clock.now(),
+            clock,
+        })
+    }
+
+    // Read the messages of the server until the answer to the command `transaction`
+    async fn wait_result(stream: &mut TcpStream, reader: &mut ChunkReader, transaction: f64) -> Result<Vec<Value>, String> {
+        loop {
+            let message = reader.read_message(stream).await.map_err(|e| e.to_string())?;
+            if message.msg_type != MSG_COMMAND {
+                continue;
+            }
+            let values = decode_amf(&message.payload);
+            if values.get(1).and_then(Value::as_f64) != Some(transaction) {
+                continue;
+            }
+            match values.first().and_then(Value::as_str) {
+                Some("_result") => return Ok(values),
+                Some("_error") => {
+                    let info = values.get(3).cloned().unwrap_or_default();
+                    return Err(info["code"].as_str().unwrap_or("command failed").to_string());
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Describe the video before its first frame
+    pub async fn send_metadata(&mut self, width: u32, height: u32, fps: f64) -> io::Result<()> {
+        let metadata = json!({ "width": width, "height": height, "framerate": fps, "videocodecid": 3.0 });
+        let message = Message {
+            msg_type: MSG_DATA,
+            stream_id: self.stream_id,
+            timestamp: 0,
+            payload: encode_amf(&[json!("@setDataFrame"), json!("onMetaData"), metadata]),
+        };
+        write_message(&mut self.stream, CSID_COMMAND, &message).await
+    }
+
+    /// Send a frame of 8 bits grey pixels, from the top row
+    pub async fn send_frame(&mut self, pixels: &[u8], width: u32, height: u32) -> io::Result<()> {
+        let message = Message {
+            msg_type: MSG_VIDEO,
+            stream_id: self.stream_id,
+            timestamp: (self.clock.now() - self.start).as_millis() as u32,
+            payload: encode_screen_video(pixels, width as usize, height as usize),
+        };
+        write_message(&mut self.stream, CSID_VIDEO, &message).await
+    }
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in data.chunks(5552) {
+        for byte in chunk {
+            a += *byte as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    (b << 16) | a
+}
+
+// Wrap data in a zlib stream of stored blocks
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x78, 0x01];
+    let mut blocks = data.chunks(0xffff).peekable();
+    if blocks.peek().is_none() {
+        out.extend_from_slice(&[0x01, 0x00, 0x00, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        out.push(if blocks.peek().is_none() { 0x01 } else { 0x00 });
+        out.extend_from_slice(&(block.len() as u16).to_le_bytes());
+        out.extend_from_slice(&(!(block.len() as u16)).to_le_bytes());
+        out.extend_from_slice(block);
+    }
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+/// Key frame of the Screen Video codec, from 8 bits grey pixels starting with the top row
+pub fn encode_screen_video(pixels: &[u8], width: usize, height: usize) -> Vec<u8> {
+    let block_code = (BLOCK_SIZE / 16 - 1) as u16;
+    let mut out = vec![SCREEN_VIDEO_KEY_FRAME];
+    out.extend_from_slice(&((block_code << 12) | width as u16).to_be_bytes());
+    out.extend_from_slice(&((block_code << 12) | height as u16).to_be_bytes());
+
+    // Blocks go from the bottom left corner to the top right, their rows from the bottom
+    for block_bottom in (0..height.div_ceil(BLOCK_SIZE)).map(|row| height - row * BLOCK_SIZE) {
+        let block_top = block_bottom.saturating_sub(BLOCK_SIZE);
+        for block_left in (0..width).step_by(BLOCK_SIZE) {
+            let block_right = (block_left + BLOCK_SIZE).min(width);
+            let mut bgr = Vec::with_capacity((block_right - block_left) * (block_bottom - block_top) * 3);
+            for y in (block_top..block_bottom).rev() {
+                for &value in &pixels[y * width + block_left..y * width + block_right] {
+                    bgr.extend_from_slice(&[value, value, value]);
+                }
+            }
+            let data = zlib_stored(&bgr);
+            out.extend_from_slice(&(data.len() as u16).to_be_bytes());
+            out.extend_from_slice(&data);
+        }
+    }
+    out
+}
+
+/// What an `RtmpSink` received
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RtmpSinkStats {
+    pub app: String,
+    pub stream_key: String,
+    pub publishing: bool,
+    // Video frames received, and the size of the last one
+    pub frames: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// RTMP server on localhost accepting any published stream, to check what the simulator streams
+pub struct RtmpSink {
+    port: u16,
+    stats: Arc<Mutex<RtmpSinkStats>>,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl RtmpSink {
+    /// Listen on `port`, or on a free port when 0
+    pub async fn bind(port: u16) -> io::Result<Self> {
+        let listener = TcpListener::bind(("127.0.0.1", port)).await?;
+        let port = listener.local_addr()?.port();
+        let stats = Arc::new(Mutex::new(RtmpSinkStats::default()));
+
+        let task_stats = stats.clone();
+        let task = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(serve_publisher(stream, task_stats.clone()));
+            }
+        });
+
+        Ok(RtmpSink { port, stats, task })
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// URL of the `live` application of the sink
+    pub fn url(&self) -> String {
+        format!("rtmp://127.0.0.1:{}/live", self.port)
+    }
+
+    pub fn stats(&self) -> RtmpSinkStats {
+        self.stats.lock().unwrap().clone()
+    }
+}
+
+impl Drop for RtmpSink {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn serve_publisher(mut stream: TcpStream, stats: Arc<Mutex<RtmpSinkStats>>) {
+    if server_handshake(&mut stream).await.is_err() || send_chunk_size(&mut stream).await.is_err() {
+        return;
+    }
+    let mut reader = ChunkReader::new();
+    while let Ok(message) = reader.read_message(&mut stream).await {
+        match message.msg_type {
+            MSG_COMMAND => {
+                let values = decode_amf(&message.payload);
+                let transaction = values.get(1).cloned().unwrap_or(json!(0.0));
+                let reply = match values.first().and_then(Value::as_str) {
+                    Some("connect") => {
+                        stats.lock().unwrap().app = values.get(2).and_then(|v| v["app"].as_str()).unwrap_or_default().to_string();
+                        let properties = json!({ "fmsVer": "FMS/3,0,1,123", "capabilities": 31.0 });
+                        let info = json!({ "level": "status", "code": "NetConnection.Connect.Success", "objectEncoding": 0.0 });
+                        Some((0, vec![json!("_result"), transaction, properties, info]))
+                    }
+                    Some("createStream") => Some((0, vec![json!("_result"), transaction, Value::Null, json!(1.0)])),
+                    Some("publish") => {
+                        let mut stats = stats.lock().unwrap();
+                        stats.stream_key = values.get(3).and_then(Value::as_str).unwrap_or_default().to_string();
+                        stats.publishing = true;
+                        let info = json!({ "level": "status", "code": "NetStream.Publish.Start", "description": "Start publishing" });
+                        Some((message.stream_id, vec![json!("onStatus"), json!(0.0), Value::Null, info]))
+                    }
+                    Some("deleteStream") => {
+                        stats.lock().unwrap().publishing = false;
+                        None
+                    }
+                    _ => None,
+                };
+                if let Some((stream_id, values)) = reply {
+                    if send_command(&mut stream, stream_id, &values).await.is_err() {
+                        break;
+                    }
+                }
+            }
+            MSG_VIDEO if message.payload.len() >= 5 => {
+                let mut stats = stats.lock().unwrap();
+                stats.frames += 1;
+                stats.width = (u16::from_be_bytes([message.payload[1], message.payload[2]]) & 0x0fff) as u32;
+                stats.height = (u16::from_be_bytes([message.payload[3], message.payload[4]]) & 0x0fff) as u32;
+            }
+            _ => {}
+        }
+    }
+    stats.lock().unwrap().publishing = false;
+}
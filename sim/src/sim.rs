@@ -1,21 +1,36 @@
 use crate::rpc::protocol::{ASIAirRequest, ASIAirResponse};
 use crate::rpc::{
-    asiair_tcp_4500_handler, asiair_tcp_4800_handler, asiair_tcp_handler, asiair_udp_handler,
+    asiair_tcp_4500_handler, asiair_tcp_4800_handler, asiair_tcp_handler, asiair_udp_handler, watch_meridian,
 };
+use crate::clock;
+use crate::control;
+use crate::fault;
+use crate::focuser;
+use crate::frame;
+use crate::guider;
+use crate::mount;
+use crate::pi;
+use crate::recorder;
 use crate::rtc;
+use crate::rtmp;
+use crate::solver;
+use crate::stack;
+use crate::storage;
 use local_ip_address::local_ip;
 use once_cell::sync::Lazy;
 use std::collections::HashMap;
+use std::str::FromStr;
 use std::sync::{Arc, Mutex};
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::{TcpListener, UdpSocket};
-use tokio::sync::{mpsc, watch};
+use tokio::sync::{broadcast, mpsc, watch};
 use serde_json::Value;
 
 use super::ASIAirSim;
 
 #[allow(dead_code)]
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum ASIAirPage {
     Preview,
     Focus,
@@ -40,6 +55,23 @@ impl ASIAirPage {
     }
 }
 
+impl FromStr for ASIAirPage {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "preview" => Ok(ASIAirPage::Preview),
+            "focus" => Ok(ASIAirPage::Focus),
+            "pa" => Ok(ASIAirPage::PA),
+            "stack" => Ok(ASIAirPage::Stack),
+            "autosave" => Ok(ASIAirPage::Autosave),
+            "plan" => Ok(ASIAirPage::Plan),
+            "rmtp" => Ok(ASIAirPage::RMTP),
+            _ => Err(()),
+        }
+    }
+}
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct AnnotateState {
     pub is_working: bool,
@@ -72,14 +104,14 @@ impl ExposureModes {
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub enum CaptureStatus {
     Idle,
-    // Working,
+    Working,
 }
 
 impl CaptureStatus {
     pub fn as_str(&self) -> &str {
         match self {
             CaptureStatus::Idle => "idle",
-            // CaptureStatus::Working => "working",
+            CaptureStatus::Working => "working",
         }
     }
 }
@@ -102,7 +134,7 @@ pub struct AutoGotoState {
 }
 
 #[allow(dead_code)]
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
 pub enum FrameType {
     Light,
     Dark,
@@ -121,6 +153,20 @@ impl FrameType {
     }
 }
 
+impl FromStr for FrameType {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "light" => Ok(FrameType::Light),
+            "dark" => Ok(FrameType::Dark),
+            "flat" => Ok(FrameType::Flat),
+            "bias" => Ok(FrameType::Bias),
+            _ => Err(()),
+        }
+    }
+}
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct StackState {
     pub is_working: bool,
@@ -145,8 +191,18 @@ pub struct MeridFlipState {
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct AutoFocusPoint {
+    pub position: i32,
+    // Star half flux radius in pixels
+    pub hfr: f64,
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
 pub struct AutoFocusResult {
-    // Fields for AutoFocusResult
+    // Points of the V-curve, in the order they were measured
+    pub points: Vec<AutoFocusPoint>,
+    pub best_position: i32,
+    pub best_hfr: f64,
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -157,7 +213,6 @@ pub struct AutoFocuserReason {
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct AutoFocusState {
-    #[allow(dead_code)]
     pub result: AutoFocusResult,
     pub is_working: bool,
     pub focuser_opened: bool,
@@ -176,16 +231,24 @@ pub struct AviRecordState {
     pub lapse_sec: u32,
     pub fps: f32,
     pub write_file_fps: f32,
+    // Frames written to the video file
+    pub frame_count: u32,
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct RtmpState {
     pub is_working: bool,
+    // Frames sent to the server since the stream started
+    pub frames: u32,
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct AutoExpState {
     pub is_working: bool,
+    // Exposure last tried, the one chosen once complete, and the mean of its frame
+    pub exposure_us: u64,
+    pub mean_adu: f64,
+    pub iteration: u32,
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -208,6 +271,41 @@ pub struct FormatDriveState {
     pub is_working: bool,
 }
 
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct PlanExposure {
+    pub filter: String,
+    pub exp_us: u64,
+    pub count: u32,
+    #[serde(default)]
+    pub done: u32,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct PlanTarget {
+    pub name: String,
+    // Right ascension in hours
+    pub ra: f64,
+    // Declination in degrees
+    pub dec: f64,
+    // RFC 3339 time window in which the target can be imaged
+    pub start_time: String,
+    pub end_time: String,
+    pub exposures: Vec<PlanExposure>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Plan {
+    pub name: String,
+    pub targets: Vec<PlanTarget>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct PlanState {
+    pub is_working: bool,
+    pub plan_name: String,
+    pub target_name: String,
+}
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct AppState {
     pub page: ASIAirPage,
@@ -228,6 +326,7 @@ pub struct AppState {
     pub batch_stack: BatchStackState,
     pub demonstrate: DemonstrateState,
     pub format_drive: FormatDriveState,
+    pub plan: PlanState,
 }
 
 impl Default for AppState {
@@ -266,7 +365,7 @@ impl Default for AppState {
             },
             merid_flip: MeridFlipState { is_working: false },
             auto_focus: AutoFocusState {
-                result: AutoFocusResult {},
+                result: AutoFocusResult::default(),
                 is_working: false,
                 focuser_opened: false,
                 reason: AutoFocuserReason {
@@ -283,13 +382,27 @@ impl Default for AppState {
                 lapse_sec: 0,
                 fps: 10.0,
                 write_file_fps: 0.0,
+                frame_count: 0,
+            },
+            rtmp: RtmpState {
+                is_working: false,
+                frames: 0,
+            },
+            auto_exp: AutoExpState {
+                is_working: false,
+                exposure_us: 0,
+                mean_adu: 0.0,
+                iteration: 0,
             },
-            rtmp: RtmpState { is_working: false },
-            auto_exp: AutoExpState { is_working: false },
             restart_guide: RestartGuideState { is_working: false },
             batch_stack: BatchStackState { is_working: false },
             demonstrate: DemonstrateState { is_working: false },
             format_drive: FormatDriveState { is_working: false },
+            plan: PlanState {
+                is_working: false,
+                plan_name: "".to_string(),
+                target_name: "".to_string(),
+            },
         }
     }
 }
@@ -320,6 +433,11 @@ pub struct AppSetting {
     pub goto_target_ra: f64,
     pub goto_target_name: String,
     pub guide_camera_name: String,
+    // Focal length of the main telescope in millimeters
+    pub focal_length: f64,
+    // Flip the mount automatically once it tracked this many minutes past the meridian
+    pub auto_merid_flip: bool,
+    pub merid_flip_minutes: f64,
 }
 
 impl Default for AppSetting {
@@ -349,10 +467,68 @@ impl Default for AppSetting {
             goto_target_ra: 0.0,
             goto_target_name: "".to_string(),
             guide_camera_name: "ZWO ASI462MM".to_string(),
+            focal_length: 400.0,
+            auto_merid_flip: false,
+            merid_flip_minutes: 5.0,
+        }
+    }
+}
+
+// Languages of the user interface of the device
+pub const LANGUAGES: [&str; 10] = ["en", "zh_CN", "zh_TW", "ja", "ko", "de", "fr", "es", "it", "ru"];
+
+// System settings of the device, read with get_setting and changed with set_setting
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Settings {
+    pub lang: String,
+    // "C" or "F"
+    pub temp_unit: String,
+    pub beep: bool,
+    // Minutes of inactivity before the device shuts down, never when 0
+    pub auto_shutdown_min: u32,
+    // "2.4G" or "5G"
+    pub wifi_band: String,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            lang: "en".to_string(),
+            temp_unit: "C".to_string(),
+            beep: true,
+            auto_shutdown_min: 0,
+            wifi_band: "5G".to_string(),
         }
     }
 }
 
+impl Settings {
+    /// Apply the keys of `changes`, leaving the other settings as they are. Nothing changes
+    /// when a key is unknown or a value invalid
+    pub fn update(&mut self, changes: &serde_json::Map<String, Value>) -> Result<(), String> {
+        let mut settings = serde_json::to_value(&*self).unwrap();
+        for (key, value) in changes {
+            match settings.get_mut(key) {
+                Some(setting) => *setting = value.clone(),
+                None => return Err(format!("unknown setting: {}", key)),
+            }
+        }
+        let settings: Settings = serde_json::from_value(settings).map_err(|e| e.to_string())?;
+
+        if !LANGUAGES.contains(&settings.lang.as_str()) {
+            return Err(format!("unsupported language: {}", settings.lang));
+        }
+        if !["C", "F"].contains(&settings.temp_unit.as_str()) {
+            return Err(format!("invalid temperature unit: {}", settings.temp_unit));
+        }
+        if !["2.4G", "5G"].contains(&settings.wifi_band.as_str()) {
+            return Err(format!("invalid wifi band: {}", settings.wifi_band));
+        }
+        *self = settings;
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct ConnectedCamera {
     pub name: String,
@@ -432,8 +608,14 @@ pub struct ASIAirState {
     pub ssid: String,
     pub connect_lock: bool,
 
+    // Time source of the RTC, the exposures, the thermal model and the periodic events
+    pub clock: clock::Clock,
     pub rtc: rtc::RTC,
-    pub language: String,
+    pub settings: Settings,
+    // CPU temperature and power supply of the board, reported by PiStatus events
+    pub pi: pi::Pi,
+    // Network trouble injected in the responses and events of each port
+    pub faults: fault::FaultInjector,
 
     // get/set_app_state
     pub app_state: AppState,
@@ -446,6 +628,162 @@ pub struct ASIAirState {
     pub camera_state: CameraState,
     pub camera_controls: CameraControls,
     pub camera_bin: u32,
+    // Frame type of the last exposure started on the main camera
+    pub frame_type: FrameType,
+    // Handle to the in-flight exposure, so it can be aborted by stop_exposure
+    pub exposure_task: Option<tokio::task::AbortHandle>,
+    // Last image of the main camera, rendered when downloaded
+    pub last_frame: Option<frame::FrameParams>,
+    pub frame_count: u64,
+
+    pub plans: Vec<Plan>,
+    pub plan_task: Option<tokio::task::AbortHandle>,
+
+    pub mount: mount::Mount,
+
+    pub solver: solver::SolverConfig,
+    // Outcome of the last plate solve, the solution or the reason it failed
+    pub solve_result: Option<Result<Value, String>>,
+    pub solve_task: Option<tokio::task::AbortHandle>,
+    // Outcome of the last annotation, the solution with the objects it contains or the reason it failed
+    pub annotate_result: Option<Result<Value, String>>,
+    pub annotate_task: Option<tokio::task::AbortHandle>,
+    pub pa_task: Option<tokio::task::AbortHandle>,
+
+    pub focuser: focuser::Focuser,
+    pub auto_focus_task: Option<tokio::task::AbortHandle>,
+
+    pub guider: guider::Guider,
+    pub guide_task: Option<tokio::task::AbortHandle>,
+    pub merid_flip_task: Option<tokio::task::AbortHandle>,
+
+    // Live stack of the light frames, fed by `complete_frame` while stacking
+    pub stacker: Arc<Mutex<stack::Stacker>>,
+    pub stack_tx: Option<mpsc::UnboundedSender<frame::FrameParams>>,
+    pub stack_task: Option<tokio::task::AbortHandle>,
+    // Bumped each time a stacking session starts or ends, so a late task leaves the counters alone
+    pub stack_generation: u64,
+
+    // Images saved by `complete_frame`, and the removable storages they can be exported to
+    pub storage: storage::Storage,
+    pub export_task: Option<tokio::task::AbortHandle>,
+
+    // Video being recorded from the main camera, and whether the camera is plugged in a USB3 port
+    pub recording: Option<recorder::Recording>,
+    pub record_task: Option<tokio::task::AbortHandle>,
+    pub usb3_host: bool,
+
+    pub auto_exp_task: Option<tokio::task::AbortHandle>,
+    // Brightest star of the last find star, or the reason none was found
+    pub find_star_result: Option<Result<Value, String>>,
+    pub find_star_task: Option<tokio::task::AbortHandle>,
+
+    // Server the frames of the main camera are streamed to
+    pub rtmp_config: rtmp::RtmpConfig,
+    pub rtmp_task: Option<tokio::task::AbortHandle>,
+
+    // Events generated by background tasks, forwarded to every connected client
+    pub events_tx: broadcast::Sender<Value>,
+}
+
+impl ASIAirState {
+    /// Publish an event to all the clients connected on port 4700
+    pub fn emit_event(&self, event: Value) {
+        let _ = self.events_tx.send(self.stamp_event(event));
+    }
+
+    /// Time of the RTC in the format of the Timestamp of the responses and events
+    pub fn timestamp(&self) -> String {
+        self.rtc.now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+    }
+
+    /// Set the Timestamp of an event to the current time of the RTC
+    pub fn stamp_event(&self, mut event: Value) -> Value {
+        if let Some(event) = event.as_object_mut() {
+            event.insert("Timestamp".to_string(), Value::String(self.timestamp()));
+        }
+        event
+    }
+
+    fn emit_camera_list_event(&self, change: &str, name: &str) {
+        self.emit_event(serde_json::json!({
+            "Event": "CameraListChange",
+            "cameras": self.connected_cameras,
+            change: name,
+        }));
+    }
+
+    /// Connect a camera to the USB ports, it gets the next free id
+    pub fn plug_camera(&mut self, name: &str) -> Result<(), String> {
+        if self.connected_cameras.iter().any(|camera| camera.name == name) {
+            return Err(format!("{} is already connected", name));
+        }
+        let id = self.connected_cameras.iter().map(|camera| camera.id + 1).max().unwrap_or(0);
+        self.connected_cameras.push(ConnectedCamera {
+            name: name.to_string(),
+            id,
+            path: format!("bus1.port:1,4,{},", id + 1),
+            dslr: false,
+        });
+        self.emit_camera_list_event("plugged", name);
+        Ok(())
+    }
+
+    /// Disconnect a camera from the USB ports. The open camera is closed and its exposure fails
+    pub fn unplug_camera(&mut self, name: &str) -> Result<(), String> {
+        let index = self
+            .connected_cameras
+            .iter()
+            .position(|camera| camera.name == name)
+            .ok_or(format!("{} is not connected", name))?;
+        self.connected_cameras.remove(index);
+        self.emit_camera_list_event("unplugged", name);
+
+        if !matches!(&self.camera_state, CameraState::Idle { name: open, .. } if open == name) {
+            return Ok(());
+        }
+        if let Some(task) = self.exposure_task.take() {
+            task.abort();
+            self.app_state.capture.is_working = false;
+            self.app_state.capture.state = CaptureStatus::Idle;
+            self.emit_event(serde_json::json!({
+                "Event": "Exposure",
+                "state": "fail",
+                "error": "camera disconnected",
+            }));
+        }
+        self.camera_state = CameraState::Close;
+        self.emit_event(serde_json::json!({
+            "Event": "CameraStateChange",
+        }));
+        Ok(())
+    }
+
+    /// Parameters of a frame starting now on the main camera, each frame gets its own noise
+    pub fn capture_frame(&mut self, frame_type: FrameType, exposure_us: u64) -> Result<frame::FrameParams, String> {
+        self.frame_count += 1;
+        frame::FrameParams::capture(self, frame_type, exposure_us, self.frame_count)
+    }
+
+    /// Keep a frame once its exposure is over, as the last image, in the live stack and on the storage
+    pub fn complete_frame(&mut self, frame: frame::FrameParams) {
+        if self.app_state.stack.is_working && frame.frame_type == FrameType::Light {
+            if let Some(stack_tx) = &self.stack_tx {
+                let _ = stack_tx.send(frame.clone());
+            }
+        }
+        let target = if self.app_state.plan.is_working {
+            self.app_state.plan.target_name.clone()
+        } else {
+            self.app_setting.goto_target_name.clone()
+        };
+        let start = self.rtc.now() - chrono::Duration::microseconds(frame.exposure_us as i64);
+        let temperature = self.camera_controls.temperature;
+        if let Err(err) = self.storage.save(frame.clone(), &target, temperature, start) {
+            log::warn!("Image not saved: {}", err);
+        }
+        self.last_frame = Some(frame);
+    }
 }
 
 /// The 80-byte prefix format:
@@ -565,6 +903,11 @@ pub static CAMERAS_INFO: Lazy<HashMap<&'static str, CameraInfo>> = Lazy::new(||
 
 impl ASIAirSim {
     pub fn new() -> Self {
+        Self::with_clock(clock::Clock::tokio())
+    }
+
+    /// Simulator running on `clock`. With a manual clock, time only passes with `advance`
+    pub fn with_clock(clock: clock::Clock) -> Self {
         let local_ip = local_ip().unwrap_or_else(|_| "0.0.0.0".parse().unwrap());
 
         ASIAirSim {
@@ -576,8 +919,11 @@ impl ASIAirSim {
                 model: "ZWO AirPlus-RK3568 (Linux)".to_string(),
                 ssid: "ASIAir SIM".to_string(),
                 connect_lock: false,
-                rtc: rtc::RTC::new(),
-                language: "en".to_string(),
+                rtc: rtc::RTC::with_clock(clock.clone()),
+                clock,
+                settings: Settings::default(),
+                pi: pi::Pi::new(),
+                faults: fault::FaultInjector::new(),
 
                 app_state: AppState::default(),
 
@@ -601,6 +947,44 @@ impl ASIAirSim {
                 camera_state: CameraState::Close,
                 camera_controls: CameraControls::default(),
                 camera_bin: 1,
+                frame_type: FrameType::Light,
+                exposure_task: None,
+                last_frame: None,
+                frame_count: 0,
+
+                plans: Vec::new(),
+                plan_task: None,
+
+                mount: mount::Mount::new(),
+
+                solver: solver::SolverConfig::default(),
+                solve_result: None,
+                solve_task: None,
+                annotate_result: None,
+                annotate_task: None,
+                pa_task: None,
+
+                focuser: focuser::Focuser::new(),
+                auto_focus_task: None,
+                guider: guider::Guider::new(),
+                guide_task: None,
+                merid_flip_task: None,
+                stacker: Arc::new(Mutex::new(stack::Stacker::new())),
+                stack_tx: None,
+                stack_task: None,
+                stack_generation: 0,
+                storage: storage::Storage::new(),
+                export_task: None,
+                recording: None,
+                record_task: None,
+                usb3_host: true,
+                auto_exp_task: None,
+                find_star_result: None,
+                find_star_task: None,
+                rtmp_config: rtmp::RtmpConfig::default(),
+                rtmp_task: None,
+
+                events_tx: broadcast::channel(64).0,
             })),
             shutdown_tx: None,
         }
@@ -627,6 +1011,11 @@ impl ASIAirSim {
         let mut tcp_shutdown_rx_4500 = shutdown_rx.clone();
         let mut tcp_shutdown_rx_4800 = shutdown_rx.clone();
 
+        tokio::spawn(mount::run_mount(self.state.clone(), shutdown_rx.clone()));
+        tokio::spawn(focuser::run_focuser(self.state.clone(), shutdown_rx.clone()));
+        tokio::spawn(pi::run_pi(self.state.clone(), shutdown_rx.clone()));
+        tokio::spawn(watch_meridian(self.state.clone(), shutdown_rx.clone()));
+
         tokio::spawn(async move {
             let mut buf = [0u8; 2048];
             loop {
@@ -650,14 +1039,24 @@ impl ASIAirSim {
                                                 id: req.id,
                                                 code: code as u8,
                                                 jsonrpc: "2.0".to_string(),
-                                                timestamp: "2025-05-06T00:00:00Z".to_string(),
+                                                timestamp: udp_state.lock().unwrap().timestamp(),
                                                 method: req.method.clone(),
                                                 result: Some(result),
                                                 error: None,
                                             };
 
                                             let json = serde_json::to_string(&response).unwrap();
-                                            udp_socket.send_to(json.as_bytes(), addr).await.unwrap();
+                                            let decision = udp_state.lock().unwrap().faults.decide(fault::Port::Udp4720, &req.method);
+                                            if !decision.delay.is_zero() {
+                                                tokio::time::sleep(decision.delay).await;
+                                            }
+                                            let datagram = match decision.fault {
+                                                None | Some(fault::Fault::Delay(_)) => json.as_bytes(),
+                                                Some(fault::Fault::Drop) | Some(fault::Fault::Close) => continue,
+                                                Some(fault::Fault::Truncate(len)) => &json.as_bytes()[..len.min(json.len())],
+                                                Some(fault::Fault::Malformed) => &json.as_bytes()[..json.len() / 2],
+                                            };
+                                            udp_socket.send_to(datagram, addr).await.unwrap();
                                             log::debug!("Sent UDP response to {}: {}", addr, json);
                                         }
                                         Err(err) => {
@@ -688,6 +1087,7 @@ impl ASIAirSim {
                                 let (event_tx, mut event_rx) = mpsc::channel::<Value>(32);
 
                                 let tcp_state = tcp_state.clone();
+                                let mut broadcast_rx = tcp_state.lock().unwrap().events_tx.subscribe();
                                 let mut per_connection_shutdown_rx = shutdown_rx.clone();
                                 tokio::spawn(async move {
                                     let mut buf = [0u8; 2048];
@@ -697,11 +1097,25 @@ impl ASIAirSim {
                                                 break;
                                             }
                                             Some(event) = event_rx.recv() => {
+                                                let delay = tcp_state.lock().unwrap().faults.event_delay(fault::Port::Tcp4700);
+                                                if !delay.is_zero() {
+                                                    tokio::time::sleep(delay).await;
+                                                }
                                                 let mut json = serde_json::to_string(&event).unwrap();
                                                 json.push_str("\r\n");
                                                 stream.write_all(json.as_bytes()).await.unwrap();
                                                 log::debug!("Sent Async Event to {}: {}", addr, json);
                                             }
+                                            Ok(event) = broadcast_rx.recv() => {
+                                                let delay = tcp_state.lock().unwrap().faults.event_delay(fault::Port::Tcp4700);
+                                                if !delay.is_zero() {
+                                                    tokio::time::sleep(delay).await;
+                                                }
+                                                let mut json = serde_json::to_string(&event).unwrap();
+                                                json.push_str("\r\n");
+                                                stream.write_all(json.as_bytes()).await.unwrap();
+                                                log::debug!("Sent Broadcast Event to {}: {}", addr, json);
+                                            }
                                             read_result = stream.read(&mut buf) => {
                                                 match read_result {
                                                     Ok(len) if len > 0 => {
@@ -716,7 +1130,7 @@ impl ASIAirSim {
                                                                         id: req.id,
                                                                         code: 0,
                                                                         jsonrpc: "2.0".to_string(),
-                                                                        timestamp: "2025-05-06T00:00:00Z".to_string(),
+                                                                        timestamp: String::new(),
                                                                         method: req.method.clone(),
                                                                         error: None,
                                                                         result: None,
@@ -733,9 +1147,17 @@ impl ASIAirSim {
                                                                         }
                                                                     }
 
+                                                                    // The response is stamped once handled
+                                                                    response.timestamp = tcp_state.lock().unwrap().timestamp();
                                                                     let mut json = serde_json::to_string(&response).unwrap();
                                                                     json.push_str("\r\n");
-                                                                    stream.write_all(json.as_bytes()).await.unwrap();
+                                                                    let decision = tcp_state.lock().unwrap().faults.decide(fault::Port::Tcp4700, &req.method);
+                                                                    if !decision.delay.is_zero() {
+                                                                        tokio::time::sleep(decision.delay).await;
+                                                                    }
+                                                                    if !matches!(fault::write_line(&mut stream, &json, decision.fault.as_ref()).await, Ok(true)) {
+                                                                        break;
+                                                                    }
                                                                     log::debug!("Sent TCP response to {}: {}", addr, json);
                                                                 }
                                                                 Err(err) => {
@@ -801,7 +1223,7 @@ impl ASIAirSim {
                                                                         id: req.id,
                                                                         code: 0,
                                                                         jsonrpc: "2.0".to_string(),
-                                                                        timestamp: "2025-05-06T00:00:00Z".to_string(),
+                                                                        timestamp: String::new(),
                                                                         method: req.method.clone(),
                                                                         error: None,
                                                                         result: None,
@@ -818,9 +1240,17 @@ impl ASIAirSim {
                                                                         }
                                                                     }
 
+                                                                    // The response is stamped once handled
+                                                                    response.timestamp = tcp_state.lock().unwrap().timestamp();
                                                                     let mut json = serde_json::to_string(&response).unwrap();
                                                                     json.push_str("\r\n");
-                                                                    stream.write_all(json.as_bytes()).await.unwrap();
+                                                                    let decision = tcp_state.lock().unwrap().faults.decide(fault::Port::Tcp4500, &req.method);
+                                                                    if !decision.delay.is_zero() {
+                                                                        tokio::time::sleep(decision.delay).await;
+                                                                    }
+                                                                    if !matches!(fault::write_line(&mut stream, &json, decision.fault.as_ref()).await, Ok(true)) {
+                                                                        break;
+                                                                    }
                                                                     log::debug!("Sent TCP response to {}: {}", addr, json);
                                                                 }
                                                                 Err(err) => {
@@ -893,13 +1323,16 @@ impl ASIAirSim {
                                                                             header.bin = state.camera_bin as u16;
                                                                         }
                                                                         let bytes = header.to_bytes();
+                                                                        let decision = tcp_state.lock().unwrap().faults.decide(fault::Port::Tcp4800, &req.method);
+                                                                        if !decision.delay.is_zero() {
+                                                                            tokio::time::sleep(decision.delay).await;
+                                                                        }
 
-                                                                        // Send the binary header first
-                                                                        log::debug!("Sending TCP 4800 header of size {}", bytes.len());
-                                                                        stream.write_all(&bytes).await.unwrap();
-                                                                        // Then send the binary data
-                                                                        log::debug!("Sending {} bytes of binary data", result.data.len());
-                                                                        stream.write_all(&result.data).await.unwrap();
+                                                                        // Send the binary header first, then the binary data
+                                                                        log::debug!("Sending TCP 4800 header of size {} and {} bytes of binary data", bytes.len(), result.data.len());
+                                                                        if !matches!(fault::write_binary(&mut stream, &bytes, &result.data, decision.fault.as_ref()).await, Ok(true)) {
+                                                                            break;
+                                                                        }
                                                                     } else {
                                                                         eprintln!("Failed to handle TCP 4800 request");
                                                                     }
@@ -936,6 +1369,174 @@ impl ASIAirSim {
         Ok(())
     }
 
+    /// Change how the simulated plate solver behaves
+    pub fn set_solver_config(&self, config: solver::SolverConfig) {
+        self.state.lock().unwrap().solver = config;
+    }
+
+    pub fn solver_config(&self) -> solver::SolverConfig {
+        self.state.lock().unwrap().solver.clone()
+    }
+
+    /// Change how the simulated guide camera and mount errors behave
+    pub fn set_guider_config(&self, config: guider::GuiderConfig) {
+        self.state.lock().unwrap().guider.config = config;
+    }
+
+    pub fn guider_config(&self) -> guider::GuiderConfig {
+        self.state.lock().unwrap().guider.config.clone()
+    }
+
+    /// Misalign the polar axis of the mount, in arcminutes above and east of the pole
+    pub fn set_polar_error(&self, alt: f64, az: f64) {
+        let mut state = self.state.lock().unwrap();
+        state.mount.polar_error_alt = alt;
+        state.mount.polar_error_az = az;
+    }
+
+    /// Current misalignment of the polar axis, altitude and azimuth in arcminutes
+    pub fn polar_error(&self) -> (f64, f64) {
+        let state = self.state.lock().unwrap();
+        (state.mount.polar_error_alt, state.mount.polar_error_az)
+    }
+
+    /// Turn the altitude and azimuth knobs of the mount, moving the polar axis by these amounts in arcminutes
+    pub fn adjust_polar_knobs(&self, alt: f64, az: f64) {
+        let mut state = self.state.lock().unwrap();
+        state.mount.polar_error_alt += alt;
+        state.mount.polar_error_az += az;
+    }
+
+    /// Set the ambient temperature seen by the focuser in degrees Celsius, and its drift in degrees per hour
+    pub fn set_focuser_temperature(&self, temperature: f64, drift: f64) {
+        let mut state = self.state.lock().unwrap();
+        state.focuser.temperature = temperature;
+        state.focuser.temperature_drift = drift;
+    }
+
+    /// Focuser position where stars are the sharpest at the current temperature
+    pub fn focus_position(&self) -> f64 {
+        let state = self.state.lock().unwrap();
+        state.focuser.focus_position()
+    }
+
+    /// Plug an empty removable storage of `capacity` bytes, replacing the one with the same name
+    pub fn attach_storage(&self, name: &str, capacity: u64) {
+        self.state.lock().unwrap().storage.attach(name, capacity);
+    }
+
+    /// Unplug a removable storage, an export to it fails
+    pub fn detach_storage(&self, name: &str) {
+        self.state.lock().unwrap().storage.detach(name);
+    }
+
+    /// Plug the main camera in a USB3 or a USB2 port, USB2 limits the frame rate of videos
+    pub fn set_usb3_host(&self, usb3_host: bool) {
+        self.state.lock().unwrap().usb3_host = usb3_host;
+    }
+
+    /// Change how the simulated board heats up, and trigger faults of its power supply
+    pub fn set_pi_config(&self, config: pi::PiConfig) {
+        self.state.lock().unwrap().pi.set_config(config);
+    }
+
+    pub fn pi_config(&self) -> pi::PiConfig {
+        self.state.lock().unwrap().pi.config.clone()
+    }
+
+    /// CPU temperature of the board in degrees Celsius
+    pub fn pi_temperature(&self) -> f64 {
+        self.state.lock().unwrap().pi.temperature
+    }
+
+    /// Inject network trouble in the transport, the random draws restart from the seed of `config`
+    pub fn set_fault_config(&self, config: fault::FaultConfig) {
+        self.state.lock().unwrap().faults.set_config(config);
+    }
+
+    pub fn fault_config(&self) -> fault::FaultConfig {
+        self.state.lock().unwrap().faults.config().clone()
+    }
+
+    /// Plug a camera in, it is listed after the cameras already connected
+    pub fn plug_camera(&self, name: &str) -> Result<(), String> {
+        self.state.lock().unwrap().plug_camera(name)
+    }
+
+    /// Unplug a connected camera, closing it when it is the open camera
+    pub fn unplug_camera(&self, name: &str) -> Result<(), String> {
+        self.state.lock().unwrap().unplug_camera(name)
+    }
+
+    /// Set the ambient temperature in degrees Celsius, seen by the focuser, the board and the
+    /// sensor of the main camera while its cooler is off
+    pub fn set_ambient_temperature(&self, temperature: f64) {
+        let mut state = self.state.lock().unwrap();
+        state.focuser.temperature = temperature;
+        state.pi.config.ambient = temperature;
+        if state.camera_controls.cooler_on == 0 {
+            state.camera_controls.temperature = temperature.round() as i64;
+        }
+    }
+
+    /// Jump the clock of the device forward, or backward for negative durations
+    pub fn advance_time(&self, duration: chrono::Duration) {
+        self.state.lock().unwrap().rtc.advance(duration);
+    }
+
+    /// Move the clock of the simulator forward, completing the exposures and the other tasks due
+    /// on the way. The clock has to be manual, or tokio's clock has to be paused with the `test-util`
+    /// feature on
+    pub async fn advance(&self, duration: std::time::Duration) {
+        let clock = self.state.lock().unwrap().clock.clone();
+        clock.advance(duration).await;
+    }
+
+    /// Current time of the clock of the device
+    pub fn now(&self) -> chrono::DateTime<chrono::FixedOffset> {
+        self.state.lock().unwrap().rtc.now()
+    }
+
+    /// Internal state of the simulator, for assertions
+    pub fn snapshot(&self) -> Value {
+        let state = self.state.lock().unwrap();
+        serde_json::json!({
+            "time": state.rtc.now().to_rfc3339(),
+            "app_state": state.app_state,
+            "app_setting": state.app_setting,
+            "connected_cameras": state.connected_cameras,
+            "camera_state": state.camera_state,
+            "camera_controls": state.camera_controls,
+            "camera_bin": state.camera_bin,
+            "mount": {
+                "ra": state.mount.ra,
+                "dec": state.mount.dec,
+                "tracking": state.mount.tracking,
+                "parked": state.mount.parked,
+                "slewing": state.mount.is_slewing(),
+                "pier_side": state.mount.pier_side,
+            },
+            "focuser": {
+                "position": state.focuser.position,
+                "temperature": state.focuser.temperature,
+            },
+            "pi": {
+                "temperature": state.pi.temperature,
+                "is_overtemp": state.pi.is_overtemp(),
+            },
+        })
+    }
+
+    /// Serve the control API over HTTP on localhost, for tests not written in Rust.
+    /// Listens on `port`, or on a free port when 0, and returns the port. Needs a started simulator
+    pub async fn start_control_server(&self, port: u16) -> Result<u16, Box<dyn std::error::Error + Send + Sync>> {
+        let shutdown_rx = self.shutdown_tx.as_ref().ok_or("simulator is not started")?.subscribe();
+        let listener = TcpListener::bind(("127.0.0.1", port)).await?;
+        let port = listener.local_addr()?.port();
+        tokio::spawn(control::run_control(self.clone(), listener, shutdown_rx));
+        Ok(port)
+    }
+
     pub fn shutdown(&self) {
         if let Some(tx) = &self.shutdown_tx {
             println!("Shutting down ASIAIR simulator...");
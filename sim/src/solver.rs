@@ -0,0 +1,119 @@
+use crate::mount::{Mount, PierSide};
+use crate::sim::{ASIAirState, CameraState, CAMERAS_INFO};
+use chrono::{DateTime, FixedOffset};
+use serde_json::{json, Value};
+use std::time::Duration;
+
+/// Reasons a plate solve can be forced to fail
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SolveFailure {
+    // Not enough stars detected in the image
+    NoStars,
+    // Stars were found but no match in the catalog before the timeout
+    Timeout,
+}
+
+impl SolveFailure {
+    pub fn as_str(&self) -> &str {
+        match self {
+            SolveFailure::NoStars => "no stars",
+            SolveFailure::Timeout => "timeout",
+        }
+    }
+}
+
+/// Behavior of the simulated plate solver
+#[derive(Debug, Clone)]
+pub struct SolverConfig {
+    // Time taken by a successful solve
+    pub latency: Duration,
+    // Time after which a solve that can't match the catalog gives up
+    pub timeout: Duration,
+    // Systematic error of the solution against the mount pointing, in arcseconds
+    pub ra_offset_arcsec: f64,
+    pub dec_offset_arcsec: f64,
+    // Standard deviation of the random error of the solution, in arcseconds
+    pub noise_arcsec: f64,
+    // Rotation of the camera in degrees, east of north, when the mount is west of the pier
+    pub camera_angle: f64,
+    // Number of stars detected in the image
+    pub star_number: u32,
+    // When set, every solve fails for that reason
+    pub failure: Option<SolveFailure>,
+}
+
+impl Default for SolverConfig {
+    fn default() -> Self {
+        SolverConfig {
+            latency: Duration::from_millis(1500),
+            timeout: Duration::from_secs(5),
+            ra_offset_arcsec: 0.0,
+            dec_offset_arcsec: 0.0,
+            noise_arcsec: 2.0,
+            camera_angle: 0.0,
+            star_number: 250,
+            failure: None,
+        }
+    }
+}
+
+// Normally distributed random value, using the Box-Muller transform
+pub fn gaussian(sigma: f64) -> f64 {
+    let u1: f64 = 1.0 - rand::random::<f64>();
+    let u2: f64 = rand::random::<f64>();
+    sigma * (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+}
+
+/// Size of the main camera images in binned pixels, and their scale in arcseconds per binned pixel
+pub fn image_geometry(state: &ASIAirState) -> Result<([u32; 2], f64), String> {
+    if let CameraState::Close = state.camera_state {
+        return Err("camera is not open".to_string());
+    }
+    let Some(camera_info) = CAMERAS_INFO.get(state.app_setting.main_camera_name.as_str()) else {
+        return Err("Unknown Camera".to_string());
+    };
+
+    let bin = state.camera_bin.max(1);
+    let chip_size = [camera_info.chip_size[0] / bin, camera_info.chip_size[1] / bin];
+    let scale = 206.265 * camera_info.pixel_size_um as f64 * bin as f64 / state.app_setting.focal_length;
+    Ok((chip_size, scale))
+}
+
+/// Solve the image taken at the current mount position.
+/// `chip_size` is in binned pixels and `scale` in arcseconds per binned pixel
+pub fn solve(
+    config: &SolverConfig,
+    mount: &Mount,
+    chip_size: [u32; 2],
+    scale: f64,
+    now: DateTime<FixedOffset>,
+) -> Result<Value, SolveFailure> {
+    if let Some(failure) = config.failure {
+        return Err(failure);
+    }
+    // Stars are trailed while slewing, and nothing is visible below the horizon
+    if mount.is_slewing() || mount.altitude(mount.ra, mount.dec, now) < 0.0 {
+        return Err(SolveFailure::NoStars);
+    }
+
+    let dec = (mount.dec + (config.dec_offset_arcsec + gaussian(config.noise_arcsec)) / 3600.0).clamp(-90.0, 90.0);
+    let ra_offset = (config.ra_offset_arcsec + gaussian(config.noise_arcsec)) / 3600.0 / dec.to_radians().cos().max(1e-6);
+    let ra = (mount.ra + ra_offset / 15.0).rem_euclid(24.0);
+
+    // The camera turns upside down with the telescope after a meridian flip
+    let flip = if mount.pier_side == PierSide::East { 180.0 } else { 0.0 };
+    let angle = (config.camera_angle + flip + 180.0).rem_euclid(360.0) - 180.0;
+
+    Ok(json!({
+        "ra": ra,
+        "dec": dec,
+        "angle": angle,
+        "scale": scale,
+        "fov": [
+            chip_size[0] as f64 * scale / 3600.0,
+            chip_size[1] as f64 * scale / 3600.0,
+        ],
+        "star_number": config.star_number,
+        "lapse_ms": config.latency.as_millis() as u64,
+    }))
+}
@@ -0,0 +1,218 @@
+// Stars kept from each frame for registration, brightest first
+const MAX_STARS: usize = 100;
+// Stars used to find the candidate offsets, and the distance in pixels under which two stars match
+const ANCHOR_STARS: usize = 15;
+const MATCH_DISTANCE: f64 = 2.0;
+// Frames with fewer stars, or fewer stars matching the reference, are dropped
+const MIN_STARS: usize = 8;
+const MIN_MATCHES: usize = 5;
+// Part of the reference star count a frame needs to be stacked
+const MIN_STAR_RATIO: f64 = 0.5;
+// Detection threshold in standard deviations of the background
+const DETECTION_SIGMA: f64 = 5.0;
+
+/// Star detected in an image, position in pixels and peak above the background in ADU
+#[derive(Debug, Clone, Copy)]
+pub struct DetectedStar {
+    pub x: f64,
+    pub y: f64,
+    pub peak: f64,
+}
+
+fn pixel(raw: &[u8], index: usize) -> f64 {
+    (i16::from_be_bytes([raw[index * 2], raw[index * 2 + 1]]) as i32 + 32768) as f64
+}
+
+/// Median and standard deviation of the background, estimated on a sample of the pixels
+fn background(raw: &[u8]) -> (f64, f64) {
+    let count = raw.len() / 2;
+    let stride = (count / 10000).max(1);
+    let mut sample: Vec<f64> = (0..count).step_by(stride).map(|i| pixel(raw, i)).collect();
+    sample.sort_by(f64::total_cmp);
+    let median = sample[sample.len() / 2];
+    let mut deviations: Vec<f64> = sample.iter().map(|v| (v - median).abs()).collect();
+    deviations.sort_by(f64::total_cmp);
+    (median, (deviations[deviations.len() / 2] * 1.4826).max(1.0))
+}
+
+/// Find the stars of a raw image, as local maxima above the background noise
+pub fn detect_stars(raw: &[u8], width: u32, height: u32) -> Vec<DetectedStar> {
+    let (width, height) = (width as usize, height as usize);
+    if raw.len() < width * height * 2 || width < 5 || height < 5 {
+        return Vec::new();
+    }
+    let (median, sigma) = background(raw);
+    let threshold = median + DETECTION_SIGMA * sigma;
+
+    let mut stars = Vec::new();
+    for y in 2..height - 2 {
+        for x in 2..width - 2 {
+            let value = pixel(raw, y * width + x);
+            if value < threshold {
+                continue;
+            }
+            let is_max = (y - 1..=y + 1).all(|ny| {
+                (x - 1..=x + 1).all(|nx| (nx, ny) == (x, y) || pixel(raw, ny * width + nx) < value)
+            });
+            if !is_max {
+                continue;
+            }
+
+            // Centroid of the 5x5 pixels around the maximum
+            let (mut sum, mut sum_x, mut sum_y) = (0.0, 0.0, 0.0);
+            for ny in y - 2..=y + 2 {
+                for nx in x - 2..=x + 2 {
+                    let weight = (pixel(raw, ny * width + nx) - median).max(0.0);
+                    sum += weight;
+                    sum_x += weight * (nx as f64 + 0.5);
+                    sum_y += weight * (ny as f64 + 0.5);
+                }
+            }
+            stars.push(DetectedStar {
+                x: sum_x / sum,
+                y: sum_y / sum,
+                peak: value - median,
+            });
+        }
+    }
+
+    stars.sort_by(|a, b| b.peak.total_cmp(&a.peak));
+    stars.truncate(MAX_STARS);
+    stars
+}
+
+/// Flux of a star above the background in ADU, counted within `radius` pixels of its center, and
+/// its half flux radius in pixels, the radius of the circle holding half of that flux
+pub fn measure_star(raw: &[u8], width: u32, height: u32, star: &DetectedStar, radius: f64) -> (f64, f64) {
+    let (median, _) = background(raw);
+    let mut fluxes = Vec::new();
+    let (min_y, max_y) = ((star.y - radius).floor().max(0.0) as usize, ((star.y + radius).ceil() as usize).min(height as usize));
+    let (min_x, max_x) = ((star.x - radius).floor().max(0.0) as usize, ((star.x + radius).ceil() as usize).min(width as usize));
+    for y in min_y..max_y {
+        for x in min_x..max_x {
+            let distance = (x as f64 + 0.5 - star.x).hypot(y as f64 + 0.5 - star.y);
+            if distance <= radius {
+                fluxes.push((distance, pixel(raw, y * width as usize + x) - median));
+            }
+        }
+    }
+    fluxes.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let total = fluxes.iter().map(|(_, flux)| flux).sum::<f64>();
+    let mut cumulated = 0.0;
+    for (distance, flux) in fluxes {
+        cumulated += flux;
+        if cumulated >= total / 2.0 {
+            return (total, distance);
+        }
+    }
+    (total, 0.0)
+}
+
+/// Offset to add to the positions of `stars` to bring them on `reference`, found by voting
+/// on the offsets between the brightest stars of both images
+pub fn register(reference: &[DetectedStar], stars: &[DetectedStar]) -> Option<(f64, f64)> {
+    let matches = |dx: f64, dy: f64| -> Vec<(f64, f64)> {
+        stars
+            .iter()
+            .filter_map(|s| {
+                reference
+                    .iter()
+                    .find(|r| (r.x - s.x - dx).hypot(r.y - s.y - dy) < MATCH_DISTANCE)
+                    .map(|r| (r.x - s.x, r.y - s.y))
+            })
+            .collect()
+    };
+
+    let mut best: Vec<(f64, f64)> = Vec::new();
+    for r in reference.iter().take(ANCHOR_STARS) {
+        for s in stars.iter().take(ANCHOR_STARS) {
+            let candidate = matches(r.x - s.x, r.y - s.y);
+            if candidate.len() > best.len() {
+                best = candidate;
+            }
+        }
+    }
+    if best.len() < MIN_MATCHES {
+        return None;
+    }
+
+    let count = best.len() as f64;
+    Some((best.iter().map(|m| m.0).sum::<f64>() / count, best.iter().map(|m| m.1).sum::<f64>() / count))
+}
+
+/// Average of the frames registered on the first one
+#[derive(Debug, Default)]
+pub struct Stacker {
+    width: u32,
+    height: u32,
+    reference: Vec<DetectedStar>,
+    sum: Vec<f32>,
+    count: Vec<u16>,
+}
+
+impl Stacker {
+    pub fn new() -> Self {
+        Stacker::default()
+    }
+
+    /// Register a frame on the stack and add it, returning the number of stars detected in it,
+    /// or why the frame was dropped
+    pub fn add(&mut self, raw: &[u8], width: u32, height: u32) -> Result<usize, (String, usize)> {
+        let stars = detect_stars(raw, width, height);
+        if stars.len() < MIN_STARS {
+            return Err(("not enough stars".to_string(), stars.len()));
+        }
+
+        let (dx, dy) = if self.reference.is_empty() {
+            self.width = width;
+            self.height = height;
+            self.reference = stars.clone();
+            self.sum = vec![0.0; (width * height) as usize];
+            self.count = vec![0; (width * height) as usize];
+            (0.0, 0.0)
+        } else {
+            if (width, height) != (self.width, self.height) {
+                return Err(("frame size changed".to_string(), stars.len()));
+            }
+            if (stars.len() as f64) < self.reference.len() as f64 * MIN_STAR_RATIO {
+                return Err(("not enough stars".to_string(), stars.len()));
+            }
+            register(&self.reference, &stars).ok_or_else(|| ("registration failed".to_string(), stars.len()))?
+        };
+
+        // Shift the frame by whole pixels onto the reference
+        let (dx, dy) = (dx.round() as i64, dy.round() as i64);
+        let (width, height) = (width as i64, height as i64);
+        for y in 0..height {
+            let ty = y + dy;
+            if !(0..height).contains(&ty) {
+                continue;
+            }
+            for x in 0..width {
+                let tx = x + dx;
+                if !(0..width).contains(&tx) {
+                    continue;
+                }
+                let target = (ty * width + tx) as usize;
+                self.sum[target] += pixel(raw, (y * width + x) as usize) as f32;
+                self.count[target] = self.count[target].saturating_add(1);
+            }
+        }
+
+        Ok(stars.len())
+    }
+
+    /// Average image in the raw format of the device, with its size, None until a frame is stacked
+    pub fn image(&self) -> Option<(Vec<u8>, u32, u32)> {
+        if self.reference.is_empty() {
+            return None;
+        }
+        let mut data = Vec::with_capacity(self.sum.len() * 2);
+        for (sum, count) in self.sum.iter().zip(&self.count) {
+            let value = if *count > 0 { (sum / *count as f32).round() as i32 } else { 0 };
+            data.extend_from_slice(&((value.clamp(0, 65535) - 32768) as i16).to_be_bytes());
+        }
+        Some((data, self.width, self.height))
+    }
+}
@@ -0,0 +1,292 @@
+use crate::frame::FrameParams;
+use crate::sim::FrameType;
+use chrono::{DateTime, FixedOffset};
+use serde_json::{json, Value};
+
+// Root folder of the images saved by the device
+pub const AUTOSAVE_DIR: &str = "Autosave";
+// Name of the internal storage, where the images are saved
+pub const INTERNAL_STORAGE: &str = "emmc";
+// Size of the internal storage, and the part taken by the system
+const INTERNAL_CAPACITY: u64 = 64 * 1024 * 1024 * 1024;
+const SYSTEM_SIZE: u64 = 6 * 1024 * 1024 * 1024;
+// Removable storages attached at startup
+const USB_CAPACITY: u64 = 32 * 1024 * 1024 * 1024;
+const SD_CAPACITY: u64 = 16 * 1024 * 1024 * 1024;
+// FITS header blocks written before the pixels
+const FITS_HEADER_SIZE: u64 = 2 * 2880;
+// Root folder of the recorded videos, and the size of their headers and index
+pub const VIDEO_DIR: &str = "Video";
+const VIDEO_HEADER_SIZE: u64 = 4096;
+
+/// Image or video saved on the device, the pixels are rendered again from the frame when downloaded,
+/// videos give their first frame
+#[derive(Debug, Clone)]
+pub struct StoredImage {
+    pub path: String,
+    pub size: u64,
+    // Frames in the file, more than one for videos
+    pub frame_count: u32,
+    pub target: String,
+    // Sensor temperature in degrees Celsius
+    pub temperature: i64,
+    // Start of the exposure
+    pub date_time: DateTime<FixedOffset>,
+    pub frame: FrameParams,
+}
+
+impl StoredImage {
+    pub fn name(&self) -> &str {
+        self.path.rsplit('/').next().unwrap_or(&self.path)
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "path": self.path,
+            "name": self.name(),
+            "size": self.size,
+            "frame_count": self.frame_count,
+            "frame_type": self.frame.frame_type.as_str(),
+            "target": self.target,
+            "exposure_us": self.frame.exposure_us,
+            "gain": self.frame.gain,
+            "bin": self.frame.bin,
+            "width": self.frame.width,
+            "height": self.frame.height,
+            "temperature": self.temperature,
+            "ra": self.frame.ra,
+            "dec": self.frame.dec,
+            "date_time": self.date_time.to_rfc3339(),
+        })
+    }
+}
+
+/// Internal or removable storage of the device, with the images it holds
+#[derive(Debug, Clone)]
+pub struct Volume {
+    pub name: String,
+    pub capacity: u64,
+    // Bytes not available for images
+    reserved: u64,
+    images: Vec<StoredImage>,
+}
+
+impl Volume {
+    pub fn new(name: &str, capacity: u64) -> Self {
+        Volume {
+            name: name.to_string(),
+            capacity,
+            reserved: 0,
+            images: Vec::new(),
+        }
+    }
+
+    /// Bytes taken by the system and the images
+    pub fn used(&self) -> u64 {
+        self.reserved + self.images.iter().map(|image| image.size).sum::<u64>()
+    }
+
+    pub fn free(&self) -> u64 {
+        self.capacity.saturating_sub(self.used())
+    }
+
+    /// Images in a folder and its subfolders, oldest first
+    pub fn list(&self, dir: &str) -> Vec<&StoredImage> {
+        self.images.iter().filter(|image| is_in(&image.path, dir)).collect()
+    }
+
+    pub fn get(&self, path: &str) -> Option<&StoredImage> {
+        self.images.iter().find(|image| image.path == path)
+    }
+
+    /// Copy an image at the same path, replacing an older copy
+    pub fn copy(&mut self, image: &StoredImage) -> Result<(), String> {
+        self.images.retain(|other| other.path != image.path);
+        if image.size > self.free() {
+            return Err("not enough space".to_string());
+        }
+        self.images.push(image.clone());
+        Ok(())
+    }
+
+    /// Delete an image, or a folder with all its images, returning the number of images deleted
+    pub fn delete(&mut self, path: &str) -> usize {
+        if path.trim_end_matches('/').is_empty() {
+            return 0;
+        }
+        let count = self.images.len();
+        self.images.retain(|image| !is_in(&image.path, path));
+        count - self.images.len()
+    }
+}
+
+/// Storages of the device, the internal one first. Frames are saved on the internal storage
+/// in the folder layout of the device
+#[derive(Debug, Clone)]
+pub struct Storage {
+    pub volumes: Vec<Volume>,
+    // Sequence number of the next image, in its file name
+    sequence: u32,
+}
+
+impl Default for Storage {
+    fn default() -> Self {
+        let mut internal = Volume::new(INTERNAL_STORAGE, INTERNAL_CAPACITY);
+        internal.reserved = SYSTEM_SIZE;
+        Storage {
+            volumes: vec![internal, Volume::new("usb", USB_CAPACITY), Volume::new("sd", SD_CAPACITY)],
+            sequence: 1,
+        }
+    }
+}
+
+// Keep the characters allowed in file names
+fn sanitize(name: &str) -> String {
+    let name: String = name
+        .trim()
+        .chars()
+        .map(|c| if c.is_alphanumeric() || "-_.".contains(c) { c } else { '_' })
+        .collect();
+    if name.is_empty() {
+        "Untitled".to_string()
+    } else {
+        name
+    }
+}
+
+// Bytes of the pixels of a frame
+fn frame_size(frame: &FrameParams) -> u64 {
+    frame.width as u64 * frame.height as u64 * 2
+}
+
+fn folder(frame_type: FrameType) -> &'static str {
+    match frame_type {
+        FrameType::Light => "Light",
+        FrameType::Dark => "Dark",
+        FrameType::Flat => "Flat",
+        FrameType::Bias => "Bias",
+    }
+}
+
+// True if `path` is the folder `dir` or inside it
+fn is_in(path: &str, dir: &str) -> bool {
+    let dir = dir.trim_end_matches('/');
+    dir.is_empty() || path == dir || path.strip_prefix(dir).is_some_and(|rest| rest.starts_with('/'))
+}
+
+impl Storage {
+    pub fn new() -> Self {
+        Storage::default()
+    }
+
+    pub fn internal(&self) -> &Volume {
+        &self.volumes[0]
+    }
+
+    pub fn internal_mut(&mut self) -> &mut Volume {
+        &mut self.volumes[0]
+    }
+
+    pub fn volume(&self, name: &str) -> Option<&Volume> {
+        self.volumes.iter().find(|volume| volume.name == name)
+    }
+
+    pub fn volume_mut(&mut self, name: &str) -> Option<&mut Volume> {
+        self.volumes.iter_mut().find(|volume| volume.name == name)
+    }
+
+    /// Plug an empty removable storage, replacing the one with the same name
+    pub fn attach(&mut self, name: &str, capacity: u64) {
+        self.detach(name);
+        self.volumes.push(Volume::new(name, capacity));
+    }
+
+    pub fn detach(&mut self, name: &str) {
+        if name != INTERNAL_STORAGE {
+            self.volumes.retain(|volume| volume.name != name);
+        }
+    }
+
+    /// Save a frame on the internal storage, lights go in a folder per target as `Autosave/Light/<target>/`
+    pub fn save(&mut self, frame: FrameParams, target: &str, temperature: i64, date_time: DateTime<FixedOffset>) -> Result<&StoredImage, String> {
+        let size = FITS_HEADER_SIZE + frame_size(&frame);
+        if size > self.internal().free() {
+            return Err("storage full".to_string());
+        }
+
+        let target = sanitize(target);
+        let kind = folder(frame.frame_type);
+        let dir = match frame.frame_type {
+            FrameType::Light => format!("{}/{}/{}", AUTOSAVE_DIR, kind, target),
+            _ => format!("{}/{}", AUTOSAVE_DIR, kind),
+        };
+        let name = format!(
+            "{}_{}_{:.1}s_Bin{}_gain{}_{}_{}C_{:04}.fit",
+            kind,
+            target,
+            frame.exposure_us as f64 / 1e6,
+            frame.bin,
+            frame.gain,
+            date_time.format("%Y%m%d-%H%M%S"),
+            temperature,
+            self.sequence,
+        );
+        self.sequence += 1;
+
+        let images = &mut self.internal_mut().images;
+        images.push(StoredImage {
+            path: format!("{}/{}", dir, name),
+            size,
+            frame_count: 1,
+            target,
+            temperature,
+            date_time,
+            frame,
+        });
+        Ok(images.last().unwrap())
+    }
+
+    /// Frames of the size of `frame` a video can hold before the internal storage is full
+    pub fn video_capacity(&self, frame: &FrameParams) -> u64 {
+        self.internal().free().saturating_sub(VIDEO_HEADER_SIZE) / frame_size(frame)
+    }
+
+    /// Save a video of `frame_count` frames like `frame` on the internal storage, as
+    /// `Video/<target>/` with the `format` extension
+    pub fn save_video(&mut self, frame: FrameParams, frame_count: u32, format: &str, target: &str, temperature: i64, date_time: DateTime<FixedOffset>) -> Result<&StoredImage, String> {
+        if frame_count as u64 > self.video_capacity(&frame) {
+            return Err("storage full".to_string());
+        }
+
+        let target = sanitize(target);
+        let name = format!(
+            "{}_{}_{:.1}ms_Bin{}_gain{}_{:04}.{}",
+            target,
+            date_time.format("%Y%m%d-%H%M%S"),
+            frame.exposure_us as f64 / 1e3,
+            frame.bin,
+            frame.gain,
+            self.sequence,
+            format,
+        );
+        self.sequence += 1;
+
+        let images = &mut self.internal_mut().images;
+        images.push(StoredImage {
+            path: format!("{}/{}/{}", VIDEO_DIR, target, name),
+            size: VIDEO_HEADER_SIZE + frame_count as u64 * frame_size(&frame),
+            frame_count,
+            target,
+            temperature,
+            date_time,
+            frame,
+        });
+        Ok(images.last().unwrap())
+    }
+
+    /// Delete every image of the internal storage and start the file numbers again
+    pub fn format(&mut self) {
+        self.internal_mut().images.clear();
+        self.sequence = 1;
+    }
+}